@@ -5,8 +5,9 @@
 //! Run with: cargo bench
 //! Run with profiling: cargo bench --bench parse_benchmark -- --profile-time=5
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use pprof::criterion::{Output, PProfProfiler};
+use std::hint::black_box;
 use std::io::Cursor;
 
 fn benchmark_read_null_record(c: &mut Criterion) {
@@ -121,7 +122,7 @@ fn benchmark_table_dump_v2(c: &mut Criterion) {
         body.extend_from_slice(&entry_count.to_be_bytes()); // entry_count
 
         for i in 0..entry_count {
-            body.extend_from_slice(&(i as u16).to_be_bytes()); // peer_index
+            body.extend_from_slice(&i.to_be_bytes()); // peer_index
             body.extend_from_slice(&[0x5F, 0x5E, 0x10, 0x00]); // originated_time
             body.extend_from_slice(&[0x00, 0x10]); // attr_len = 16
             body.extend_from_slice(&[0u8; 16]); // attributes
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reader that stops at a byte limit on a record boundary.
+
+use std::io::Read;
+
+use crate::{read_up_to, COMMON_HEADER_LEN};
+
+/// A reader that serves whole MRT records up to a total byte limit.
+///
+/// Unlike [`Read::take`], the limit is applied per record: a record is passed
+/// through only if all of it (common header plus `length` bytes) fits in what
+/// is left of the limit. Otherwise the reader reports a clean EOF at the
+/// preceding record boundary, so a [`read`](crate::read) loop ends with
+/// `Ok(None)` instead of a truncation error.
+///
+/// To find record boundaries the reader parses each common header as it goes.
+/// When a record is refused, its common header bytes have already been consumed
+/// from the wrapped reader.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::BufReader;
+/// use mrt_ingester::BoundedReader;
+///
+/// let file = std::fs::File::open("updates.mrt").unwrap();
+/// // Only the records within the first 64 MB
+/// let mut reader = BoundedReader::new(BufReader::new(file), 64 << 20);
+///
+/// while let Some((header, record)) = mrt_ingester::read(&mut reader).unwrap() {
+///     // Process record
+/// }
+/// ```
+pub struct BoundedReader<R> {
+    inner: R,
+    remaining: u64,
+    header: [u8; COMMON_HEADER_LEN],
+    header_len: usize,
+    header_pos: usize,
+    body_left: u64,
+    done: bool,
+}
+
+impl<R: Read> BoundedReader<R> {
+    /// Wrap a stream positioned at the start of a record, serving at most
+    /// `limit` bytes.
+    pub fn new(inner: R, limit: u64) -> Self {
+        BoundedReader {
+            inner,
+            remaining: limit,
+            header: [0; COMMON_HEADER_LEN],
+            header_len: 0,
+            header_pos: 0,
+            body_left: 0,
+            done: false,
+        }
+    }
+
+    /// Bytes of the limit not yet committed to a record.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Consume the reader, returning the wrapped stream.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read the next common header and decide whether its record fits.
+    ///
+    /// Returns `false` if the limit is reached.
+    fn next_record(&mut self) -> std::io::Result<bool> {
+        if self.done || self.remaining < COMMON_HEADER_LEN as u64 {
+            self.done = true;
+            return Ok(false);
+        }
+        let n = read_up_to(&mut self.inner, &mut self.header)?;
+        if n == COMMON_HEADER_LEN {
+            let length = u32::from_be_bytes(self.header[8..12].try_into().unwrap()) as u64;
+            let total = COMMON_HEADER_LEN as u64 + length;
+            if total > self.remaining {
+                self.done = true;
+                return Ok(false);
+            }
+            self.remaining -= total;
+            self.body_left = length;
+        } else {
+            // EOF, or a truncated header for the parser to report
+            self.remaining -= n as u64;
+        }
+        self.header_len = n;
+        self.header_pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for BoundedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.header_pos == self.header_len && self.body_left == 0 && !self.next_record()? {
+            return Ok(0);
+        }
+
+        if self.header_pos < self.header_len {
+            let pending = &self.header[self.header_pos..self.header_len];
+            let n = pending.len().min(buf.len());
+            buf[..n].copy_from_slice(&pending[..n]);
+            self.header_pos += n;
+            return Ok(n);
+        }
+
+        let len = buf.len().min(self.body_left.min(usize::MAX as u64) as usize);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.body_left -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read, write, Record};
+
+    #[test]
+    fn test_bounded_reader_stops_at_record_boundary() {
+        let mut data = Vec::new();
+        write(&mut data, 1, 0, &Record::START).unwrap();
+        write(&mut data, 2, 0, &Record::ISIS(vec![0xAB; 8])).unwrap();
+        write(&mut data, 3, 0, &Record::NULL).unwrap();
+
+        // The limit lands inside the second record
+        let mut reader = BoundedReader::new(data.as_slice(), 12 + 15);
+        assert_eq!(read(&mut reader).unwrap().unwrap().0.timestamp, 1);
+        assert!(read(&mut reader).unwrap().is_none());
+        assert!(read(&mut reader).unwrap().is_none());
+
+        // Exactly two records
+        let mut reader = BoundedReader::new(data.as_slice(), 12 + 20);
+        assert_eq!(read(&mut reader).unwrap().unwrap().0.timestamp, 1);
+        let (header, record) = read(&mut reader).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert_eq!(record, Record::ISIS(vec![0xAB; 8]));
+        assert!(read(&mut reader).unwrap().is_none());
+        assert_eq!(reader.remaining(), 0);
+
+        // A limit past the end reads everything
+        let mut reader = BoundedReader::new(data.as_slice(), 1000);
+        let mut count = 0;
+        while read(&mut reader).unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_bounded_reader_passes_truncation_through() {
+        let mut data = Vec::new();
+        write(&mut data, 1, 0, &Record::ISIS(vec![0xAB; 8])).unwrap();
+        data.truncate(15);
+
+        let mut reader = BoundedReader::new(data.as_slice(), 1000);
+        let err = read(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
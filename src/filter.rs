@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Record type filters parsed from names.
+
+use std::collections::BTreeSet;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+use crate::{record_types, type_name, Header};
+
+/// A set of MRT record types, parsed from a comma-separated list of names.
+///
+/// Names are those returned by [`type_name`], matched case-insensitively,
+/// so CLI tools can take `--types bgp4mp,table_dump_v2` and test each
+/// header against the result.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::RecordTypeFilter;
+///
+/// let filter: RecordTypeFilter = "bgp4mp,bgp4mp_et".parse().unwrap();
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// let mut body_buf = Vec::new();
+/// while let Some((header, body)) = mrt_ingester::read_split(&mut reader, &mut body_buf).unwrap() {
+///     if filter.matches(&header) {
+///         let record = mrt_ingester::parse_record(&header, body).unwrap();
+///         // Process BGP4MP record
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordTypeFilter {
+    types: BTreeSet<u16>,
+}
+
+impl RecordTypeFilter {
+    /// Returns `true` if the header's record type is in the set.
+    #[inline]
+    pub fn matches(&self, header: &Header) -> bool {
+        self.types.contains(&header.record_type)
+    }
+
+    /// Returns `true` if `record_type` is in the set.
+    #[inline]
+    pub fn contains(&self, record_type: u16) -> bool {
+        self.types.contains(&record_type)
+    }
+
+    /// The record type codes in the set, in ascending order.
+    pub fn record_types(&self) -> impl Iterator<Item = u16> + '_ {
+        self.types.iter().copied()
+    }
+}
+
+impl FromIterator<u16> for RecordTypeFilter {
+    /// Collect record type codes; unknown codes are kept as given.
+    fn from_iter<I: IntoIterator<Item = u16>>(iter: I) -> Self {
+        RecordTypeFilter {
+            types: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromStr for RecordTypeFilter {
+    type Err = Error;
+
+    /// Parse a comma-separated list of record type names.
+    ///
+    /// Surrounding whitespace is ignored. Fails with `InvalidInput`, listing
+    /// the valid names, on an empty or unknown name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut types = BTreeSet::new();
+        for name in s.split(',').map(str::trim) {
+            let record_type = record_types::ALL
+                .iter()
+                .copied()
+                .find(|&t| type_name(t).eq_ignore_ascii_case(name))
+                .ok_or_else(|| unknown_name(name))?;
+            types.insert(record_type);
+        }
+        Ok(RecordTypeFilter { types })
+    }
+}
+
+/// Error for a name that matches no record type, listing the valid ones.
+fn unknown_name(name: &str) -> Error {
+    let valid: Vec<&str> = record_types::ALL.iter().map(|&t| type_name(t)).collect();
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("unknown record type '{}'; valid names: {}", name, valid.join(", ")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_type_filter_from_str() {
+        let filter: RecordTypeFilter = "bgp4mp, TABLE_DUMP_V2,ospfv3_et".parse().unwrap();
+        assert_eq!(filter.record_types().collect::<Vec<_>>(), [13, 16, 49]);
+        assert!(filter.contains(16));
+        assert!(!filter.contains(17));
+
+        let header = Header {
+            timestamp: 0,
+            extended: 0,
+            record_type: 13,
+            sub_type: 2,
+            length: 0,
+        };
+        assert!(filter.matches(&header));
+
+        let from_codes: RecordTypeFilter = [49, 13, 16, 13].into_iter().collect();
+        assert_eq!(from_codes, filter);
+    }
+
+    #[test]
+    fn test_record_type_filter_rejects_unknown_names() {
+        let err = "bgp4mp,tabledump".parse::<RecordTypeFilter>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let message = err.to_string();
+        assert!(message.starts_with("unknown record type 'tabledump'; valid names: NULL, START"));
+        assert!(message.ends_with("OSPFv3, OSPFv3_ET"));
+
+        assert!("".parse::<RecordTypeFilter>().is_err());
+        assert!("bgp4mp,".parse::<RecordTypeFilter>().is_err());
+        assert!("unknown".parse::<RecordTypeFilter>().is_err());
+    }
+}
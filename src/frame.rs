@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Push-based record decoding for non-blocking streams.
+
+use std::io::{Error, ErrorKind};
+
+use crate::{parse_record, read_header, Header, Record, COMMON_HEADER_LEN};
+
+/// An incremental decoder that is fed bytes and yields complete records.
+///
+/// This is the push-based counterpart to [`read`](crate::read): instead of
+/// blocking on the stream until a record is complete, bytes are appended with
+/// [`extend_from_slice`](FrameDecoder::extend_from_slice) as they arrive, and
+/// [`decode`](FrameDecoder::decode) returns `Ok(None)` until a whole record is
+/// buffered. This suits event loops and codecs, e.g. mio or tokio, that
+/// cannot block on `read_exact`.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::Read;
+/// use mrt_ingester::FrameDecoder;
+///
+/// let mut socket = std::net::TcpStream::connect("127.0.0.1:5000").unwrap();
+/// let mut decoder = FrameDecoder::new();
+/// let mut chunk = [0u8; 4096];
+/// loop {
+///     let n = socket.read(&mut chunk).unwrap();
+///     if n == 0 {
+///         break;
+///     }
+///     decoder.extend_from_slice(&chunk[..n]);
+///     while let Some((header, record)) = decoder.decode().unwrap() {
+///         // Process record
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    pos: usize,
+    max_record_len: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            max_record_len: Self::DEFAULT_MAX_RECORD_LEN,
+        }
+    }
+}
+
+impl FrameDecoder {
+    /// Default limit on the body length of a record, 16 MiB.
+    pub const DEFAULT_MAX_RECORD_LEN: usize = 16 << 20;
+
+    /// Create a decoder with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest record body length, as given by the header, that
+    /// [`decode`](Self::decode) will wait to buffer.
+    pub fn with_max_record_len(mut self, max_record_len: usize) -> Self {
+        self.max_record_len = max_record_len;
+        self
+    }
+
+    /// Append bytes received from the stream.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        // Drop the records already decoded before growing the buffer
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Decode the next record if all of it is buffered.
+    ///
+    /// Returns `Ok(None)` if more bytes are needed. A record that fails to
+    /// parse is still consumed, so decoding can continue with the next one.
+    ///
+    /// A header whose length exceeds the maximum record length fails with
+    /// `InvalidData` without being consumed, since the stream cannot be
+    /// resynchronized past it; every later call fails the same way.
+    pub fn decode(&mut self) -> Result<Option<(Header, Record)>, Error> {
+        let pending = &self.buf[self.pos..];
+        if pending.len() < COMMON_HEADER_LEN {
+            return Ok(None);
+        }
+        let length = u32::from_be_bytes(pending[8..12].try_into().unwrap()) as usize;
+        let frame_len = match COMMON_HEADER_LEN.checked_add(length) {
+            Some(n) if length <= self.max_record_len => n,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "record length {} exceeds limit {}",
+                        length, self.max_record_len
+                    ),
+                ));
+            }
+        };
+        let Some(frame) = pending.get(..frame_len) else {
+            return Ok(None);
+        };
+        self.pos += frame.len();
+
+        let mut stream = frame;
+        let header = read_header(&mut stream)?.expect("frame holds a full common header");
+        let record = parse_record(&header, stream)?;
+        Ok(Some((header, record)))
+    }
+
+    /// Number of buffered bytes not yet decoded.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_frame_decoder_byte_at_a_time() {
+        let mut data = Vec::new();
+        write(&mut data, 1, 0, &Record::START).unwrap();
+        write(&mut data, 2, 0, &Record::ISIS(vec![0xAB; 8])).unwrap();
+
+        let mut decoder = FrameDecoder::new();
+        let mut records = Vec::new();
+        for &byte in &data {
+            decoder.extend_from_slice(&[byte]);
+            while let Some(record) = decoder.decode().unwrap() {
+                records.push(record);
+            }
+        }
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0.timestamp, 1);
+        assert_eq!(records[1].1, Record::ISIS(vec![0xAB; 8]));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn test_frame_decoder_skips_bad_record() {
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0xFF, 0xFF, // type: unknown
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x02, // length
+            0xAA, 0xBB, // body
+        ];
+        write(&mut data, 2, 0, &Record::NULL).unwrap();
+        data.extend_from_slice(&[0x00, 0x00, 0x00]);
+
+        let mut decoder = FrameDecoder::new();
+        decoder.extend_from_slice(&data);
+        let err = decoder.decode().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let (header, record) = decoder.decode().unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert_eq!(record, Record::NULL);
+        // A partial header waits for more data
+        assert!(decoder.decode().unwrap().is_none());
+        assert_eq!(decoder.buffered(), 3);
+    }
+
+    #[test]
+    fn test_frame_decoder_max_record_len() {
+        let mut data = Vec::new();
+        write(&mut data, 1, 0, &Record::ISIS(vec![0xAB; 8])).unwrap();
+
+        let mut decoder = FrameDecoder::new().with_max_record_len(4);
+        decoder.extend_from_slice(&data[..COMMON_HEADER_LEN]);
+        let err = decoder.decode().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("exceeds limit"));
+        // The oversized record is not skipped
+        decoder.extend_from_slice(&data[COMMON_HEADER_LEN..]);
+        assert!(decoder.decode().is_err());
+
+        let mut decoder = FrameDecoder::new().with_max_record_len(8);
+        decoder.extend_from_slice(&data);
+        assert!(decoder.decode().unwrap().is_some());
+
+        // A length near u32::MAX is rejected by the default limit
+        let mut decoder = FrameDecoder::new();
+        decoder.extend_from_slice(&[0, 0, 0, 1, 0, 0x20, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(decoder.decode().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
@@ -45,12 +45,42 @@
 //!     // Process record
 //! }
 //! ```
+//!
+//! ## Safety
+//!
+//! The crate contains no `unsafe` code; record bodies are read into
+//! initialized buffers. This is enforced with `#![forbid(unsafe_code)]`.
 
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind, Read};
+#![forbid(unsafe_code)]
+
+use std::io::{Error, ErrorKind, Read, Write};
 
 pub mod records;
 pub mod readahead;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "test-util")]
+pub mod testgen;
+mod bounded;
+mod filter;
+mod frame;
+mod offset;
+mod prefix;
+mod reader;
+mod sequence;
+mod snapshot;
+mod stats;
+
+pub use address::{read_afi, read_ip_by_afi, write_afi, write_ip, write_ip_by_afi};
+pub use bounded::BoundedReader;
+pub use filter::RecordTypeFilter;
+pub use frame::FrameDecoder;
+pub use offset::{OffsetError, OffsetReader};
+pub use prefix::Prefix;
+pub use reader::RecordReader;
+pub use sequence::{SequenceChecker, SequenceError};
+pub use snapshot::{RibDiff, RibSnapshot, RouteKey};
+pub use stats::{StatLine, Stats};
 
 // Re-export record modules at crate root for API compatibility
 pub use records::bgp;
@@ -60,11 +90,13 @@ pub use records::isis;
 pub use records::ospf;
 pub use records::rip;
 pub use records::tabledump;
+pub use records::HasPeer;
 
 /// Address Family Identifier (AFI) as defined in RFC 4760.
 ///
 /// Used to distinguish between IPv4 and IPv6 address families in MRT records.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(u16)]
 pub enum AFI {
     /// IPv4 address family (AFI = 1)
@@ -86,9 +118,18 @@ impl AFI {
         }
     }
 
+    /// The AFI value as written on the wire (the inverse of
+    /// [`from_u16`](Self::from_u16)).
+    #[inline]
+    pub fn to_u16(&self) -> u16 {
+        *self as u16
+    }
+
     /// Parse an AFI value from a 16-bit integer.
+    ///
+    /// Returns an `InvalidData` error for families other than IPv4 and IPv6.
     #[inline]
-    pub(crate) fn from_u16(value: u16) -> std::io::Result<Self> {
+    pub fn from_u16(value: u16) -> std::io::Result<Self> {
         match value {
             1 => Ok(AFI::IPV4),
             2 => Ok(AFI::IPV6),
@@ -97,11 +138,31 @@ impl AFI {
     }
 }
 
+/// Convert an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) to plain IPv4.
+///
+/// Some collectors write IPv4 peers into BGP4MP records with AFI IPv6 and a
+/// mapped address. Parsing keeps the wire form; apply this to peer and local
+/// addresses to treat such peers as IPv4. Other addresses are returned as is.
+///
+/// # Example
+///
+/// ```
+/// use std::net::IpAddr;
+///
+/// let mapped: IpAddr = "::ffff:192.0.2.1".parse().unwrap();
+/// assert_eq!(mrt_ingester::normalize_v4_mapped(mapped).to_string(), "192.0.2.1");
+/// ```
+#[inline]
+pub fn normalize_v4_mapped(addr: std::net::IpAddr) -> std::net::IpAddr {
+    addr.to_canonical()
+}
+
 /// MRT record header that precedes every record.
 ///
 /// The header contains metadata about the record including timestamp,
 /// type information, and payload length.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Header {
     /// UNIX timestamp (seconds since epoch)
     pub timestamp: u32,
@@ -115,12 +176,92 @@ pub struct Header {
     pub length: u32,
 }
 
+/// Length of the MRT common header: timestamp, type, subtype and length.
+pub const COMMON_HEADER_LEN: usize = 12;
+
+/// Length of the microsecond timestamp that follows the common header in
+/// *_ET records. It is counted in [`Header::length`] but is not part of the
+/// record body.
+pub const EXTENDED_TIMESTAMP_LEN: usize = 4;
+
+impl Header {
+    /// Microseconds past `timestamp`, or 0 for types without an extended timestamp.
+    ///
+    /// Out-of-range values (1,000,000 or more) are clamped so they never
+    /// carry over into whole seconds.
+    #[inline]
+    fn micros(&self) -> u32 {
+        if is_extended_type(self.record_type) {
+            self.extended.min(999_999)
+        } else {
+            0
+        }
+    }
+
+    /// Length of the header on the wire: 12 bytes, or 16 for *_ET types
+    /// with their microsecond timestamp.
+    #[inline]
+    pub fn wire_len(&self) -> usize {
+        header_wire_len(self.record_type)
+    }
+
+    /// Length of the whole record on the wire, header included.
+    ///
+    /// This is the distance from the start of this record to the next one.
+    #[inline]
+    pub fn total_len(&self) -> u64 {
+        COMMON_HEADER_LEN as u64 + self.length as u64
+    }
+
+    /// The record time as a [`SystemTime`](std::time::SystemTime).
+    ///
+    /// For *_ET types the microseconds are added as the fractional part of
+    /// the second; for other types `extended` is ignored.
+    pub fn datetime(&self) -> std::time::SystemTime {
+        let since_epoch = std::time::Duration::new(self.timestamp as u64, self.micros() * 1000);
+        std::time::UNIX_EPOCH + since_epoch
+    }
+
+    /// The record time as a `chrono::DateTime<Utc>` (requires the `chrono` feature).
+    ///
+    /// Same semantics as [`datetime`](Self::datetime).
+    #[cfg(feature = "chrono")]
+    pub fn chrono(&self) -> chrono::DateTime<chrono::Utc> {
+        // Any u32 timestamp is within chrono's range
+        chrono::DateTime::from_timestamp(self.timestamp as i64, self.micros() * 1000)
+            .expect("u32 timestamp out of chrono range")
+    }
+}
+
+/// The exact on-wire bytes of an MRT record.
+///
+/// Returned by [`read_raw`] so filter tools can copy matching records
+/// verbatim (`out.write_all(&raw.bytes)`) without re-encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawRecord {
+    /// Parsed record header
+    pub header: Header,
+    /// Complete record: 12-byte common header, the 4-byte extended
+    /// timestamp for *_ET types, and the body
+    pub bytes: Vec<u8>,
+}
+
+impl RawRecord {
+    /// The record body (everything after the common header and extended timestamp).
+    pub fn body(&self) -> &[u8] {
+        &self.bytes[body_offset(&self.header)..]
+    }
+}
+
 /// Fully-parsed MRT record.
 ///
 /// Each variant corresponds to a specific MRT record type as defined in RFC 6396.
-#[derive(Debug)]
+/// More may be added, so matches outside this crate need a wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(missing_docs)]
 #[allow(non_camel_case_types)]
+#[non_exhaustive]
 pub enum Record {
     /// Null record (type 0)
     NULL,
@@ -162,6 +303,126 @@ pub enum Record {
     OSPFv3(records::ospf::OSPFv3),
     /// OSPFv3 with extended timestamp (type 49)
     OSPFv3_ET(records::ospf::OSPFv3),
+    /// Record of a type this crate does not decode, kept as the raw body.
+    /// Only produced when [`ReadOptions::skip_unknown_types`] is set.
+    Unknown {
+        raw_type: u16,
+        sub_type: u16,
+        body: Vec<u8>,
+    },
+}
+
+impl Record {
+    /// The MRT record type of this record.
+    pub fn record_type(&self) -> u16 {
+        use record_types::*;
+
+        match self {
+            Record::NULL => NULL,
+            Record::START => START,
+            Record::DIE => DIE,
+            Record::I_AM_DEAD => I_AM_DEAD,
+            Record::PEER_DOWN => PEER_DOWN,
+            Record::BGP(_) => BGP,
+            Record::RIP(_) => RIP,
+            Record::IDRP => IDRP,
+            Record::RIPNG(_) => RIPNG,
+            Record::BGP4PLUS(_) => BGP4PLUS,
+            Record::BGP4PLUS_01(_) => BGP4PLUS_01,
+            Record::OSPFv2(_) => OSPFV2,
+            Record::TABLE_DUMP(_) => TABLE_DUMP,
+            Record::TABLE_DUMP_V2(_) => TABLE_DUMP_V2,
+            Record::BGP4MP(_) => BGP4MP,
+            Record::BGP4MP_ET(_) => BGP4MP_ET,
+            Record::ISIS(_) => ISIS,
+            Record::ISIS_ET(_) => ISIS_ET,
+            Record::OSPFv3(_) => OSPFV3,
+            Record::OSPFv3_ET(_) => OSPFV3_ET,
+            Record::Unknown { raw_type, .. } => *raw_type,
+        }
+    }
+
+    /// Name of this record's type, e.g. `"TABLE_DUMP_V2"`.
+    ///
+    /// See [`type_name`].
+    pub fn type_name(&self) -> &'static str {
+        type_name(self.record_type())
+    }
+
+    /// The MRT subtype of this record.
+    pub fn sub_type(&self) -> u16 {
+        match self {
+            Record::BGP(x) => x.sub_type(),
+            Record::BGP4PLUS(x) | Record::BGP4PLUS_01(x) => x.sub_type(),
+            Record::TABLE_DUMP(x) => x.sub_type(),
+            Record::TABLE_DUMP_V2(x) => x.sub_type(),
+            Record::BGP4MP(x) | Record::BGP4MP_ET(x) => x.sub_type(),
+            Record::Unknown { sub_type, .. } => *sub_type,
+            _ => 0,
+        }
+    }
+
+    /// Encode the record body in wire format.
+    ///
+    /// This is the inverse of parsing: the result excludes the common header
+    /// and the extended timestamp of *_ET types. Use [`write`] to emit a
+    /// complete record.
+    ///
+    /// Fails with `InvalidInput` if the record cannot be represented on the
+    /// wire, e.g. mismatched peer/local address families or a field longer
+    /// than its length prefix allows.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut body = Vec::new();
+        self.write_body(&mut body)?;
+        Ok(body)
+    }
+
+    /// Number of bytes [`write`] emits for this record, header included.
+    ///
+    /// Computed by encoding into a byte counter, so nothing is allocated.
+    /// Fails in the same cases as [`encode`](Self::encode).
+    pub fn wire_len(&self) -> Result<usize, Error> {
+        let mut counter = ByteCounter(0);
+        self.write_body(&mut counter)?;
+        Ok(header_wire_len(self.record_type()) + counter.0)
+    }
+
+    fn write_body(&self, body: &mut impl Write) -> Result<(), Error> {
+        match self {
+            Record::NULL
+            | Record::START
+            | Record::DIE
+            | Record::I_AM_DEAD
+            | Record::PEER_DOWN
+            | Record::IDRP => {}
+            Record::BGP(x) => x.write(body)?,
+            Record::RIP(x) => x.write(body)?,
+            Record::RIPNG(x) => x.write(body)?,
+            Record::BGP4PLUS(x) | Record::BGP4PLUS_01(x) => x.write(body)?,
+            Record::OSPFv2(x) => x.write(body)?,
+            Record::TABLE_DUMP(x) => x.write(body)?,
+            Record::TABLE_DUMP_V2(x) => x.write(body)?,
+            Record::BGP4MP(x) | Record::BGP4MP_ET(x) => x.write(body)?,
+            Record::ISIS(pdu) | Record::ISIS_ET(pdu) => body.write_all(pdu)?,
+            Record::OSPFv3(x) | Record::OSPFv3_ET(x) => x.write(body)?,
+            Record::Unknown { body: raw, .. } => body.write_all(raw)?,
+        }
+        Ok(())
+    }
+}
+
+/// Writer that discards its input, counting the bytes.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Record type constants
@@ -186,17 +447,343 @@ mod record_types {
     pub const ISIS_ET: u16 = 33;
     pub const OSPFV3: u16 = 48;
     pub const OSPFV3_ET: u16 = 49;
+
+    /// Every type above, in ascending order.
+    pub const ALL: [u16; 20] = [
+        NULL,
+        START,
+        DIE,
+        I_AM_DEAD,
+        PEER_DOWN,
+        BGP,
+        RIP,
+        IDRP,
+        RIPNG,
+        BGP4PLUS,
+        BGP4PLUS_01,
+        OSPFV2,
+        TABLE_DUMP,
+        TABLE_DUMP_V2,
+        BGP4MP,
+        BGP4MP_ET,
+        ISIS,
+        ISIS_ET,
+        OSPFV3,
+        OSPFV3_ET,
+    ];
+}
+
+/// Name of an MRT record type, as used in RFC 6396.
+///
+/// Returns `"UNKNOWN"` for unassigned type codes.
+pub fn type_name(record_type: u16) -> &'static str {
+    known_type_name(record_type).unwrap_or("UNKNOWN")
+}
+
+/// Name of an assigned MRT record type, or `None`.
+fn known_type_name(record_type: u16) -> Option<&'static str> {
+    use record_types::*;
+
+    Some(match record_type {
+        NULL => "NULL",
+        START => "START",
+        DIE => "DIE",
+        I_AM_DEAD => "I_AM_DEAD",
+        PEER_DOWN => "PEER_DOWN",
+        BGP => "BGP",
+        RIP => "RIP",
+        IDRP => "IDRP",
+        RIPNG => "RIPNG",
+        BGP4PLUS => "BGP4PLUS",
+        BGP4PLUS_01 => "BGP4PLUS_01",
+        OSPFV2 => "OSPFv2",
+        TABLE_DUMP => "TABLE_DUMP",
+        TABLE_DUMP_V2 => "TABLE_DUMP_V2",
+        BGP4MP => "BGP4MP",
+        BGP4MP_ET => "BGP4MP_ET",
+        ISIS => "ISIS",
+        ISIS_ET => "ISIS_ET",
+        OSPFV3 => "OSPFv3",
+        OSPFV3_ET => "OSPFv3_ET",
+        _ => return None,
+    })
+}
+
+/// Name of an MRT subtype within a record type, as used in RFC 6396.
+///
+/// Returns `"UNKNOWN"` for unknown combinations and for record types that do
+/// not define subtypes.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(mrt_ingester::subtype_name(16, 4), "MESSAGE_AS4");
+/// assert_eq!(mrt_ingester::subtype_name(13, 4), "RIB_IPV6_UNICAST");
+/// ```
+pub fn subtype_name(record_type: u16, sub_type: u16) -> &'static str {
+    known_subtype_name(record_type, sub_type).unwrap_or("UNKNOWN")
+}
+
+/// Name of a subtype defined for `record_type`, or `None`.
+fn known_subtype_name(record_type: u16, sub_type: u16) -> Option<&'static str> {
+    match record_type {
+        record_types::BGP => bgp::subtype_name(sub_type),
+        record_types::BGP4PLUS | record_types::BGP4PLUS_01 => bgp4plus::subtype_name(sub_type),
+        record_types::TABLE_DUMP => match sub_type {
+            1 => Some("AFI_IPv4"),
+            2 => Some("AFI_IPv6"),
+            _ => None,
+        },
+        record_types::TABLE_DUMP_V2 => tabledump::subtype_name(sub_type),
+        record_types::BGP4MP | record_types::BGP4MP_ET => bgp4mp::subtype_name(sub_type),
+        _ => None,
+    }
+}
+
+/// Header length of a record type, including the extended timestamp.
+#[inline]
+fn header_wire_len(record_type: u16) -> usize {
+    if is_extended_type(record_type) {
+        COMMON_HEADER_LEN + EXTENDED_TIMESTAMP_LEN
+    } else {
+        COMMON_HEADER_LEN
+    }
 }
 
 /// Check if a record type uses extended timestamp format.
 #[inline]
-fn is_extended_type(record_type: u16) -> bool {
+pub(crate) fn is_extended_type(record_type: u16) -> bool {
     matches!(
         record_type,
         record_types::BGP4MP_ET | record_types::ISIS_ET | record_types::OSPFV3_ET
     )
 }
 
+/// Offset of the record body from the start of the record.
+#[inline]
+fn body_offset(header: &Header) -> usize {
+    header.wire_len()
+}
+
+/// Length of the record body that follows the common header.
+///
+/// For *_ET types the header `length` also covers the 4-byte microsecond
+/// timestamp, which is not part of the body. This is the single place that
+/// adjustment is made; readers and record parsers must not re-derive it.
+#[inline]
+pub(crate) fn body_length(header: &Header) -> u32 {
+    if is_extended_type(header.record_type) {
+        header.length.saturating_sub(EXTENDED_TIMESTAMP_LEN as u32)
+    } else {
+        header.length
+    }
+}
+
+/// Error payload for a record cut off by the end of the stream.
+///
+/// Returned inside an `io::Error` of kind `UnexpectedEof` by [`read`],
+/// [`read_with_buffer`] and [`read_raw`] when the stream ends part-way
+/// through a record, as happens with interrupted transfers. A stream that
+/// ends exactly at a record boundary yields `Ok(None)` instead.
+///
+/// ```no_run
+/// # let mut stream: &[u8] = &[];
+/// match mrt_ingester::read(&mut stream) {
+///     Err(e) => match e.get_ref().and_then(|e| e.downcast_ref::<mrt_ingester::Truncated>()) {
+///         Some(t) => eprintln!("file cut off: {} of {} bytes", t.available, t.expected),
+///         None => eprintln!("read error: {}", e),
+///     },
+///     Ok(_) => {}
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncated {
+    /// Bytes the record needs: 12 if the common header itself is cut off,
+    /// otherwise the full record size (header, extended timestamp and body)
+    pub expected: u64,
+    /// Bytes of the record actually present in the stream
+    pub available: u64,
+}
+
+impl std::fmt::Display for Truncated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "truncated record: expected {} bytes, only {} available",
+            self.expected, self.available
+        )
+    }
+}
+
+impl std::error::Error for Truncated {}
+
+impl Truncated {
+    fn into_error(self) -> Error {
+        Error::new(ErrorKind::UnexpectedEof, self)
+    }
+}
+
+/// A recoverable anomaly in a record that otherwise parsed, collected by
+/// [`read_with_warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ParseWarning {
+    /// The decoder stopped before the end of the record body.
+    TrailingBytes {
+        /// MRT record type
+        record_type: u16,
+        /// MRT record subtype
+        sub_type: u16,
+        /// Body bytes left unread
+        unread: usize,
+    },
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::TrailingBytes {
+                record_type,
+                sub_type,
+                unread,
+            } => write!(
+                f,
+                "{} trailing bytes in {}/{} record body not consumed",
+                unread,
+                type_name(*record_type),
+                subtype_name(*record_type, *sub_type)
+            ),
+        }
+    }
+}
+
+/// Read until `buf` is full or EOF, returning the number of bytes read.
+pub(crate) fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Read the common header.
+///
+/// Returns `Ok(false)` on EOF before the first byte and a [`Truncated`]
+/// error on EOF inside the header.
+#[inline]
+fn read_common_header(
+    stream: &mut impl Read,
+    header_buf: &mut [u8; COMMON_HEADER_LEN],
+) -> Result<bool, Error> {
+    match read_up_to(stream, header_buf)? {
+        0 => Ok(false),
+        COMMON_HEADER_LEN => Ok(true),
+        n => Err(Truncated {
+            expected: COMMON_HEADER_LEN as u64,
+            available: n as u64,
+        }
+        .into_error()),
+    }
+}
+
+/// Fill `buf` with the next part of a record, which starts `offset` bytes
+/// into a record of `length` bytes after the common header.
+#[inline]
+fn read_record_part(
+    stream: &mut impl Read,
+    buf: &mut [u8],
+    offset: usize,
+    length: u32,
+) -> Result<(), Error> {
+    let n = read_up_to(stream, buf)?;
+    if n < buf.len() {
+        return Err(Truncated {
+            expected: COMMON_HEADER_LEN as u64 + length as u64,
+            available: (offset + n) as u64,
+        }
+        .into_error());
+    }
+    Ok(())
+}
+
+/// Options controlling how record bodies are parsed.
+///
+/// Passed to [`read_with_options`], [`parse_record_with_options`] and
+/// [`RecordReader::with_options`]; the other read functions use the
+/// defaults, which accept anything that is well formed. New options are
+/// added as fields here, so construct with `..Default::default()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Maximum number of peers in a PEER_INDEX_TABLE or entries in a
+    /// TABLE_DUMP_V2 RIB record (default unlimited). Records declaring more
+    /// fail with `InvalidData`.
+    pub max_entries: usize,
+    /// Return records of unknown type as [`Record::Unknown`] instead of
+    /// failing with `InvalidData` (default `false`). The body is skipped
+    /// using the header length, so the stream stays in sync.
+    pub skip_unknown_types: bool,
+    /// Fail with `InvalidData` when a decoder leaves body bytes unread
+    /// (default `false`). Such trailing bytes are otherwise reported as a
+    /// [`ParseWarning`] by [`read_with_warnings`] and ignored elsewhere.
+    pub strict: bool,
+    /// Fail with `InvalidData` when a BGP message in a BGP4MP MESSAGE record
+    /// has a marker that is not all ones (default `false`). The message bytes
+    /// are split into PDUs, so misframed ones fail too. This also rejects the
+    /// RFC 1771 authentication markers found in some old dumps.
+    pub strict_marker: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            max_entries: usize::MAX,
+            skip_unknown_types: false,
+            strict: false,
+            strict_marker: false,
+        }
+    }
+}
+
+/// Largest body allocated up front. Longer bodies grow as data arrives, so
+/// a corrupt length field cannot force a multi-gigabyte allocation.
+const MAX_BODY_PREALLOC: usize = 1 << 20;
+
+/// Read the body of `header` into `buf`, replacing its contents.
+#[inline]
+fn read_body_into(stream: &mut impl Read, buf: &mut Vec<u8>, header: &Header) -> Result<(), Error> {
+    let body_len = body_length(header) as usize;
+    buf.clear();
+    buf.reserve(body_len.min(MAX_BODY_PREALLOC));
+    let n = stream.take(body_len as u64).read_to_end(buf)?;
+    if n < body_len {
+        return Err(Truncated {
+            expected: header.total_len(),
+            available: (body_offset(header) + n) as u64,
+        }
+        .into_error());
+    }
+    Ok(())
+}
+
+/// Read exactly `len` bytes into a new buffer.
+///
+/// `len` comes from untrusted input, so at most [`MAX_BODY_PREALLOC`] bytes
+/// are reserved up front; a truncated stream fails with `UnexpectedEof`
+/// instead of first allocating the full claimed length.
+pub(crate) fn read_bounded(stream: &mut impl Read, len: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(len.min(MAX_BODY_PREALLOC));
+    stream.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"));
+    }
+    Ok(buf)
+}
+
 /// Reads the next MRT record from the stream.
 ///
 /// # Returns
@@ -226,47 +813,15 @@ fn is_extended_type(record_type: u16) -> bool {
 /// ```
 #[inline]
 pub fn read(stream: &mut impl Read) -> Result<Option<(Header, Record)>, Error> {
-    // Read entire common header (12 bytes) in one syscall
-    let mut header_buf = [0u8; 12];
-    match stream.read_exact(&mut header_buf) {
-        Ok(()) => {}
-        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
-        Err(e) => return Err(e),
-    }
-
-    // Parse header fields from buffer (big-endian)
-    let timestamp = u32::from_be_bytes([header_buf[0], header_buf[1], header_buf[2], header_buf[3]]);
-    let record_type = u16::from_be_bytes([header_buf[4], header_buf[5]]);
-    let sub_type = u16::from_be_bytes([header_buf[6], header_buf[7]]);
-    let length = u32::from_be_bytes([header_buf[8], header_buf[9], header_buf[10], header_buf[11]]);
-
-    // Handle extended timestamp for *_ET types
-    let (extended, body_length) = if is_extended_type(record_type) {
-        let microseconds = stream.read_u32::<BigEndian>()?;
-        (microseconds, length.saturating_sub(4))
-    } else {
-        (0, length)
-    };
-
-    let header = Header {
-        timestamp,
-        extended,
-        record_type,
-        sub_type,
-        length,
-    };
-
     // Read body into buffer and parse from Cursor (faster than stream-direct for BufReader)
-    let body_len = body_length as usize;
-    let mut body_buf = Vec::with_capacity(body_len);
-    // SAFETY: We immediately read_exact into this buffer
-    unsafe {
-        body_buf.set_len(body_len);
-    }
-    stream.read_exact(&mut body_buf)?;
+    let mut body_buf = Vec::new();
+    let header = match read_frame(stream, &mut body_buf)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
 
     // Parse record based on type
-    let record = parse_record(&header, &body_buf)?;
+    let record = parse_body(&header, &body_buf, &ReadOptions::default(), None)?;
 
     Ok(Some((header, record)))
 }
@@ -307,116 +862,1023 @@ pub fn read_with_buffer(
     stream: &mut impl Read,
     body_buf: &mut Vec<u8>,
 ) -> Result<Option<(Header, Record)>, Error> {
-    // Read entire common header (12 bytes) in one syscall
-    let mut header_buf = [0u8; 12];
-    match stream.read_exact(&mut header_buf) {
-        Ok(()) => {}
-        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
-        Err(e) => return Err(e),
-    }
-
-    // Parse header fields from buffer (big-endian) - using array indexing is faster than from_be_bytes
-    let timestamp = u32::from_be_bytes([header_buf[0], header_buf[1], header_buf[2], header_buf[3]]);
-    let record_type = u16::from_be_bytes([header_buf[4], header_buf[5]]);
-    let sub_type = u16::from_be_bytes([header_buf[6], header_buf[7]]);
-    let length = u32::from_be_bytes([header_buf[8], header_buf[9], header_buf[10], header_buf[11]]);
-
-    // Handle extended timestamp for *_ET types
-    let (extended, body_length) = if is_extended_type(record_type) {
-        let microseconds = stream.read_u32::<BigEndian>()?;
-        (microseconds, length.saturating_sub(4))
-    } else {
-        (0, length)
-    };
-
-    let header = Header {
-        timestamp,
-        extended,
-        record_type,
-        sub_type,
-        length,
-    };
-
-    // Resize buffer and read body (reuses existing capacity when possible)
-    let body_len = body_length as usize;
+    read_with_options(stream, body_buf, &ReadOptions::default())
+}
 
-    // Fast path: if buffer already has enough capacity, just set length
-    if body_buf.capacity() >= body_len {
-        // SAFETY: We're about to read_exact into this buffer, capacity is sufficient
-        unsafe {
-            body_buf.set_len(body_len);
-        }
-    } else {
-        // Need to grow - use resize which handles allocation efficiently
-        body_buf.clear();
-        body_buf.reserve(body_len);
-        unsafe {
-            body_buf.set_len(body_len);
+/// Reads at most `n` records, stopping early at EOF.
+///
+/// Convenient for sampling the start of a file. A single body buffer is
+/// reused across records, as with [`read_with_buffer`].
+///
+/// # Example
+///
+/// ```no_run
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// for (header, record) in mrt_ingester::read_n(&mut reader, 10).unwrap() {
+///     println!("{} {:?}", header.timestamp, record);
+/// }
+/// ```
+pub fn read_n(stream: &mut impl Read, n: usize) -> Result<Vec<(Header, Record)>, Error> {
+    // Cap the up-front allocation; `n` may be a large "everything" value
+    let mut records = Vec::with_capacity(n.min(1024));
+    let mut body_buf = Vec::new();
+    while records.len() < n {
+        match read_with_buffer(stream, &mut body_buf)? {
+            Some(record) => records.push(record),
+            None => break,
         }
     }
-    stream.read_exact(body_buf)?;
-
-    // Parse record based on type
-    let record = parse_record(&header, body_buf)?;
-
-    Ok(Some((header, record)))
+    Ok(records)
 }
 
-/// Reads only the MRT header from the stream, skipping the body.
+/// Like [`read_with_buffer`], with explicit parsing limits.
 ///
-/// This is useful for scanning/filtering files without full parsing overhead.
+/// # Example
 ///
-/// # Returns
+/// ```no_run
+/// use mrt_ingester::ReadOptions;
 ///
-/// - `Ok(None)` - EOF reached at the beginning of a record
-/// - `Ok(Some(header))` - Successfully read header, body bytes skipped
-/// - `Err(e)` - I/O error
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("rib.mrt").unwrap());
+/// let options = ReadOptions {
+///     max_entries: 4096,
+///     skip_unknown_types: true,
+///     ..Default::default()
+/// };
+/// let mut body_buf = Vec::new();
+/// while let Some((header, record)) =
+///     mrt_ingester::read_with_options(&mut reader, &mut body_buf, &options).unwrap()
+/// {
+///     // RIB records with more than 4096 entries fail with InvalidData, and
+///     // records of unknown type come back as Record::Unknown
+/// }
+/// ```
 #[inline]
-pub fn read_header_only(stream: &mut (impl Read + std::io::Seek)) -> Result<Option<Header>, Error> {
-    use std::io::SeekFrom;
-
-    // Read timestamp (4 bytes) - EOF here is clean end of stream
-    let timestamp = match stream.read_u32::<BigEndian>() {
-        Ok(ts) => ts,
-        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
-        Err(e) => return Err(e),
-    };
-
-    let record_type = stream.read_u16::<BigEndian>()?;
-    let sub_type = stream.read_u16::<BigEndian>()?;
-    let length = stream.read_u32::<BigEndian>()?;
-
-    let extended = if is_extended_type(record_type) {
-        stream.read_u32::<BigEndian>()?
-    } else {
-        0
+pub fn read_with_options(
+    stream: &mut impl Read,
+    body_buf: &mut Vec<u8>,
+    options: &ReadOptions,
+) -> Result<Option<(Header, Record)>, Error> {
+    // Read body (reuses existing capacity when possible)
+    let header = match read_frame(stream, body_buf)? {
+        Some(header) => header,
+        None => return Ok(None),
     };
 
-    // Skip the body
-    let skip_len = if is_extended_type(record_type) {
-        length.saturating_sub(4)
-    } else {
-        length
-    };
-    stream.seek(SeekFrom::Current(skip_len as i64))?;
+    // Parse record based on type
+    let record = parse_body(&header, body_buf, options, None)?;
 
-    Ok(Some(Header {
-        timestamp,
-        extended,
-        record_type,
-        sub_type,
-        length,
-    }))
+    Ok(Some((header, record)))
 }
 
-/// Parse record body into appropriate Record variant (from pre-read buffer).
-#[inline]
-fn parse_record(header: &Header, body: &[u8]) -> Result<Record, Error> {
-    use record_types::*;
-
-    let mut cursor = std::io::Cursor::new(body);
-
-    match header.record_type {
+/// Like [`read_with_options`], collecting recoverable anomalies in `warnings`
+/// instead of ignoring them.
+///
+/// Warnings are appended for the record just read; `warnings` is not cleared
+/// between calls. With [`ReadOptions::strict`] the same anomalies are errors
+/// and no warnings are produced.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::ReadOptions;
+///
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// let options = ReadOptions::default();
+/// let mut body_buf = Vec::new();
+/// let mut warnings = Vec::new();
+/// while let Some((header, _record)) =
+///     mrt_ingester::read_with_warnings(&mut reader, &mut body_buf, &options, &mut warnings)
+///         .unwrap()
+/// {
+///     for warning in warnings.drain(..) {
+///         eprintln!("record at {}: {}", header.timestamp, warning);
+///     }
+/// }
+/// ```
+pub fn read_with_warnings(
+    stream: &mut impl Read,
+    body_buf: &mut Vec<u8>,
+    options: &ReadOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<Option<(Header, Record)>, Error> {
+    let header = match read_frame(stream, body_buf)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let record = parse_body(&header, body_buf, options, Some(warnings))?;
+    Ok(Some((header, record)))
+}
+
+/// Like [`read_with_buffer`], also returning the SHA-256 of the record body.
+///
+/// The hash covers the body bytes as read, excluding the common header and
+/// the extended timestamp of *_ET types, so the same record written at a
+/// different time hashes identically. It is computed from `body_buf`, with
+/// no extra copy.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::collections::HashSet;
+///
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// let mut body_buf = Vec::new();
+/// let mut seen = HashSet::new();
+/// while let Some((header, record, hash)) =
+///     mrt_ingester::read_with_hash(&mut reader, &mut body_buf).unwrap()
+/// {
+///     if !seen.insert(hash) {
+///         continue; // Duplicate record body
+///     }
+///     // Process record
+/// }
+/// ```
+#[cfg(feature = "hash")]
+pub fn read_with_hash(
+    stream: &mut impl Read,
+    body_buf: &mut Vec<u8>,
+) -> Result<Option<(Header, Record, [u8; 32])>, Error> {
+    use sha2::{Digest, Sha256};
+
+    let header = match read_frame(stream, body_buf)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let hash: [u8; 32] = Sha256::digest(&body_buf[..]).into();
+    let record = parse_body(&header, body_buf, &ReadOptions::default(), None)?;
+
+    Ok(Some((header, record, hash)))
+}
+
+/// Reads the next MRT record without parsing its body.
+///
+/// The body is read into `body_buf` and returned as a borrowed slice, so a
+/// dispatcher can inspect the header and call [`parse_record`] only for the
+/// records it wants. Works on non-seekable streams.
+///
+/// # Returns
+///
+/// - `Ok(None)` - EOF reached at the beginning of a record (clean end of file)
+/// - `Ok(Some((header, body)))` - Header and body bytes (excluding the
+///   extended timestamp of *_ET types)
+/// - `Err(e)` - I/O error or truncated record
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new(File::open("updates.mrt").unwrap());
+/// let mut body_buf = Vec::new();
+/// while let Some((header, body)) = mrt_ingester::read_split(&mut reader, &mut body_buf).unwrap() {
+///     if header.record_type == 13 {
+///         let record = mrt_ingester::parse_record(&header, body).unwrap();
+///         // Process TABLE_DUMP_V2 record
+///     }
+/// }
+/// ```
+#[inline]
+pub fn read_split<'a>(
+    stream: &mut impl Read,
+    body_buf: &'a mut Vec<u8>,
+) -> Result<Option<(Header, &'a [u8])>, Error> {
+    Ok(read_frame(stream, body_buf)?.map(|header| (header, body_buf.as_slice())))
+}
+
+/// Read the next header and its body into `body_buf`.
+#[inline]
+fn read_frame(stream: &mut impl Read, body_buf: &mut Vec<u8>) -> Result<Option<Header>, Error> {
+    let header = match read_header(stream)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    read_body_into(stream, body_buf, &header)?;
+    Ok(Some(header))
+}
+
+/// Read a header, including the extended timestamp of *_ET types.
+///
+/// Returns `Ok(None)` on EOF before the first byte and a [`Truncated`]
+/// error on EOF anywhere inside the header.
+#[inline]
+fn read_header(stream: &mut impl Read) -> Result<Option<Header>, Error> {
+    // Read entire common header in one syscall
+    let mut header_buf = [0u8; COMMON_HEADER_LEN];
+    if !read_common_header(stream, &mut header_buf)? {
+        return Ok(None);
+    }
+
+    // Parse header fields from buffer (big-endian) - using array indexing is faster than from_be_bytes
+    let timestamp = u32::from_be_bytes([header_buf[0], header_buf[1], header_buf[2], header_buf[3]]);
+    let record_type = u16::from_be_bytes([header_buf[4], header_buf[5]]);
+    let sub_type = u16::from_be_bytes([header_buf[6], header_buf[7]]);
+    let length = u32::from_be_bytes([header_buf[8], header_buf[9], header_buf[10], header_buf[11]]);
+
+    // Handle extended timestamp for *_ET types
+    let extended = if is_extended_type(record_type) {
+        let mut microseconds = [0u8; EXTENDED_TIMESTAMP_LEN];
+        read_record_part(stream, &mut microseconds, COMMON_HEADER_LEN, length)?;
+        u32::from_be_bytes(microseconds)
+    } else {
+        0
+    };
+
+    let header = Header {
+        timestamp,
+        extended,
+        record_type,
+        sub_type,
+        length,
+    };
+
+    Ok(Some(header))
+}
+
+/// Reads the next MRT record, returning its raw bytes alongside the parsed record.
+///
+/// The [`RawRecord`] holds the record exactly as it appeared in the stream,
+/// including the extended timestamp of *_ET types, so it can be written back
+/// out byte-for-byte.
+///
+/// # Returns
+///
+/// - `Ok(None)` - EOF reached at the beginning of a record (clean end of file)
+/// - `Ok(Some((raw, record)))` - Successfully read and parsed a record
+/// - `Err(e)` - I/O error or invalid/unsupported record format
+pub fn read_raw(stream: &mut impl Read) -> Result<Option<(RawRecord, Record)>, Error> {
+    let mut header_buf = [0u8; COMMON_HEADER_LEN];
+    if !read_common_header(stream, &mut header_buf)? {
+        return Ok(None);
+    }
+
+    let timestamp = u32::from_be_bytes([header_buf[0], header_buf[1], header_buf[2], header_buf[3]]);
+    let record_type = u16::from_be_bytes([header_buf[4], header_buf[5]]);
+    let sub_type = u16::from_be_bytes([header_buf[6], header_buf[7]]);
+    let length = u32::from_be_bytes([header_buf[8], header_buf[9], header_buf[10], header_buf[11]]);
+
+    // The length field covers the extended timestamp, so the record is
+    // always COMMON_HEADER_LEN + length bytes on the wire
+    let capacity = COMMON_HEADER_LEN + (length as usize).min(MAX_BODY_PREALLOC);
+    let mut bytes = Vec::with_capacity(capacity);
+    bytes.extend_from_slice(&header_buf);
+    let n = stream.take(length as u64).read_to_end(&mut bytes)?;
+    if n < length as usize {
+        return Err(Truncated {
+            expected: COMMON_HEADER_LEN as u64 + length as u64,
+            available: (COMMON_HEADER_LEN + n) as u64,
+        }
+        .into_error());
+    }
+
+    let (extended, body_offset) = if is_extended_type(record_type) {
+        if (length as usize) < EXTENDED_TIMESTAMP_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "extended record too short"));
+        }
+        let extended = u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+        (extended, COMMON_HEADER_LEN + EXTENDED_TIMESTAMP_LEN)
+    } else {
+        (0, COMMON_HEADER_LEN)
+    };
+
+    let header = Header {
+        timestamp,
+        extended,
+        record_type,
+        sub_type,
+        length,
+    };
+    let record = parse_record(&header, &bytes[body_offset..])?;
+
+    Ok(Some((RawRecord { header, bytes }, record)))
+}
+
+/// Writes a complete MRT record to the stream.
+///
+/// This is the inverse of [`read`]: the common header is derived from the
+/// record and the encoded body length. For *_ET types `microseconds` is
+/// written as the extended timestamp; it is ignored for other types.
+///
+/// Returns the header that was written.
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::bgp4mp::{BGP4MP, STATE_CHANGE_AS4};
+/// use mrt_ingester::Record;
+///
+/// let change = STATE_CHANGE_AS4::new(
+///     64512,
+///     65000,
+///     "2001:db8::1".parse().unwrap(),
+///     "2001:db8::2".parse().unwrap(),
+///     1, // Idle
+///     6, // Established
+/// )
+/// .unwrap();
+/// let record = Record::BGP4MP_ET(BGP4MP::STATE_CHANGE_AS4(change));
+///
+/// let mut out = Vec::new();
+/// let header = mrt_ingester::write(&mut out, 1_700_000_000, 250_000, &record).unwrap();
+///
+/// let (read_header, _) = mrt_ingester::read(&mut out.as_slice()).unwrap().unwrap();
+/// assert_eq!(read_header, header);
+/// ```
+pub fn write(
+    stream: &mut impl Write,
+    timestamp: u32,
+    microseconds: u32,
+    record: &Record,
+) -> Result<Header, Error> {
+    let body = record.encode()?;
+    let record_type = record.record_type();
+    let (extended, extra) = if is_extended_type(record_type) {
+        (microseconds, EXTENDED_TIMESTAMP_LEN)
+    } else {
+        (0, 0)
+    };
+    let length = u32::try_from(body.len() + extra)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "record body too long"))?;
+
+    let header = Header {
+        timestamp,
+        extended,
+        record_type,
+        sub_type: record.sub_type(),
+        length,
+    };
+
+    let mut header_buf = [0u8; COMMON_HEADER_LEN + EXTENDED_TIMESTAMP_LEN];
+    header_buf[0..4].copy_from_slice(&timestamp.to_be_bytes());
+    header_buf[4..6].copy_from_slice(&record_type.to_be_bytes());
+    header_buf[6..8].copy_from_slice(&header.sub_type.to_be_bytes());
+    header_buf[8..12].copy_from_slice(&length.to_be_bytes());
+    header_buf[12..16].copy_from_slice(&extended.to_be_bytes());
+    stream.write_all(&header_buf[..COMMON_HEADER_LEN + extra])?;
+    stream.write_all(&body)?;
+
+    Ok(header)
+}
+
+/// Converts an MRT stream to newline-delimited JSON.
+///
+/// Each record is written to `writer` as one line holding a JSON object with
+/// `header` and `record` fields. Returns the number of records written.
+/// Stops at the first read error, after flushing the lines written so far.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::{stdout, BufReader};
+///
+/// let mut reader = BufReader::new(File::open("updates.mrt").unwrap());
+/// let count = mrt_ingester::to_ndjson(&mut reader, &mut stdout().lock()).unwrap();
+/// eprintln!("{} records", count);
+/// ```
+#[cfg(feature = "json")]
+pub fn to_ndjson(reader: &mut impl Read, writer: &mut impl Write) -> Result<u64, Error> {
+    #[derive(serde::Serialize)]
+    struct Line<'a> {
+        header: &'a Header,
+        record: &'a Record,
+    }
+
+    let mut body_buf = Vec::new();
+    let mut count = 0;
+    while let Some((header, record)) = read_with_buffer(reader, &mut body_buf)? {
+        serde_json::to_writer(
+            &mut *writer,
+            &Line {
+                header: &header,
+                record: &record,
+            },
+        )?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Reads only the MRT header from the stream, skipping the body.
+///
+/// This is useful for scanning/filtering files without full parsing overhead.
+///
+/// # Returns
+///
+/// - `Ok(None)` - EOF reached at the beginning of a record
+/// - `Ok(Some(header))` - Successfully read header, body bytes skipped
+/// - `Err(e)` - I/O error, or an `UnexpectedEof` error wrapping
+///   [`Truncated`] if EOF falls inside the header or the body, as with
+///   [`read`]
+#[inline]
+pub fn read_header_only(stream: &mut (impl Read + std::io::Seek)) -> Result<Option<Header>, Error> {
+    let header = match read_header(stream)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    skip_body(stream, &header)?;
+
+    Ok(Some(header))
+}
+
+/// Seek over the body of `header`, failing with [`Truncated`] if the stream
+/// ends before the body does.
+///
+/// Seeking past the end of a file succeeds, so the last byte of the body is
+/// read back to confirm it exists. Only on failure is the stream size
+/// queried, to report how much of the record is present.
+fn skip_body(stream: &mut (impl Read + std::io::Seek), header: &Header) -> Result<(), Error> {
+    use std::io::SeekFrom;
+
+    let body_len = body_length(header) as u64;
+    if body_len == 0 {
+        return Ok(());
+    }
+    let last = stream.seek(SeekFrom::Current(body_len as i64 - 1))?;
+    if read_up_to(stream, &mut [0u8])? == 1 {
+        return Ok(());
+    }
+    let body_start = last + 1 - body_len;
+    let end = stream.seek(SeekFrom::End(0))?;
+    Err(Truncated {
+        expected: header.total_len(),
+        available: body_offset(header) as u64 + end.saturating_sub(body_start),
+    }
+    .into_error())
+}
+
+/// Skips records until one of `record_type` is found.
+///
+/// Headers are read and non-matching bodies are seeked over without parsing.
+/// On a match the header is returned and the stream is left at the start of
+/// that record's body, ready for [`read_body`].
+///
+/// # Returns
+///
+/// - `Ok(None)` - EOF reached without finding a matching record
+/// - `Ok(Some(header))` - Header of the matching record
+/// - `Err(e)` - I/O error
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new(File::open("updates.mrt").unwrap());
+/// while let Some(header) = mrt_ingester::skip_to_type(&mut reader, 17).unwrap() {
+///     let record = mrt_ingester::read_body(&mut reader, &header).unwrap();
+///     // Process BGP4MP_ET record
+/// }
+/// ```
+pub fn skip_to_type(
+    stream: &mut (impl Read + std::io::Seek),
+    record_type: u16,
+) -> Result<Option<Header>, Error> {
+    while let Some(header) = read_header(stream)? {
+        if header.record_type == record_type {
+            return Ok(Some(header));
+        }
+        skip_body(stream, &header)?;
+    }
+    Ok(None)
+}
+
+/// Scans forward for the next plausible record header after corruption.
+///
+/// Starting at the current position, every byte offset is tested as the
+/// start of a common header. A candidate is accepted when:
+///
+/// - the record type is known, and the subtype is known for that type or 0
+/// - the length is at most 16 MB (and covers the extended timestamp of
+///   `_ET` types)
+/// - the timestamp lies between 1990 and one day past the current time
+/// - the record is followed either by EOF or by another header passing
+///   the same checks
+///
+/// On success the stream is positioned at the start of the header, so the
+/// next [`read`] parses that record, and the header is returned. Returns
+/// `Ok(None)` if EOF is reached without a match.
+///
+/// This is a heuristic for recovering damaged archives: record bodies can
+/// contain byte sequences that pass every check, so a match is not proof of
+/// a real record boundary. Records parsed after a resync should be treated
+/// with suspicion, and a parse error right after one usually means a false
+/// positive; calling `resync` again after skipping a byte moves past it.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new(File::open("damaged.mrt").unwrap());
+/// loop {
+///     match mrt_ingester::read(&mut reader) {
+///         Ok(Some((header, record))) => { /* Process record */ }
+///         Ok(None) => break,
+///         Err(_) => {
+///             if mrt_ingester::resync(&mut reader).unwrap().is_none() {
+///                 break;
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub fn resync(stream: &mut (impl Read + std::io::Seek)) -> Result<Option<Header>, Error> {
+    use std::io::SeekFrom;
+
+    const WINDOW: usize = 64 * 1024;
+
+    let mut pos = stream.stream_position()?;
+    let mut window = vec![0u8; WINDOW];
+    let max_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(u32::MAX as u64, |d| d.as_secs())
+        .saturating_add(86_400);
+
+    loop {
+        stream.seek(SeekFrom::Start(pos))?;
+        let n = read_up_to(stream, &mut window)?;
+        if n < COMMON_HEADER_LEN {
+            return Ok(None);
+        }
+
+        for i in 0..=n - COMMON_HEADER_LEN {
+            let candidate = &window[i..i + COMMON_HEADER_LEN];
+            let Some(length) = plausible_header(candidate, max_timestamp) else {
+                continue;
+            };
+            let offset = pos + i as u64;
+
+            // Require the record to end at EOF or at another plausible header
+            stream.seek(SeekFrom::Start(offset + COMMON_HEADER_LEN as u64 + length as u64))?;
+            let mut next = [0u8; COMMON_HEADER_LEN];
+            let followed = match read_up_to(stream, &mut next)? {
+                0 => true,
+                COMMON_HEADER_LEN => plausible_header(&next, max_timestamp).is_some(),
+                _ => false,
+            };
+            if followed {
+                stream.seek(SeekFrom::Start(offset))?;
+                let header = read_header(stream)?;
+                stream.seek(SeekFrom::Start(offset))?;
+                return Ok(header);
+            }
+        }
+
+        if n < WINDOW {
+            return Ok(None);
+        }
+        // Overlap windows so headers straddling the boundary are tested
+        pos += (n - (COMMON_HEADER_LEN - 1)) as u64;
+    }
+}
+
+/// Returns the length of a common header that passes the [`resync`] checks.
+fn plausible_header(bytes: &[u8], max_timestamp: u64) -> Option<u32> {
+    /// 1990-01-01T00:00:00Z, before any MRT collector existed
+    const MIN_TIMESTAMP: u32 = 631_152_000;
+    const MAX_LENGTH: u32 = 16 * 1024 * 1024;
+
+    let timestamp = u32::from_be_bytes(bytes[0..4].try_into().unwrap());
+    let record_type = u16::from_be_bytes([bytes[4], bytes[5]]);
+    let sub_type = u16::from_be_bytes([bytes[6], bytes[7]]);
+    let length = u32::from_be_bytes(bytes[8..12].try_into().unwrap());
+
+    let plausible = timestamp >= MIN_TIMESTAMP
+        && timestamp as u64 <= max_timestamp
+        && known_type_name(record_type).is_some()
+        && (sub_type == 0 || known_subtype_name(record_type, sub_type).is_some())
+        && length <= MAX_LENGTH
+        && (length as usize >= EXTENDED_TIMESTAMP_LEN || !is_extended_type(record_type));
+    plausible.then_some(length)
+}
+
+/// Skips up to `n` records without parsing their bodies.
+///
+/// Each header is read and its body discarded using the header length, so
+/// this works on non-seekable streams such as pipes, e.g. to resume reading
+/// at a known record offset.
+///
+/// # Returns
+///
+/// The number of records skipped, which is less than `n` only if EOF was
+/// reached first. A record cut short by EOF is an `UnexpectedEof` error.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut stdin = std::io::stdin().lock();
+/// let skipped = mrt_ingester::skip_records(&mut stdin, 1000).unwrap();
+/// while let Some((header, record)) = mrt_ingester::read(&mut stdin).unwrap() {
+///     // Process records after the first `skipped`
+/// }
+/// ```
+pub fn skip_records(stream: &mut impl Read, n: u64) -> Result<u64, Error> {
+    let mut skipped = 0;
+    while skipped < n {
+        let header = match read_header(stream)? {
+            Some(header) => header,
+            None => break,
+        };
+        discard_body(stream, &header)?;
+        skipped += 1;
+    }
+    Ok(skipped)
+}
+
+/// Reads the next record with a timestamp in `[start, end]`, stopping once
+/// past `end`.
+///
+/// Records before `start` are skipped without parsing their bodies. The
+/// first record after `end` is skipped too and ends the read with
+/// `Ok(None)`, as at EOF, so the rest of the stream is never read. This
+/// assumes timestamps rarely decrease, as in BGP4MP updates files; for data
+/// that is not time-ordered use [`read_in_window_unordered`].
+///
+/// Only the seconds field of the header is compared, so the microseconds of
+/// `_ET` records are ignored.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new(File::open("updates.mrt").unwrap());
+/// // One hour from 2024-01-01 00:00:00 UTC
+/// let start = 1704067200;
+/// while let Some((header, record)) =
+///     mrt_ingester::read_in_window(&mut reader, start, start + 3599).unwrap()
+/// {
+///     // Process record
+/// }
+/// ```
+pub fn read_in_window(
+    stream: &mut impl Read,
+    start: u32,
+    end: u32,
+) -> Result<Option<(Header, Record)>, Error> {
+    read_window(stream, start, end, true)
+}
+
+/// Reads the next record with a timestamp in `[start, end]`, skipping all
+/// others without parsing them.
+///
+/// Unlike [`read_in_window`], records after `end` do not end the read, so
+/// the whole stream is scanned and `Ok(None)` means EOF.
+pub fn read_in_window_unordered(
+    stream: &mut impl Read,
+    start: u32,
+    end: u32,
+) -> Result<Option<(Header, Record)>, Error> {
+    read_window(stream, start, end, false)
+}
+
+/// Shared loop of [`read_in_window`] and [`read_in_window_unordered`].
+fn read_window(
+    stream: &mut impl Read,
+    start: u32,
+    end: u32,
+    stop_after_end: bool,
+) -> Result<Option<(Header, Record)>, Error> {
+    while let Some(header) = read_header(stream)? {
+        if (start..=end).contains(&header.timestamp) {
+            let record = read_body(stream, &header)?;
+            return Ok(Some((header, record)));
+        }
+        discard_body(stream, &header)?;
+        if stop_after_end && header.timestamp > end {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// Read and drop the body of `header`, for streams that cannot seek.
+fn discard_body(stream: &mut impl Read, header: &Header) -> Result<(), Error> {
+    let body_len = body_length(header) as u64;
+    let discarded = std::io::copy(&mut stream.by_ref().take(body_len), &mut std::io::sink())?;
+    if discarded < body_len {
+        return Err(Truncated {
+            expected: header.total_len(),
+            available: body_offset(header) as u64 + discarded,
+        }
+        .into_error());
+    }
+    Ok(())
+}
+
+/// Kind of MRT file, as guessed by [`classify`] from its first records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MrtFileKind {
+    /// RIB snapshot: TABLE_DUMP or TABLE_DUMP_V2 records
+    Rib,
+    /// Update stream: BGP4MP or BGP4MP_ET records
+    Updates,
+    /// Both RIB and update records
+    Mixed,
+    /// Neither, or no records at all
+    Unknown,
+}
+
+/// Number of records [`classify`] inspects.
+const CLASSIFY_RECORDS: usize = 16;
+
+/// Guesses whether a stream holds a RIB snapshot or BGP updates.
+///
+/// Reads the headers of up to 16 records, skipping their bodies, and looks
+/// for TABLE_DUMP/TABLE_DUMP_V2 (RIB) and BGP4MP/BGP4MP_ET (updates, which
+/// includes the state changes they interleave). A record cut short ends
+/// the sample without an error, so a prefix of a file, e.g. the first
+/// 64 KB read into a buffer, classifies like the whole file.
+///
+/// The records are consumed. Classify a buffered copy of the start of the
+/// input, or use [`classify_and_rewind`] on a seekable stream.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::Read;
+/// use mrt_ingester::MrtFileKind;
+///
+/// let mut head = Vec::new();
+/// std::fs::File::open("unknown.mrt").unwrap().take(64 * 1024).read_to_end(&mut head).unwrap();
+/// match mrt_ingester::classify(&mut head.as_slice()).unwrap() {
+///     MrtFileKind::Rib => { /* Build a RIB snapshot */ }
+///     MrtFileKind::Updates => { /* Replay updates */ }
+///     _ => { /* Handle records one by one */ }
+/// }
+/// ```
+pub fn classify(stream: &mut impl Read) -> Result<MrtFileKind, Error> {
+    use record_types::*;
+
+    let mut rib = false;
+    let mut updates = false;
+    for _ in 0..CLASSIFY_RECORDS {
+        let header = match read_header(stream) {
+            Ok(Some(header)) => header,
+            Ok(None) => break,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        match header.record_type {
+            TABLE_DUMP | TABLE_DUMP_V2 => rib = true,
+            BGP4MP | BGP4MP_ET => updates = true,
+            _ => {}
+        }
+        let body_len = body_length(&header) as u64;
+        let discarded = std::io::copy(&mut stream.by_ref().take(body_len), &mut std::io::sink())?;
+        if discarded < body_len {
+            break;
+        }
+    }
+
+    Ok(match (rib, updates) {
+        (true, false) => MrtFileKind::Rib,
+        (false, true) => MrtFileKind::Updates,
+        (true, true) => MrtFileKind::Mixed,
+        (false, false) => MrtFileKind::Unknown,
+    })
+}
+
+/// Like [`classify`], then seeks back to where the stream started.
+///
+/// The stream is rewound even if classification fails.
+pub fn classify_and_rewind(
+    stream: &mut (impl Read + std::io::Seek),
+) -> Result<MrtFileKind, Error> {
+    let start = stream.stream_position()?;
+    let kind = classify(stream);
+    stream.seek(std::io::SeekFrom::Start(start))?;
+    kind
+}
+
+/// Reads and parses the body of a record whose header has already been read.
+///
+/// The stream must be positioned at the start of the body, as left by
+/// [`skip_to_type`].
+pub fn read_body(stream: &mut impl Read, header: &Header) -> Result<Record, Error> {
+    let mut body = Vec::new();
+    read_body_into(stream, &mut body, header)?;
+    parse_record(header, &body)
+}
+
+/// Reads records until the first TABLE_DUMP_V2 PEER_INDEX_TABLE and returns it.
+///
+/// Other records are skipped without being parsed, and reading stops as soon
+/// as the table is found, so for a RIB dump (which starts with the table) only
+/// the first record is read.
+///
+/// # Returns
+///
+/// - `Ok(None)` - EOF reached without finding a PEER_INDEX_TABLE
+/// - `Ok(Some(table))` - The first peer index table in the stream
+/// - `Err(e)` - I/O or parse error
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new(File::open("rib.mrt").unwrap());
+/// if let Some(table) = mrt_ingester::read_peer_index(&mut reader).unwrap() {
+///     println!("{} peers", table.peer_entries.len());
+/// }
+/// ```
+pub fn read_peer_index(
+    stream: &mut impl Read,
+) -> Result<Option<records::tabledump::PEER_INDEX_TABLE>, Error> {
+    use records::tabledump::{subtypes, PEER_INDEX_TABLE};
+
+    let mut body = Vec::new();
+    while let Some(header) = read_header(stream)? {
+        read_body_into(stream, &mut body, &header)?;
+        if header.record_type == record_types::TABLE_DUMP_V2
+            && header.sub_type == subtypes::PEER_INDEX_TABLE
+        {
+            let options = ReadOptions::default();
+            let table = PEER_INDEX_TABLE::parse_with_options(&mut &body[..], body.len(), &options)?;
+            return Ok(Some(table));
+        }
+    }
+    Ok(None)
+}
+
+/// Collects the distinct origin ASNs of the TABLE_DUMP_V2 RIB entries in a
+/// stream.
+///
+/// Every RIB subtype is covered, with and without Add-Path; other records
+/// are skipped. The origin is the last AS of each entry's AS_PATH (see
+/// [`AsPath::origin_asn`](records::bgp::attributes::AsPath::origin_asn)).
+/// Entries without an AS_PATH, or whose path ends in an AS_SET, contribute
+/// nothing.
+///
+/// Fails on the first unreadable record or malformed AS_PATH.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("rib.mrt").unwrap());
+/// let origins = mrt_ingester::origin_asns(&mut reader).unwrap();
+/// println!("{} origin ASNs", origins.len());
+/// ```
+pub fn origin_asns(stream: &mut impl Read) -> Result<std::collections::HashSet<u32>, Error> {
+    use records::bgp::PathAttributes;
+
+    let mut origins = std::collections::HashSet::new();
+    let mut body_buf = Vec::new();
+    while let Some((_, record)) = read_with_buffer(stream, &mut body_buf)? {
+        let Record::TABLE_DUMP_V2(table_dump) = record else {
+            continue;
+        };
+        for attributes in table_dump.entry_attributes() {
+            let as_path = PathAttributes::parse(attributes, true)?.as_path()?;
+            if let Some(origin) = as_path.and_then(|path| path.origin_asn()) {
+                origins.insert(origin);
+            }
+        }
+    }
+    Ok(origins)
+}
+
+/// Distinct prefix counts of a RIB dump, from [`prefix_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PrefixStats {
+    /// Distinct IPv4 prefixes
+    pub v4_prefixes: u64,
+    /// Distinct IPv6 prefixes
+    pub v6_prefixes: u64,
+    /// RIB entries (routes) across all prefixes
+    pub total_entries: u64,
+}
+
+/// Count the distinct prefixes and the RIB entries in a RIB dump.
+///
+/// Prefixes are deduplicated across records, so a prefix present in both
+/// the unicast and multicast RIBs, or in several TABLE_DUMP records, counts
+/// once. RIB_GENERIC entries add to `total_entries` only, since their NLRI
+/// is not decoded as a prefix. Other records are skipped.
+///
+/// Every distinct prefix is held in memory until the end, some tens of MB for
+/// a full IPv4 and IPv6 table of roughly a million prefixes.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("rib.mrt").unwrap());
+/// let stats = mrt_ingester::prefix_stats(&mut reader).unwrap();
+/// println!("{} IPv4, {} IPv6 prefixes", stats.v4_prefixes, stats.v6_prefixes);
+/// ```
+pub fn prefix_stats(stream: &mut impl Read) -> Result<PrefixStats, Error> {
+    let mut prefixes = std::collections::HashSet::new();
+    let mut stats = PrefixStats::default();
+    let mut body_buf = Vec::new();
+    while let Some((_, record)) = read_with_buffer(stream, &mut body_buf)? {
+        let prefix = match &record {
+            Record::TABLE_DUMP(table_dump) => {
+                stats.total_entries += 1;
+                table_dump.prefix_addr()?
+            }
+            Record::TABLE_DUMP_V2(table_dump) => {
+                stats.total_entries += table_dump.entry_count() as u64;
+                match table_dump.prefix_addr()? {
+                    Some(prefix) => prefix,
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        if prefixes.insert(prefix) {
+            match prefix.addr() {
+                std::net::IpAddr::V4(_) => stats.v4_prefixes += 1,
+                std::net::IpAddr::V6(_) => stats.v6_prefixes += 1,
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Fuzzing entry point: reads records from arbitrary bytes until an error or
+/// EOF, re-encoding each record that parses.
+///
+/// Must never panic, whatever `data` holds. Used by the targets under `fuzz/`.
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_read(data: &[u8]) {
+    let mut cursor = data;
+    while let Ok(Some((_, record))) = read(&mut cursor) {
+        let _ = record.encode();
+    }
+
+    let mut cursor = data;
+    while let Ok(Some(_)) = read_raw(&mut cursor) {}
+}
+
+/// Parses a record body into a [`Record`].
+///
+/// `body` holds the record after the common header, excluding the extended
+/// timestamp of *_ET types, as returned by [`read_split`]. This lets callers
+/// that do their own framing (memory maps, custom containers) reuse the
+/// record decoders.
+///
+/// [`Header`], [`Record`] and every record type are `Send + Sync`, so bodies
+/// read on one thread can be parsed on worker threads.
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::{Header, Record};
+///
+/// // A RIP record framed by the caller, e.g. sliced out of a memory map
+/// let header = Header {
+///     timestamp: 1_700_000_000,
+///     extended: 0,
+///     record_type: 6,
+///     sub_type: 0,
+///     length: 8,
+/// };
+/// let body = [192, 0, 2, 1, 192, 0, 2, 2];
+///
+/// match mrt_ingester::parse_record(&header, &body).unwrap() {
+///     Record::RIP(rip) => assert_eq!(rip.remote.to_string(), "192.0.2.1"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[inline]
+pub fn parse_record(header: &Header, body: &[u8]) -> Result<Record, Error> {
+    parse_body(header, body, &ReadOptions::default(), None)
+}
+
+/// Like [`parse_record`], with explicit parsing options.
+#[inline]
+pub fn parse_record_with_options(
+    header: &Header,
+    body: &[u8],
+    options: &ReadOptions,
+) -> Result<Record, Error> {
+    parse_body(header, body, options, None)
+}
+
+/// Parse a record body, pushing recoverable anomalies to `warnings` if given.
+#[inline]
+fn parse_body(
+    header: &Header,
+    body: &[u8],
+    options: &ReadOptions,
+    warnings: Option<&mut Vec<ParseWarning>>,
+) -> Result<Record, Error> {
+    use record_types::*;
+
+    let mut cursor = std::io::Cursor::new(body);
+
+    let record = match header.record_type {
         NULL => Ok(Record::NULL),
         START => Ok(Record::START),
         DIE => Ok(Record::DIE),
@@ -446,7 +1908,7 @@ fn parse_record(header: &Header, body: &[u8]) -> Result<Record, Error> {
             &mut cursor,
         )?)),
         TABLE_DUMP_V2 => Ok(Record::TABLE_DUMP_V2(
-            records::tabledump::TABLE_DUMP_V2::parse(header, &mut cursor)?,
+            records::tabledump::TABLE_DUMP_V2::parse_with_options(header, &mut cursor, options)?,
         )),
         BGP4MP => Ok(Record::BGP4MP(records::bgp4mp::BGP4MP::parse(
             header,
@@ -466,14 +1928,81 @@ fn parse_record(header: &Header, body: &[u8]) -> Result<Record, Error> {
             header,
             &mut cursor,
         )?)),
+        raw_type if options.skip_unknown_types => Ok(Record::Unknown {
+            raw_type,
+            sub_type: header.sub_type,
+            body: body.to_vec(),
+        }),
         _ => Err(Error::new(ErrorKind::InvalidData, "unknown record type")),
+    }?;
+
+    if options.strict_marker
+        && let Record::BGP4MP(x) | Record::BGP4MP_ET(x) = &record
+        && let Some(message) = x.message()
+    {
+        records::bgp::message::split_pdus_with_options(message, options)?;
+    }
+
+    // Records without a decoded body have nothing to check
+    if !matches!(
+        record,
+        Record::NULL
+            | Record::START
+            | Record::DIE
+            | Record::I_AM_DEAD
+            | Record::PEER_DOWN
+            | Record::IDRP
+            | Record::Unknown { .. }
+    ) {
+        let consumed = cursor.position() as usize;
+        check_consumed(header, consumed, body.len(), options, warnings)?;
     }
+    Ok(record)
 }
 
-/// Internal helper module for address parsing.
+/// Check that a decoder read the whole record body.
+///
+/// Leftover bytes mean the decoder and the header length disagree, either
+/// because the record is malformed or because the decoder reads too little.
+/// With [`ReadOptions::strict`] this is an error. Otherwise it becomes a
+/// [`ParseWarning`] when the caller collects them and is ignored when it
+/// does not. The body comes from untrusted input, so this never panics.
+fn check_consumed(
+    header: &Header,
+    consumed: usize,
+    body_len: usize,
+    options: &ReadOptions,
+    warnings: Option<&mut Vec<ParseWarning>>,
+) -> Result<(), Error> {
+    if consumed == body_len {
+        return Ok(());
+    }
+    if options.strict {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{}/{} record body is {} bytes but only {} were parsed",
+                type_name(header.record_type),
+                subtype_name(header.record_type, header.sub_type),
+                body_len,
+                consumed
+            ),
+        ));
+    }
+    if let Some(warnings) = warnings {
+        warnings.push(ParseWarning::TrailingBytes {
+            record_type: header.record_type,
+            sub_type: header.sub_type,
+            unread: body_len.saturating_sub(consumed),
+        });
+    }
+    Ok(())
+}
+
+/// Internal helper module for address parsing and encoding.
 pub(crate) mod address {
-    use byteorder::{BigEndian, ReadBytesExt};
-    use std::io::Read;
+    use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+    use std::io::{Error, ErrorKind, Read, Write};
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
     use crate::AFI;
@@ -509,7 +2038,7 @@ pub(crate) mod address {
     /// Calculate the number of bytes needed to store a prefix of given length.
     #[inline]
     pub fn prefix_bytes_needed(prefix_length: u8) -> usize {
-        ((prefix_length as usize) + 7) / 8
+        (prefix_length as usize).div_ceil(8)
     }
 
     /// Read a prefix of the given bit length.
@@ -520,6 +2049,86 @@ pub(crate) mod address {
         stream.read_exact(&mut prefix)?;
         Ok(prefix)
     }
+
+    /// Write an IP address in its 4- or 16-byte wire form.
+    #[inline]
+    pub fn write_ip(stream: &mut impl Write, addr: &IpAddr) -> std::io::Result<()> {
+        match addr {
+            IpAddr::V4(ip) => stream.write_all(&ip.octets()),
+            IpAddr::V6(ip) => stream.write_all(&ip.octets()),
+        }
+    }
+
+    /// Write an IP address whose family must match `afi`.
+    ///
+    /// The inverse of [`read_ip_by_afi`]. Fails with `InvalidInput` if the
+    /// address is not of the family `afi` names.
+    #[inline]
+    pub fn write_ip_by_afi(
+        stream: &mut impl Write,
+        addr: &IpAddr,
+        afi: &AFI,
+    ) -> std::io::Result<()> {
+        if afi_of(addr) != *afi {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("address {} does not match AFI {:?}", addr, afi),
+            ));
+        }
+        write_ip(stream, addr)
+    }
+
+    /// Write an AFI value to the stream.
+    #[inline]
+    pub fn write_afi(stream: &mut impl Write, afi: &AFI) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(afi.to_u16())
+    }
+
+    /// The AFI of an address.
+    #[inline]
+    pub fn afi_of(addr: &IpAddr) -> AFI {
+        match addr {
+            IpAddr::V4(_) => AFI::IPV4,
+            IpAddr::V6(_) => AFI::IPV6,
+        }
+    }
+
+    /// The AFI shared by two addresses that are encoded under one AFI field.
+    ///
+    /// Fails with `InvalidInput` if one address is IPv4 and the other IPv6.
+    pub fn common_afi(a: &IpAddr, b: &IpAddr) -> std::io::Result<AFI> {
+        let afi = afi_of(a);
+        if afi != afi_of(b) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "peer and local addresses must be of the same address family",
+            ));
+        }
+        Ok(afi)
+    }
+
+    /// Check that prefix bytes match the prefix length before encoding.
+    pub fn check_prefix(prefix_length: u8, prefix: &[u8]) -> std::io::Result<()> {
+        if prefix.len() != prefix_bytes_needed(prefix_length) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "prefix byte count does not match prefix length",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write a field preceded by its 2-byte length.
+    pub fn write_u16_prefixed(stream: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(u16_len(bytes.len())?)?;
+        stream.write_all(bytes)
+    }
+
+    /// Convert a length or count to its 16-bit wire form.
+    pub fn u16_len(len: usize) -> std::io::Result<u16> {
+        u16::try_from(len)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "field too long for 16-bit length"))
+    }
 }
 
 #[cfg(test)]
@@ -539,10 +2148,61 @@ mod tests {
         assert_eq!(AFI::IPV6 as u16, 2);
     }
 
+    #[test]
+    fn test_afi_wire_round_trip() {
+        for afi in [AFI::IPV4, AFI::IPV6] {
+            assert_eq!(AFI::from_u16(afi.to_u16()).unwrap(), afi);
+            let mut out = Vec::new();
+            address::write_afi(&mut out, &afi).unwrap();
+            assert_eq!(out, afi.to_u16().to_be_bytes());
+            assert_eq!(address::read_afi(&mut out.as_slice()).unwrap(), afi);
+        }
+        for addr in ["192.0.2.1".parse().unwrap(), "2001:db8::1".parse().unwrap()] {
+            let afi = address::afi_of(&addr);
+            let mut out = Vec::new();
+            write_ip_by_afi(&mut out, &addr, &afi).unwrap();
+            assert_eq!(read_ip_by_afi(&mut out.as_slice(), &afi).unwrap(), addr);
+        }
+        let v4: std::net::IpAddr = "192.0.2.1".parse().unwrap();
+        let err = write_ip_by_afi(&mut Vec::new(), &v4, &AFI::IPV6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(AFI::from_u16(3).is_err());
+    }
+
+    #[test]
+    fn test_safi_wire_round_trip() {
+        use records::bgp::attributes::{Safi, safis};
+        for safi in [
+            safis::UNICAST,
+            safis::MULTICAST,
+            safis::MPLS_LABEL,
+            safis::BGP_LS,
+            safis::MPLS_VPN,
+            safis::MULTICAST_VPN,
+        ] {
+            let body: &[u8] = &[
+                0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+                0x00, 0x01, // AFI = IPv4
+                safi, // SAFI
+                0x00, 0x00, // nlri_len = 0
+                0x00, 0x00, // entry_count = 0
+            ];
+            let rib = tabledump::RIB_GENERIC::parse(&mut &body[..]).unwrap();
+            assert_eq!(rib.safi, safi);
+            assert_eq!(Safi::from_u8(rib.safi).to_u8(), safi);
+            assert!(!matches!(Safi::from_u8(safi), Safi::Other(_)));
+            let mut out = Vec::new();
+            rib.write(&mut out).unwrap();
+            assert_eq!(out, body);
+        }
+        assert_eq!(Safi::from_u8(200), Safi::Other(200));
+        assert_eq!(Safi::Other(200).to_u8(), 200);
+    }
+
     #[test]
     fn test_read_eof_at_start() {
         let data: &[u8] = &[];
-        let result = read(&mut data.as_ref());
+        let result = read(&mut &data[..]);
         assert!(result.unwrap().is_none());
     }
 
@@ -554,7 +2214,7 @@ mod tests {
             0x00, 0x00, // subtype = 0
             0x00, 0x00, 0x00, 0x00, // length = 0
         ];
-        let result = read(&mut data.as_ref()).unwrap().unwrap();
+        let result = read(&mut &data[..]).unwrap().unwrap();
         assert_eq!(result.0.timestamp, 1);
         assert!(matches!(result.1, Record::NULL));
     }
@@ -567,7 +2227,7 @@ mod tests {
             0x00, 0x00, // subtype = 0
             0x00, 0x00, 0x00, 0x00, // length = 0
         ];
-        let result = read(&mut data.as_ref()).unwrap().unwrap();
+        let result = read(&mut &data[..]).unwrap().unwrap();
         assert!(matches!(result.1, Record::START));
     }
 
@@ -579,11 +2239,820 @@ mod tests {
             0x00, 0x00, // subtype
             0x00, 0x00, 0x00, 0x00, // length = 0
         ];
-        let result = read(&mut data.as_ref());
+        let result = read(&mut &data[..]);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
     }
 
+    #[test]
+    fn test_read_raw_extended() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x21, // type = 33 (ISIS_ET)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x06, // length = 6 (4 microseconds + 2 body)
+            0x00, 0x00, 0x01, 0xF4, // microseconds = 500
+            0xAA, 0xBB, // body
+            0x00, 0x00, 0x00, 0x02, // trailing NULL record
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut cursor = data;
+        let (raw, record) = read_raw(&mut cursor).unwrap().unwrap();
+        assert_eq!(raw.header.extended, 500);
+        assert_eq!(raw.bytes, &data[..18]);
+        assert_eq!(raw.body(), &[0xAA, 0xBB]);
+        assert!(matches!(record, Record::ISIS_ET(ref pdu) if pdu == &[0xAA, 0xBB]));
+
+        let (raw, _) = read_raw(&mut cursor).unwrap().unwrap();
+        assert_eq!(raw.bytes.len(), 12);
+        assert!(read_raw(&mut cursor).unwrap().is_none());
+    }
+
+    /// BGP4MP_ET MESSAGE with a nonzero microseconds field, a 3-byte BGP
+    /// message, then a NULL record to detect any over- or under-read.
+    const BGP4MP_ET_MESSAGE: &[u8] = &[
+        0x00, 0x00, 0x00, 0x01, // timestamp
+        0x00, 0x11, // type = 17 (BGP4MP_ET)
+        0x00, 0x01, // subtype = 1 (MESSAGE)
+        0x00, 0x00, 0x00, 0x17, // length = 23 (4 microseconds + 16 header + 3 message)
+        0x00, 0x0F, 0x42, 0x3F, // microseconds = 999999
+        0xFD, 0xE8, // peer_as = 65000
+        0xFD, 0xE9, // local_as = 65001
+        0x00, 0x00, // interface
+        0x00, 0x01, // AFI = IPv4
+        0xC0, 0x00, 0x02, 0x01, // peer_address = 192.0.2.1
+        0xC0, 0x00, 0x02, 0x02, // local_address = 192.0.2.2
+        0xAA, 0xBB, 0xCC, // message
+        0x00, 0x00, 0x00, 0x02, // trailing NULL record
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn assert_bgp4mp_et_message(header: &Header, record: &Record) {
+        assert_eq!(header.extended, 999_999);
+        assert_eq!(body_length(header), 19);
+        match record {
+            Record::BGP4MP_ET(records::bgp4mp::BGP4MP::MESSAGE(msg)) => {
+                assert_eq!(msg.peer_as, 65000);
+                assert_eq!(msg.message, &[0xAA, 0xBB, 0xCC]);
+                assert_eq!(msg.timestamp_micros, Some(999_999));
+            }
+            other => panic!("unexpected record {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_bgp4mp_et_message_length() {
+        let mut cursor = BGP4MP_ET_MESSAGE;
+        let (header, record) = read(&mut cursor).unwrap().unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+        let (header, _) = read(&mut cursor).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert!(read(&mut cursor).unwrap().is_none());
+
+        let mut cursor = BGP4MP_ET_MESSAGE;
+        let mut body_buf = Vec::new();
+        let (header, record) = read_with_buffer(&mut cursor, &mut body_buf).unwrap().unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+        let (header, _) = read_with_buffer(&mut cursor, &mut body_buf).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+
+        let mut cursor = BGP4MP_ET_MESSAGE;
+        let (raw, record) = read_raw(&mut cursor).unwrap().unwrap();
+        assert_bgp4mp_et_message(&raw.header, &record);
+        assert_eq!(raw.body().len(), 19);
+    }
+
+    #[test]
+    fn test_read_header_only_bgp4mp_et() {
+        let mut cursor = std::io::Cursor::new(BGP4MP_ET_MESSAGE);
+        let header = read_header_only(&mut cursor).unwrap().unwrap();
+        assert_eq!(header.extended, 999_999);
+        assert_eq!(cursor.position(), 12 + 23);
+        let header = read_header_only(&mut cursor).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert!(read_header_only(&mut cursor).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_header_only_truncated() {
+        use std::io::Cursor;
+
+        // EOF inside the common header
+        let mut cursor = Cursor::new(&BGP4MP_ET_MESSAGE[..7]);
+        let err = read_header_only(&mut cursor).unwrap_err();
+        assert_eq!(truncation(err), Truncated { expected: 12, available: 7 });
+
+        // EOF inside the extended timestamp
+        let mut cursor = Cursor::new(&BGP4MP_ET_MESSAGE[..14]);
+        let err = read_header_only(&mut cursor).unwrap_err();
+        assert_eq!(truncation(err), Truncated { expected: 35, available: 14 });
+
+        // EOF inside the body, which a bare seek would skip past
+        let mut cursor = Cursor::new(&BGP4MP_ET_MESSAGE[..30]);
+        let err = read_header_only(&mut cursor).unwrap_err();
+        assert_eq!(truncation(err), Truncated { expected: 35, available: 30 });
+        let mut cursor = Cursor::new(&BGP4MP_ET_MESSAGE[..34]);
+        let err = skip_to_type(&mut cursor, record_types::NULL).unwrap_err();
+        assert_eq!(truncation(err), Truncated { expected: 35, available: 34 });
+
+        // A body ending exactly at EOF is complete
+        let mut cursor = Cursor::new(&BGP4MP_ET_MESSAGE[..35]);
+        assert!(read_header_only(&mut cursor).unwrap().is_some());
+        assert!(read_header_only(&mut cursor).unwrap().is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_to_ndjson() {
+        let mut out = Vec::new();
+        let count = to_ndjson(&mut &BGP4MP_ET_MESSAGE[..], &mut out).unwrap();
+        assert_eq!(count, 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["header"]["record_type"], 17);
+        assert_eq!(first["header"]["extended"], 999_999);
+        let message = &first["record"]["BGP4MP_ET"]["MESSAGE"];
+        assert_eq!(message["peer_as"], 65000);
+        assert_eq!(message["peer_address"], "192.0.2.1");
+        assert!(lines[1].ends_with(r#""record":"NULL"}"#));
+    }
+
+    #[test]
+    fn test_read_split() {
+        let mut body_buf = Vec::new();
+        let mut stream = BGP4MP_ET_MESSAGE;
+
+        let (header, body) = read_split(&mut stream, &mut body_buf).unwrap().unwrap();
+        assert_eq!(body.len(), 19);
+        assert_eq!(&body[..2], &[0xFD, 0xE8]);
+        let record = parse_record(&header, body).unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+
+        let (header, body) = read_split(&mut stream, &mut body_buf).unwrap().unwrap();
+        assert_eq!(header.record_type, 0);
+        assert!(body.is_empty());
+        assert!(read_split(&mut stream, &mut body_buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_skip_to_type() {
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, // timestamp
+            0x00, 0x01, // type = 1 (START)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x02, // length = 2
+            0xFF, 0xFF, // body (skipped)
+        ];
+        data.extend_from_slice(BGP4MP_ET_MESSAGE);
+
+        let mut cursor = std::io::Cursor::new(data);
+        let header = skip_to_type(&mut cursor, 17).unwrap().unwrap();
+        assert_eq!(cursor.position(), 14 + 16);
+        let record = read_body(&mut cursor, &header).unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+
+        let header = skip_to_type(&mut cursor, 0).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert!(skip_to_type(&mut cursor, 17).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_n_and_skip_records() {
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, // timestamp
+            0x00, 0x01, // type = 1 (START)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x02, // length = 2
+            0xFF, 0xFF, // body
+        ];
+        data.extend_from_slice(BGP4MP_ET_MESSAGE);
+
+        let records = read_n(&mut data.as_slice(), 2).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_bgp4mp_et_message(&records[1].0, &records[1].1);
+        assert_eq!(read_n(&mut data.as_slice(), 10).unwrap().len(), 3);
+        assert!(read_n(&mut data.as_slice(), 0).unwrap().is_empty());
+
+        let mut stream = data.as_slice();
+        assert_eq!(skip_records(&mut stream, 2).unwrap(), 2);
+        let (header, _) = read(&mut stream).unwrap().unwrap();
+        assert_eq!(header.timestamp, 2);
+        assert_eq!(skip_records(&mut stream, 5).unwrap(), 0);
+
+        let mut stream = data.as_slice();
+        assert_eq!(skip_records(&mut stream, 5).unwrap(), 3);
+
+        let mut stream = &data[..20];
+        let err = skip_records(&mut stream, 2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_in_window() {
+        let mut data = Vec::new();
+        for timestamp in [1, 5, 10, 20, 8] {
+            write(&mut data, timestamp, 0, &Record::ISIS(vec![timestamp as u8])).unwrap();
+        }
+
+        let mut stream = data.as_slice();
+        let mut timestamps = Vec::new();
+        while let Some((header, record)) = read_in_window(&mut stream, 5, 10).unwrap() {
+            assert_eq!(record, Record::ISIS(vec![header.timestamp as u8]));
+            timestamps.push(header.timestamp);
+        }
+        assert_eq!(timestamps, [5, 10]);
+        // Stopped after the record at 20
+        assert_eq!(stream.len(), 13);
+
+        let mut stream = data.as_slice();
+        let mut timestamps = Vec::new();
+        while let Some((header, _)) = read_in_window_unordered(&mut stream, 5, 10).unwrap() {
+            timestamps.push(header.timestamp);
+        }
+        assert_eq!(timestamps, [5, 10, 8]);
+        assert!(stream.is_empty());
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_read_with_hash() {
+        // The message record without the trailing NULL record
+        let message = &BGP4MP_ET_MESSAGE[..35];
+        let mut data = message.to_vec();
+        data.extend_from_slice(message);
+        // Same body with a different timestamp
+        data[35 + 3] ^= 0xFF;
+        write(&mut data, 1, 0, &Record::NULL).unwrap();
+
+        let mut cursor = &data[..];
+        let mut body_buf = Vec::new();
+        let (header, record, first) = read_with_hash(&mut cursor, &mut body_buf).unwrap().unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+        let (_, _, second) = read_with_hash(&mut cursor, &mut body_buf).unwrap().unwrap();
+        assert_eq!(first, second);
+
+        // SHA-256 of the empty string
+        let (_, _, empty) = read_with_hash(&mut cursor, &mut body_buf).unwrap().unwrap();
+        assert_eq!(empty[..4], [0xE3, 0xB0, 0xC4, 0x42]);
+        assert_ne!(first, empty);
+
+        assert!(read_with_hash(&mut cursor, &mut body_buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prefix_stats() {
+        use records::tabledump::{PeerEntry, TableDumpV2Writer};
+
+        let mut writer = TableDumpV2Writer::new(0, 1);
+        writer.add_peer(PeerEntry::new(1, "10.0.0.1".parse().unwrap(), 65001)).unwrap();
+        writer.add_peer(PeerEntry::new(2, "10.0.0.2".parse().unwrap(), 65002)).unwrap();
+        for (prefix, peer_index) in [
+            ("192.0.2.0/24", 0),
+            ("192.0.2.0/24", 1),
+            ("198.51.100.0/24", 0),
+            ("2001:db8::/32", 1),
+        ] {
+            writer.add_route(peer_index, prefix.parse().unwrap(), Vec::new(), 1).unwrap();
+        }
+        let mut data = Vec::new();
+        writer.finish(&mut data).unwrap();
+
+        // A TABLE_DUMP entry repeating a prefix from the TABLE_DUMP_V2 RIB
+        let table_dump = Record::Unknown {
+            raw_type: record_types::TABLE_DUMP,
+            sub_type: 1, // AFI_IPv4
+            body: vec![
+                0x00, 0x00, // view_number
+                0x00, 0x01, // sequence_number
+                198, 51, 100, 0, // prefix
+                0x18, // prefix_length = 24
+                0x01, // status
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                10, 0, 0, 3, // peer_address
+                0xFD, 0xEB, // peer_as = 65003
+                0x00, 0x00, // attr_len
+            ],
+        };
+        write(&mut data, 1, 0, &table_dump).unwrap();
+
+        let stats = prefix_stats(&mut data.as_slice()).unwrap();
+        assert_eq!(
+            stats,
+            PrefixStats {
+                v4_prefixes: 2,
+                v6_prefixes: 1,
+                total_entries: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_origin_asns() {
+        let mut data = Vec::new();
+        let rib = Record::Unknown {
+            raw_type: record_types::TABLE_DUMP_V2,
+            sub_type: 2, // RIB_IPV4_UNICAST
+            body: vec![
+                0x00, 0x00, 0x00, 0x01, // sequence_number
+                0x18, 192, 0, 2, // 192.0.2.0/24
+                0x00, 0x02, // entry_count = 2
+                0x00, 0x00, // peer_index
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                0x00, 0x0D, // attr_len = 13
+                0x40, 0x02, 0x0A, // AS_PATH, length 10
+                0x02, 0x02, // AS_SEQUENCE of 2
+                0x00, 0x00, 0x0D, 0x1C, 0x00, 0x00, 0xFB, 0xF4, // 3356 64500
+                0x00, 0x01, // peer_index
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                0x00, 0x04, // attr_len = 4
+                0x40, 0x01, 0x01, 0x00, // ORIGIN only, no AS_PATH
+            ],
+        };
+        let addpath_rib = Record::Unknown {
+            raw_type: record_types::TABLE_DUMP_V2,
+            sub_type: 8, // RIB_IPV4_UNICAST_ADDPATH
+            body: vec![
+                0x00, 0x00, 0x00, 0x02, // sequence_number
+                0x10, 198, 51, // 198.51.0.0/16
+                0x00, 0x02, // entry_count = 2
+                0x00, 0x00, // peer_index
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                0x00, 0x00, 0x00, 0x01, // path_identifier
+                0x00, 0x0D, // attr_len = 13
+                0x40, 0x02, 0x0A, // AS_PATH, length 10
+                0x02, 0x02, // AS_SEQUENCE of 2
+                0x00, 0x00, 0x00, 0xAE, 0xFA, 0x56, 0xEA, 0x00, // 174 4200000000
+                0x00, 0x01, // peer_index
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                0x00, 0x00, 0x00, 0x02, // path_identifier
+                0x00, 0x0D, // attr_len = 13
+                0x40, 0x02, 0x0A, // AS_PATH, length 10
+                0x01, 0x02, // AS_SET of 2
+                0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, // {1, 2}
+            ],
+        };
+        write(&mut data, 1, 0, &rib).unwrap();
+        write(&mut data, 1, 0, &Record::NULL).unwrap();
+        write(&mut data, 1, 0, &addpath_rib).unwrap();
+
+        let origins = origin_asns(&mut data.as_slice()).unwrap();
+        let mut origins: Vec<u32> = origins.into_iter().collect();
+        origins.sort();
+        assert_eq!(origins, [64500, 4_200_000_000]);
+    }
+
+    #[test]
+    fn test_classify() {
+        let mut rib = Vec::new();
+        let peer_index = Record::Unknown {
+            raw_type: record_types::TABLE_DUMP_V2,
+            sub_type: 1,
+            body: vec![0; 8],
+        };
+        write(&mut rib, 1, 0, &Record::START).unwrap();
+        write(&mut rib, 1, 0, &peer_index).unwrap();
+        assert_eq!(classify(&mut rib.as_slice()).unwrap(), MrtFileKind::Rib);
+
+        let mut updates = BGP4MP_ET_MESSAGE.to_vec();
+        assert_eq!(classify(&mut updates.as_slice()).unwrap(), MrtFileKind::Updates);
+        // A truncated record ends the sample
+        assert_eq!(classify(&mut &updates[..20]).unwrap(), MrtFileKind::Updates);
+        assert_eq!(classify(&mut &updates[..5]).unwrap(), MrtFileKind::Unknown);
+
+        updates.extend_from_slice(&rib);
+        let mut cursor = std::io::Cursor::new(&updates);
+        assert_eq!(classify_and_rewind(&mut cursor).unwrap(), MrtFileKind::Mixed);
+        assert_eq!(cursor.position(), 0);
+        let (header, record) = read(&mut cursor).unwrap().unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+
+        assert_eq!(classify(&mut &[][..]).unwrap(), MrtFileKind::Unknown);
+    }
+
+    #[test]
+    fn test_resync() {
+        let mut records = Vec::new();
+        write(&mut records, 1_700_000_000, 0, &Record::START).unwrap();
+        write(&mut records, 1_700_000_001, 0, &Record::NULL).unwrap();
+
+        let mut data = vec![0xFF, 0x00, 0x13];
+        // Plausible header whose length does not lead to another header
+        data.extend_from_slice(&[
+            0x65, 0x53, 0xF1, 0x00, // timestamp (2023)
+            0x00, 0x0D, // type = 13 (TABLE_DUMP_V2)
+            0x00, 0x02, // subtype = 2 (RIB_IPV4_UNICAST)
+            0x00, 0x00, 0x00, 0x05, // length = 5
+        ]);
+        data.extend_from_slice(&[0xAB; 7]);
+        let garbage_len = data.len() as u64;
+        data.extend_from_slice(&records);
+
+        let mut cursor = std::io::Cursor::new(data);
+        let header = resync(&mut cursor).unwrap().unwrap();
+        assert_eq!(cursor.position(), garbage_len);
+        assert_eq!(header.timestamp, 1_700_000_000);
+        assert!(matches!(read(&mut cursor).unwrap().unwrap().1, Record::START));
+
+        // Already aligned: stays put
+        let header = resync(&mut cursor).unwrap().unwrap();
+        assert_eq!(header.timestamp, 1_700_000_001);
+        assert_eq!(cursor.position(), garbage_len + 12);
+
+        let mut cursor = std::io::Cursor::new(vec![0xAB; 100]);
+        assert!(resync(&mut cursor).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record_clone_and_eq() {
+        let (_, record) = read(&mut &BGP4MP_ET_MESSAGE[..]).unwrap().unwrap();
+        let (_, again) = read(&mut &BGP4MP_ET_MESSAGE[..]).unwrap().unwrap();
+        assert_eq!(record, again);
+
+        let mut copy = record.clone();
+        assert_eq!(copy, record);
+        if let Record::BGP4MP_ET(records::bgp4mp::BGP4MP::MESSAGE(msg)) = &mut copy {
+            msg.message.push(0xDD);
+        }
+        assert_ne!(copy, record);
+        assert_ne!(record, Record::NULL);
+    }
+
+    #[test]
+    fn test_read_peer_index() {
+        let mut data = BGP4MP_ET_MESSAGE.to_vec();
+        data.extend_from_slice(&[
+            0x00, 0x00, 0x00, 0x03, // timestamp
+            0x00, 0x0D, // type = 13 (TABLE_DUMP_V2)
+            0x00, 0x01, // subtype = PEER_INDEX_TABLE
+            0x00, 0x00, 0x00, 0x13, // length = 19
+            0x0A, 0x00, 0x00, 0x01, // collector_id
+            0x00, 0x00, // view_name_length = 0
+            0x00, 0x01, // peer_count = 1
+            0x00, // peer_type = IPv4, 2-byte AS
+            0x0A, 0x00, 0x00, 0x02, // peer_bgp_id
+            0xC0, 0x00, 0x02, 0x02, // peer_ip = 192.0.2.2
+            0xFD, 0xE9, // peer_as = 65001
+            0xFF, 0xFF, // trailing garbage, never read
+        ]);
+
+        let mut cursor = &data[..];
+        let table = read_peer_index(&mut cursor).unwrap().unwrap();
+        assert_eq!(table.collector_id, 0x0A000001);
+        assert_eq!(table.peer_entries[0].peer_as, 65001);
+        assert_eq!(cursor, &[0xFF, 0xFF]);
+
+        assert!(read_peer_index(&mut &BGP4MP_ET_MESSAGE[..]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_types_are_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Header>();
+        assert_send_sync::<Record>();
+        assert_send_sync::<RawRecord>();
+        assert_send_sync::<Prefix>();
+        assert_send_sync::<Truncated>();
+        assert_send_sync::<OffsetError>();
+        assert_send_sync::<records::bgp::BGP>();
+        assert_send_sync::<records::bgp::PathAttributes>();
+        assert_send_sync::<records::bgp::PathAttributesRef<'static>>();
+        assert_send_sync::<records::bgp4mp::BGP4MP>();
+        assert_send_sync::<records::bgp4plus::BGP4PLUS>();
+        assert_send_sync::<records::ospf::OSPFv2>();
+        assert_send_sync::<records::ospf::OSPFv3>();
+        assert_send_sync::<records::rip::RIP>();
+        assert_send_sync::<records::rip::RIPNG>();
+        assert_send_sync::<records::rip::RipPacket>();
+        assert_send_sync::<records::tabledump::TABLE_DUMP>();
+        assert_send_sync::<records::tabledump::TABLE_DUMP_V2>();
+        assert_send_sync::<records::tabledump::TableDumpV2Session>();
+        assert_send_sync::<records::tabledump::FlatRoute>();
+    }
+
+    #[test]
+    fn test_parse_record_on_worker_thread() {
+        let mut cursor = BGP4MP_ET_MESSAGE;
+        let mut body = Vec::new();
+        let (header, body) = read_split(&mut cursor, &mut body).unwrap().unwrap();
+        let body = body.to_vec();
+
+        let record = std::thread::spawn(move || parse_record(&header, &body))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+    }
+
+    /// A BGP4MP STATE_CHANGE record with two bytes after new_state.
+    const STATE_CHANGE_TRAILING_BYTES: &[u8] = &[
+        0x00, 0x00, 0x00, 0x01, // timestamp
+        0x00, 0x10, // type = BGP4MP
+        0x00, 0x00, // subtype = STATE_CHANGE
+        0x00, 0x00, 0x00, 0x16, // length = 22
+        0xFD, 0xE9, // peer_as = 65001
+        0xFD, 0xEA, // local_as = 65002
+        0x00, 0x00, // interface
+        0x00, 0x01, // AFI = IPv4
+        192, 0, 2, 1, // peer_address
+        192, 0, 2, 2, // local_address
+        0x00, 0x01, // old_state = Idle
+        0x00, 0x06, // new_state = Established
+        0xAB, 0xCD, // trailing bytes
+    ];
+
+    #[test]
+    fn test_strict_rejects_unread_body_bytes() {
+        let options = ReadOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let mut cursor = STATE_CHANGE_TRAILING_BYTES;
+        let err = read_with_options(&mut cursor, &mut Vec::new(), &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "BGP4MP/STATE_CHANGE record body is 22 bytes but only 20 were parsed"
+        );
+
+        // Exact bodies pass
+        let mut data = STATE_CHANGE_TRAILING_BYTES.to_vec();
+        data[11] = 20;
+        data.truncate(32);
+        let mut cursor = &data[..];
+        assert!(read_with_options(&mut cursor, &mut Vec::new(), &options).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_strict_marker_checks_message_records() {
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x10, // type = 16 (BGP4MP)
+            0x00, 0x01, // subtype = 1 (MESSAGE)
+            0x00, 0x00, 0x00, 0x23, // length = 35
+            0xFD, 0xE8, // peer_as = 65000
+            0xFD, 0xE9, // local_as = 65001
+            0x00, 0x00, // interface
+            0x00, 0x01, // AFI = IPv4
+            192, 0, 2, 1, // peer_address
+            192, 0, 2, 2, // local_address
+        ];
+        data.extend_from_slice(&[0xFF; 16]); // marker
+        data.extend_from_slice(&[0x00, 0x13, 0x04]); // length = 19, type = KEEPALIVE
+        let options = ReadOptions {
+            strict_marker: true,
+            ..Default::default()
+        };
+        let mut cursor = &data[..];
+        assert!(read_with_options(&mut cursor, &mut Vec::new(), &options).unwrap().is_some());
+
+        // RFC 1771 authentication data in the marker
+        data[28] = 0x5A;
+        let mut cursor = &data[..];
+        assert!(read(&mut cursor).unwrap().is_some());
+        let mut cursor = &data[..];
+        let err = read_with_options(&mut cursor, &mut Vec::new(), &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("BGP message at byte 0 has a marker"));
+    }
+
+    #[test]
+    fn test_read_with_warnings_reports_unread_body_bytes() {
+        let mut data = STATE_CHANGE_TRAILING_BYTES.to_vec();
+        data.extend_from_slice(&BGP4MP_ET_MESSAGE[..35]);
+
+        let mut cursor = &data[..];
+        let mut body_buf = Vec::new();
+        let mut warnings = Vec::new();
+        let options = ReadOptions::default();
+        read_with_warnings(&mut cursor, &mut body_buf, &options, &mut warnings).unwrap().unwrap();
+        assert_eq!(
+            warnings,
+            [ParseWarning::TrailingBytes {
+                record_type: 16,
+                sub_type: 0,
+                unread: 2,
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "2 trailing bytes in BGP4MP/STATE_CHANGE record body not consumed"
+        );
+
+        // A clean record adds nothing
+        read_with_warnings(&mut cursor, &mut body_buf, &options, &mut warnings).unwrap().unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_unread_body_bytes_ignored_by_default() {
+        let (_, record) = read(&mut &STATE_CHANGE_TRAILING_BYTES[..]).unwrap().unwrap();
+        assert!(matches!(record, Record::BGP4MP(_)));
+    }
+
+    #[test]
+    fn test_skip_unknown_types() {
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0xFF, // type = 255 (unknown)
+            0x00, 0x07, // subtype
+            0x00, 0x00, 0x00, 0x02, // length = 2
+            0xAB, 0xCD, // body
+        ];
+        data.extend_from_slice(BGP4MP_ET_MESSAGE);
+
+        let err = read(&mut &data[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let options = ReadOptions {
+            skip_unknown_types: true,
+            ..Default::default()
+        };
+        let mut cursor = &data[..];
+        let mut body_buf = Vec::new();
+        let (header, record) = read_with_options(&mut cursor, &mut body_buf, &options)
+            .unwrap()
+            .unwrap();
+        match &record {
+            Record::Unknown {
+                raw_type: 255,
+                sub_type: 7,
+                body,
+            } => assert_eq!(body, &[0xAB, 0xCD]),
+            other => panic!("unexpected record {:?}", other),
+        }
+        assert_eq!(record.type_name(), "UNKNOWN");
+
+        let mut out = Vec::new();
+        assert_eq!(write(&mut out, 1, 0, &record).unwrap(), header);
+        assert_eq!(out, &data[..14]);
+
+        // The following record is still read
+        let (header, record) = read_with_options(&mut cursor, &mut body_buf, &options)
+            .unwrap()
+            .unwrap();
+        assert_bgp4mp_et_message(&header, &record);
+    }
+
+    #[test]
+    fn test_normalize_v4_mapped() {
+        use std::net::IpAddr;
+
+        let mapped: IpAddr = "::ffff:10.0.0.1".parse().unwrap();
+        assert_eq!(normalize_v4_mapped(mapped), "10.0.0.1".parse::<IpAddr>().unwrap());
+        for unchanged in ["2001:db8::1", "::10.0.0.1", "10.0.0.1"] {
+            let addr: IpAddr = unchanged.parse().unwrap();
+            assert_eq!(normalize_v4_mapped(addr), addr);
+        }
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let mut cursor = BGP4MP_ET_MESSAGE;
+        let mut out = Vec::new();
+        while let Some((raw, record)) = read_raw(&mut cursor).unwrap() {
+            let before = out.len();
+            let header = write(&mut out, raw.header.timestamp, raw.header.extended, &record).unwrap();
+            assert_eq!(header, raw.header);
+            assert_eq!(record.wire_len().unwrap(), out.len() - before);
+            assert_eq!(header.wire_len() + record.encode().unwrap().len(), out.len() - before);
+        }
+        assert_eq!(out, BGP4MP_ET_MESSAGE);
+    }
+
+    #[test]
+    fn test_write_ignores_microseconds_for_plain_types() {
+        let mut out = Vec::new();
+        let header = write(&mut out, 7, 500, &Record::ISIS(vec![0x83, 0x1B])).unwrap();
+        assert_eq!(header.extended, 0);
+        assert_eq!(header.length, 2);
+        assert_eq!(out.len(), 14);
+        assert_eq!(Record::ISIS(vec![0x83]).encode().unwrap(), &[0x83]);
+        assert_eq!(Record::ISIS(vec![0x83]).wire_len().unwrap(), 13);
+        assert_eq!(header.wire_len(), 12);
+    }
+
+    fn header_at(record_type: u16, timestamp: u32, extended: u32) -> Header {
+        Header {
+            timestamp,
+            extended,
+            record_type,
+            sub_type: 0,
+            length: 0,
+        }
+    }
+
+    #[test]
+    fn test_header_datetime() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let since_epoch = |h: Header| h.datetime().duration_since(UNIX_EPOCH).unwrap();
+
+        // Microseconds are a fraction of the second, only for *_ET types
+        let et = header_at(record_types::BGP4MP_ET, 1_000, 250_000);
+        assert_eq!(since_epoch(et), Duration::new(1_000, 250_000_000));
+        let plain = header_at(record_types::BGP4MP, 1_000, 250_000);
+        assert_eq!(since_epoch(plain), Duration::from_secs(1_000));
+        let bogus = header_at(record_types::BGP4MP_ET, 1_000, 5_000_000);
+        assert_eq!(since_epoch(bogus), Duration::new(1_000, 999_999_000));
+
+        // Unsigned timestamps run past the signed 32-bit rollover in 2038
+        let rollover = header_at(record_types::BGP4MP_ET, 1 << 31, 1);
+        assert_eq!(since_epoch(rollover), Duration::new(2_147_483_648, 1_000));
+        let max = header_at(record_types::TABLE_DUMP_V2, u32::MAX, 0);
+        assert_eq!(since_epoch(max), Duration::from_secs(u32::MAX as u64));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_header_chrono() {
+        let last_i32 = header_at(record_types::BGP4MP_ET, i32::MAX as u32, 999_999);
+        assert_eq!(last_i32.chrono().to_rfc3339(), "2038-01-19T03:14:07.999999+00:00");
+        let rollover = header_at(record_types::BGP4MP_ET, 1 << 31, 0);
+        assert_eq!(rollover.chrono().to_rfc3339(), "2038-01-19T03:14:08+00:00");
+        let max = header_at(record_types::BGP4MP, u32::MAX, 123);
+        assert_eq!(max.chrono().to_rfc3339(), "2106-02-07T06:28:15+00:00");
+    }
+
+    fn truncation(err: Error) -> Truncated {
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        *err.get_ref().unwrap().downcast_ref::<Truncated>().unwrap()
+    }
+
+    #[test]
+    fn test_read_truncated_header() {
+        let data: &[u8] = &[0x00, 0x00, 0x00, 0x01, 0x00];
+        let err = read(&mut &data[..]).unwrap_err();
+        assert_eq!(truncation(err), Truncated { expected: 12, available: 5 });
+    }
+
+    #[test]
+    fn test_read_truncated_body() {
+        // BGP4MP_ET record cut off 3 bytes into its 16-byte message header
+        let data = &BGP4MP_ET_MESSAGE[..12 + 4 + 3];
+        let expected = Truncated { expected: 35, available: 19 };
+        assert_eq!(truncation(read(&mut &data[..]).unwrap_err()), expected);
+        let err = read_with_buffer(&mut &data[..], &mut Vec::new()).unwrap_err();
+        assert_eq!(truncation(err), expected);
+        assert_eq!(truncation(read_raw(&mut &data[..]).unwrap_err()), expected);
+
+        // Cut off inside the extended timestamp
+        let data = &BGP4MP_ET_MESSAGE[..14];
+        let err = read(&mut &data[..]).unwrap_err();
+        assert!(err.to_string().contains("truncated record"));
+        assert_eq!(truncation(err), Truncated { expected: 35, available: 14 });
+    }
+
+    #[test]
+    fn test_read_huge_length_is_truncated() {
+        // A corrupt length must fail with Truncated, not try to allocate 4 GB
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x0D, // type = 13 (TABLE_DUMP_V2)
+            0x00, 0x02, // subtype = 2 (RIB_IPV4_UNICAST)
+            0xFF, 0xFF, 0xFF, 0xF0, // length
+            0x00, 0x00, 0x00, 0x00, // 4 body bytes
+        ];
+        let expected = Truncated { expected: 12 + 0xFFFF_FFF0, available: 16 };
+        assert_eq!(truncation(read(&mut &data[..]).unwrap_err()), expected);
+        let err = read_with_buffer(&mut &data[..], &mut Vec::new()).unwrap_err();
+        assert_eq!(truncation(err), expected);
+        assert_eq!(truncation(read_raw(&mut &data[..]).unwrap_err()), expected);
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn test_fuzz_read_does_not_panic() {
+        fuzz_read(BGP4MP_ET_MESSAGE);
+        for len in 0..BGP4MP_ET_MESSAGE.len() {
+            fuzz_read(&BGP4MP_ET_MESSAGE[..len]);
+        }
+        fuzz_read(&[0xFF; 64]);
+    }
+
+    #[test]
+    fn test_body_length() {
+        let mut header = Header {
+            timestamp: 0,
+            extended: 0,
+            record_type: record_types::BGP4MP,
+            sub_type: 0,
+            length: 10,
+        };
+        assert_eq!(body_length(&header), 10);
+        assert_eq!((header.wire_len(), header.total_len()), (COMMON_HEADER_LEN, 22));
+        header.record_type = record_types::OSPFV3_ET;
+        assert_eq!(body_length(&header), 6);
+        assert_eq!(header.wire_len(), COMMON_HEADER_LEN + EXTENDED_TIMESTAMP_LEN);
+        // The length already covers the extended timestamp
+        assert_eq!(header.total_len(), 22);
+        header.length = 2;
+        assert_eq!(body_length(&header), 0);
+    }
+
     #[test]
     fn test_is_extended_type() {
         assert!(!is_extended_type(16)); // BGP4MP
@@ -593,4 +3062,19 @@ mod tests {
         assert!(!is_extended_type(48)); // OSPFv3
         assert!(is_extended_type(49)); // OSPFv3_ET
     }
+
+    #[test]
+    fn test_type_and_subtype_names() {
+        assert_eq!(type_name(13), "TABLE_DUMP_V2");
+        assert_eq!(type_name(49), "OSPFv3_ET");
+        assert_eq!(type_name(99), "UNKNOWN");
+        assert_eq!(Record::NULL.type_name(), "NULL");
+
+        assert_eq!(subtype_name(17, 4), "MESSAGE_AS4");
+        assert_eq!(subtype_name(13, 1), "PEER_INDEX_TABLE");
+        assert_eq!(subtype_name(12, 2), "AFI_IPv6");
+        assert_eq!(subtype_name(5, 7), "KEEPALIVE");
+        assert_eq!(subtype_name(13, 13), "UNKNOWN");
+        assert_eq!(subtype_name(32, 0), "UNKNOWN");
+    }
 }
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Record reader that reports the byte offset of each record.
+
+use std::fmt;
+use std::io::{Error, Read};
+
+use crate::{Header, Record};
+
+/// Error returned by [`OffsetReader`] for a record that failed to read or parse.
+///
+/// Carried as the payload of the returned `io::Error`, which keeps the
+/// original error kind. Recover it with
+/// `err.get_ref().and_then(|e| e.downcast_ref::<OffsetError>())`.
+#[derive(Debug)]
+pub struct OffsetError {
+    /// Byte offset of the first header byte of the failing record
+    pub offset: u64,
+    /// The underlying I/O or parse error
+    pub source: Error,
+}
+
+impl fmt::Display for OffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record at offset {}: {}", self.offset, self.source)
+    }
+}
+
+impl std::error::Error for OffsetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Reader that yields each record together with its byte offset in the stream.
+///
+/// Offsets are computed by counting the bytes read, so the stream does not
+/// need to implement `Seek`. The offset is relative to the position of the
+/// stream when it was wrapped.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::OffsetReader;
+///
+/// let file = std::fs::File::open("updates.mrt").unwrap();
+/// let mut reader = OffsetReader::new(std::io::BufReader::new(file));
+///
+/// while let Some((offset, header, record)) = reader.read().unwrap() {
+///     println!("{}: type {}", offset, header.record_type);
+/// }
+/// ```
+pub struct OffsetReader<R> {
+    inner: CountingReader<R>,
+    body_buf: Vec<u8>,
+}
+
+impl<R: Read> OffsetReader<R> {
+    /// Wrap a stream positioned at the start of a record.
+    pub fn new(inner: R) -> Self {
+        OffsetReader {
+            inner: CountingReader {
+                inner,
+                position: 0,
+            },
+            body_buf: Vec::new(),
+        }
+    }
+
+    /// Read the next record and the offset at which it starts.
+    ///
+    /// Returns `Ok(None)` at a clean end of stream, like [`read`](crate::read).
+    /// Errors keep their kind and carry an [`OffsetError`] with the offset of
+    /// the failing record.
+    pub fn read(&mut self) -> std::io::Result<Option<(u64, Header, Record)>> {
+        let offset = self.inner.position;
+        match crate::read_with_buffer(&mut self.inner, &mut self.body_buf) {
+            Ok(Some((header, record))) => Ok(Some((offset, header, record))),
+            Ok(None) => Ok(None),
+            Err(source) => Err(Error::new(source.kind(), OffsetError { offset, source })),
+        }
+    }
+
+    /// Number of bytes consumed from the stream so far.
+    ///
+    /// After a successful [`read`](Self::read) this is the offset of the next record.
+    pub fn position(&self) -> u64 {
+        self.inner.position
+    }
+
+    /// Consume the reader, returning the wrapped stream.
+    pub fn into_inner(self) -> R {
+        self.inner.inner
+    }
+}
+
+/// Read adapter that counts the bytes passing through it.
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_offsets() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x20, // type = 32 (ISIS)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x03, // length = 3
+            0x83, 0x1B, 0x01, // PDU
+            0x00, 0x00, 0x00, 0x02, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+        ];
+        let mut reader = OffsetReader::new(data);
+        assert_eq!(reader.read().unwrap().unwrap().0, 0);
+        let (offset, header, _) = reader.read().unwrap().unwrap();
+        assert_eq!(offset, 15);
+        assert_eq!(header.timestamp, 2);
+        assert!(reader.read().unwrap().is_none());
+        assert_eq!(reader.position(), 27);
+    }
+
+    #[test]
+    fn test_error_carries_offset() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+            0x00, 0x00, 0x00, 0x02, // timestamp
+            0x00, 0xFF, // type = 255 (unknown)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+        ];
+        let mut reader = OffsetReader::new(data);
+        reader.read().unwrap();
+        let err = reader.read().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let offset_err = err.get_ref().unwrap().downcast_ref::<OffsetError>().unwrap();
+        assert_eq!(offset_err.offset, 12);
+        assert!(err.to_string().starts_with("record at offset 12: "));
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parallel parsing of multiple MRT files (requires the `rayon` feature).
+//!
+//! The read-ahead reader overlaps I/O and parsing within a single file; this
+//! module spreads whole files across the rayon thread pool for batch jobs
+//! over many dumps.
+
+use crate::readahead::open_mrt_file;
+use crate::{Header, Record};
+use rayon::prelude::*;
+use std::path::Path;
+
+/// Parse several MRT files in parallel, invoking `f` for every record.
+///
+/// Each file is opened with [`open_mrt_file`] and read on a rayon worker.
+/// The callback receives the path of the file the record came from.
+///
+/// Returns one `(path, result)` pair per input, where the result holds the
+/// number of records read or the error that stopped that file. A failure in
+/// one file does not abort the others.
+///
+/// Records within a file are delivered in order, but the interleaving of
+/// callbacks across files is non-deterministic.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// let updates = AtomicU64::new(0);
+/// let results = mrt_ingester::parallel::parse_files(
+///     vec!["rib.20240101.0000", "rib.20240101.0200"],
+///     |_path, header, _record| {
+///         if header.record_type == 13 {
+///             updates.fetch_add(1, Ordering::Relaxed);
+///         }
+///     },
+/// );
+/// for (path, result) in results {
+///     if let Err(e) = result {
+///         eprintln!("{}: {}", path, e);
+///     }
+/// }
+/// ```
+pub fn parse_files<I, P, F>(paths: I, f: F) -> Vec<(P, std::io::Result<u64>)>
+where
+    I: IntoParallelIterator<Item = P>,
+    P: AsRef<Path> + Send,
+    F: Fn(&Path, Header, &Record) + Sync,
+{
+    paths
+        .into_par_iter()
+        .map(|path| {
+            let result = parse_file(path.as_ref(), &f);
+            (path, result)
+        })
+        .collect()
+}
+
+/// Read every record of one file, returning the record count.
+fn parse_file<F>(path: &Path, f: &F) -> std::io::Result<u64>
+where
+    F: Fn(&Path, Header, &Record),
+{
+    let mut reader = open_mrt_file(path)?;
+    let mut body_buf = Vec::with_capacity(65536);
+    let mut count = 0;
+
+    while let Some((header, record)) = crate::read_with_buffer(&mut reader, &mut body_buf)? {
+        f(path, header, &record);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_parse_files() {
+        let record: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+        ];
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!("mrt_ingester_parallel_{}_a", std::process::id()));
+        let second = dir.join(format!("mrt_ingester_parallel_{}_b", std::process::id()));
+        let missing = dir.join(format!("mrt_ingester_parallel_{}_missing", std::process::id()));
+        std::fs::write(&first, record.repeat(3)).unwrap();
+        std::fs::write(&second, record.repeat(2)).unwrap();
+
+        let total = AtomicU64::new(0);
+        let results = parse_files(vec![first.clone(), second.clone(), missing], |_, _, _| {
+            total.fetch_add(1, Ordering::Relaxed);
+        });
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+
+        assert_eq!(total.load(Ordering::Relaxed), 5);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].1.as_ref().unwrap(), &3);
+        assert_eq!(results[1].1.as_ref().unwrap(), &2);
+        assert!(results[2].1.is_err());
+    }
+}
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! IP prefix type used by the RIB and NLRI decoders.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use crate::address::prefix_bytes_needed;
+use crate::AFI;
+
+/// An IP prefix in CIDR form (address plus prefix length).
+///
+/// The prefix length is guaranteed to be valid for the address family:
+/// at most 32 for IPv4 and at most 128 for IPv6.
+///
+/// Prefixes order by address family (IPv4 first), then by address, then by
+/// length. Addresses compare lexicographically on their network-order bytes,
+/// so `10.0.0.0/8` sorts before `10.0.0.0/16`, which sorts before
+/// `10.1.0.0/16`. Host bits beyond the prefix length take part in the
+/// comparison, as they do in equality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Prefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl Prefix {
+    /// Create a new prefix, validating the length against the address family.
+    ///
+    /// Returns `None` if `len` is greater than 32 for IPv4 or 128 for IPv6.
+    pub fn new(addr: IpAddr, len: u8) -> Option<Prefix> {
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if len > max {
+            return None;
+        }
+        Some(Prefix { addr, len })
+    }
+
+    /// The prefix address.
+    #[inline]
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The prefix length in bits.
+    #[inline]
+    pub fn prefix_length(&self) -> u8 {
+        self.len
+    }
+
+    /// Returns `true` if `ip` falls within this prefix.
+    ///
+    /// Addresses of a different family are never contained.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.len as u32).unwrap_or(0);
+                (u32::from(net) & mask) == (u32::from(ip) & mask)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.len as u32).unwrap_or(0);
+                (u128::from(net) & mask) == (u128::from(ip) & mask)
+            }
+            _ => false,
+        }
+    }
+
+    /// Build a prefix from its wire encoding (truncated address bytes).
+    ///
+    /// `bytes` holds the leading `ceil(prefix_length / 8)` bytes of the
+    /// address; the remaining bytes are zero-filled.
+    pub(crate) fn from_wire(afi: &AFI, prefix_length: u8, bytes: &[u8]) -> std::io::Result<Prefix> {
+        if prefix_length as u32 > afi.size() * 8 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "prefix length exceeds address size",
+            ));
+        }
+        if bytes.len() != prefix_bytes_needed(prefix_length) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "prefix byte count does not match prefix length",
+            ));
+        }
+
+        let addr = match afi {
+            AFI::IPV4 => {
+                let mut octets = [0u8; 4];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            AFI::IPV6 => {
+                let mut octets = [0u8; 16];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        };
+
+        Ok(Prefix {
+            addr,
+            len: prefix_length,
+        })
+    }
+
+    /// The prefix in NLRI wire form, without the length byte.
+    ///
+    /// Returns the leading `ceil(prefix_length / 8)` bytes of the address,
+    /// with any bits beyond the prefix length cleared.
+    pub fn wire_bytes(&self) -> Vec<u8> {
+        let mut bytes = match self.addr {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        bytes.truncate(prefix_bytes_needed(self.len));
+        let spare_bits = bytes.len() * 8 - self.len as usize;
+        if let Some(last) = bytes.last_mut() {
+            *last &= 0xFFu8 << spare_bits;
+        }
+        bytes
+    }
+}
+
+impl Ord for Prefix {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |p: &Prefix| match p.addr {
+            IpAddr::V4(ip) => (0, u128::from(u32::from(ip)), p.len),
+            IpAddr::V6(ip) => (1, u128::from(ip), p.len),
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Prefix {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<IpAddr> for Prefix {
+    /// The host prefix for `addr` (`/32` for IPv4, `/128` for IPv6).
+    fn from(addr: IpAddr) -> Self {
+        let len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Prefix { addr, len }
+    }
+}
+
+impl TryFrom<(IpAddr, u8)> for Prefix {
+    type Error = Error;
+
+    /// Build a prefix from an address and length, validating the length as
+    /// [`Prefix::new`] does.
+    fn try_from((addr, len): (IpAddr, u8)) -> Result<Self, Self::Error> {
+        Prefix::new(addr, len)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "prefix length out of range"))
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = Error;
+
+    /// Parse a prefix in CIDR notation, e.g. `192.0.2.0/24` or `2001:db8::/32`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = s
+            .split_once('/')
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing '/' in prefix"))?;
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid prefix address"))?;
+        let len = len
+            .parse::<u8>()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid prefix length"))?;
+        Prefix::new(addr, len)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "prefix length out of range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_new_validates_length() {
+        assert!(Prefix::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 32).is_some());
+        assert!(Prefix::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 33).is_none());
+        assert!(Prefix::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 128).is_some());
+        assert!(Prefix::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 129).is_none());
+    }
+
+    #[test]
+    fn test_prefix_display_from_str() {
+        let prefix: Prefix = "2001:db8::/32".parse().unwrap();
+        assert_eq!(prefix.prefix_length(), 32);
+        assert_eq!(prefix.to_string(), "2001:db8::/32");
+
+        let prefix: Prefix = "192.0.2.0/24".parse().unwrap();
+        assert_eq!(prefix.addr(), IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)));
+        assert_eq!(prefix.to_string(), "192.0.2.0/24");
+
+        assert!("192.0.2.0".parse::<Prefix>().is_err());
+        assert!("192.0.2.0/33".parse::<Prefix>().is_err());
+        assert!("bogus/8".parse::<Prefix>().is_err());
+    }
+
+    #[test]
+    fn test_prefix_contains() {
+        let prefix: Prefix = "10.0.0.0/8".parse().unwrap();
+        assert!(prefix.contains("10.1.2.3".parse().unwrap()));
+        assert!(!prefix.contains("11.0.0.1".parse().unwrap()));
+        assert!(!prefix.contains("::1".parse().unwrap()));
+
+        let default: Prefix = "0.0.0.0/0".parse().unwrap();
+        assert!(default.contains("203.0.113.9".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_prefix_ordering() {
+        let sorted = [
+            "9.0.0.0/8",
+            "10.0.0.0/8",
+            "10.0.0.0/16",
+            "10.1.0.0/16",
+            "2001:db8::/32",
+        ];
+        let mut prefixes: Vec<Prefix> = sorted.iter().rev().map(|s| s.parse().unwrap()).collect();
+        prefixes.sort();
+        let strings: Vec<String> = prefixes.iter().map(|p| p.to_string()).collect();
+        assert_eq!(strings, sorted);
+
+        let mut table = std::collections::BTreeMap::new();
+        table.insert(prefixes[4], "v6");
+        table.insert(prefixes[1], "ten");
+        assert_eq!(table.values().collect::<Vec<_>>(), [&"ten", &"v6"]);
+    }
+
+    #[test]
+    fn test_prefix_conversions() {
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        assert_eq!(Prefix::from(addr).to_string(), "192.0.2.1/32");
+        assert_eq!(Prefix::try_from((addr, 24)).unwrap().prefix_length(), 24);
+        let err = Prefix::try_from((addr, 33)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_prefix_from_wire() {
+        let prefix = Prefix::from_wire(&AFI::IPV4, 20, &[172, 16, 0x10]).unwrap();
+        assert_eq!(prefix.to_string(), "172.16.16.0/20");
+        let default = Prefix::from_wire(&AFI::IPV6, 0, &[]).unwrap();
+        assert_eq!(default.to_string(), "::/0");
+
+        assert!(Prefix::from_wire(&AFI::IPV4, 24, &[10, 0]).is_err());
+        assert!(Prefix::from_wire(&AFI::IPV4, 33, &[0; 5]).is_err());
+    }
+
+    #[test]
+    fn test_prefix_wire_bytes() {
+        let prefix: Prefix = "172.16.31.255/20".parse().unwrap();
+        assert_eq!(prefix.wire_bytes(), &[172, 16, 0x10]);
+        let prefix: Prefix = "2001:db8::/32".parse().unwrap();
+        assert_eq!(prefix.wire_bytes(), &[0x20, 0x01, 0x0D, 0xB8]);
+        let default: Prefix = "0.0.0.0/0".parse().unwrap();
+        assert!(default.wire_bytes().is_empty());
+    }
+}
@@ -4,13 +4,29 @@
 //!
 //! This module provides a threaded read-ahead reader that can significantly
 //! improve parsing throughput for large MRT files by overlapping I/O with parsing.
+//!
+//! Files opened by path are checked for compression magic bytes and
+//! decompressed transparently in the background thread. Supported formats
+//! depend on the enabled cargo features:
+//!
+//! - `bzip2`: bzip2 (`.bz2`), including multi-stream files
+//! - `gzip`: gzip (`.gz`), including multi-member files
+//! - `zstd`: Zstandard (`.zst`)
+//!
+//! The same detection is available for arbitrary readers, such as pipes or
+//! sockets, through [`detect_and_wrap`]. [`create_output`] writes the same
+//! formats, chosen by file extension.
 
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
-use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 
+use crate::{read_up_to, RecordReader};
+
 /// A reader that performs read-ahead in a background thread.
 ///
 /// This can significantly improve throughput when parsing large files by
@@ -33,19 +49,25 @@ pub struct ReadAheadReader {
     receiver: Receiver<Option<Vec<u8>>>,
     current_buf: Vec<u8>,
     pos: usize,
+    /// Times the consumer found the queue empty, in adaptive mode
+    stalls: Option<Arc<AtomicU32>>,
     _handle: JoinHandle<()>,
 }
 
 impl ReadAheadReader {
     /// Opens a file with read-ahead using default settings.
     ///
-    /// Default: 4MB chunks, queue depth of 2.
+    /// Default: 4MB chunks, queue depth of 2 (see [`ReadAheadConfig`]).
     pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
-        Self::with_config(path, 4 * 1024 * 1024, 2)
+        let config = ReadAheadConfig::default();
+        Self::with_config(path, config.chunk_size, config.queue_depth)
     }
 
     /// Opens a file with custom read-ahead configuration.
     ///
+    /// Compressed files are detected by their magic bytes and decompressed
+    /// in the background thread (see the module documentation).
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the file to read
@@ -57,18 +79,68 @@ impl ReadAheadReader {
         queue_depth: usize,
     ) -> std::io::Result<Self> {
         let file = File::open(path.as_ref())?;
-        Ok(Self::from_file(file, chunk_size, queue_depth))
+        let stream = detect_and_wrap(file)?;
+        Ok(Self::from_reader(stream, chunk_size, queue_depth))
     }
 
     /// Creates a read-ahead reader from an already-opened file.
-    pub fn from_file(mut file: File, chunk_size: usize, queue_depth: usize) -> Self {
+    ///
+    /// The file is read as-is, without compression detection.
+    pub fn from_file(file: File, chunk_size: usize, queue_depth: usize) -> Self {
+        Self::from_reader(file, chunk_size, queue_depth)
+    }
+
+    /// Creates a read-ahead reader over any `Send` reader.
+    ///
+    /// This generalizes [`from_file`](Self::from_file) to streaming sources
+    /// such as a locked stdin, a socket, or a decompressor. The reader is
+    /// moved into the background thread and read as-is, without compression
+    /// detection.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::io::BufReader;
+    /// use mrt_ingester::readahead::ReadAheadReader;
+    ///
+    /// let reader = ReadAheadReader::from_reader(std::io::stdin(), 1024 * 1024, 2);
+    /// let mut buffered = BufReader::new(reader);
+    ///
+    /// while let Ok(Some((header, record))) = mrt_ingester::read(&mut buffered) {
+    ///     // Process record
+    /// }
+    /// ```
+    pub fn from_reader<R: Read + Send + 'static>(
+        reader: R,
+        chunk_size: usize,
+        queue_depth: usize,
+    ) -> Self {
+        let config = ReadAheadConfig {
+            chunk_size,
+            queue_depth,
+            ..Default::default()
+        };
+        Self::from_reader_with_config(reader, &config)
+    }
+
+    /// Creates a read-ahead reader over any `Send` reader with the given
+    /// settings, including [adaptive](ReadAheadConfig::adaptive) chunk sizing.
+    ///
+    /// `bufreader_capacity` is not used here; it only applies to
+    /// [`open_mrt_file_with_config`].
+    pub fn from_reader_with_config<R: Read + Send + 'static>(
+        mut reader: R,
+        config: &ReadAheadConfig,
+    ) -> Self {
         let (sender, receiver): (SyncSender<Option<Vec<u8>>>, _) =
-            mpsc::sync_channel(queue_depth);
+            mpsc::sync_channel(config.queue_depth);
+        let stalls = config.adaptive.then(|| Arc::new(AtomicU32::new(0)));
+        let mut sizer = ChunkSizer::new(config, stalls.clone());
 
         let handle = thread::spawn(move || {
             loop {
-                let mut buf = vec![0u8; chunk_size];
-                match file.read(&mut buf) {
+                let mut buf = vec![0u8; sizer.size];
+                match reader.read(&mut buf) {
                     Ok(0) => {
                         // EOF
                         let _ = sender.send(None);
@@ -76,7 +148,7 @@ impl ReadAheadReader {
                     }
                     Ok(n) => {
                         buf.truncate(n);
-                        if sender.send(Some(buf)).is_err() {
+                        if !sizer.send(&sender, buf) {
                             // Receiver dropped
                             break;
                         }
@@ -93,6 +165,7 @@ impl ReadAheadReader {
             receiver,
             current_buf: Vec::new(),
             pos: 0,
+            stalls,
             _handle: handle,
         }
     }
@@ -101,8 +174,18 @@ impl ReadAheadReader {
         if self.pos < self.current_buf.len() {
             return true;
         }
-        match self.receiver.recv() {
-            Ok(Some(buf)) => {
+        let next = match self.receiver.try_recv() {
+            Err(TryRecvError::Empty) => {
+                // The background thread fell behind; let it shrink its reads
+                if let Some(stalls) = &self.stalls {
+                    stalls.fetch_add(1, Ordering::Relaxed);
+                }
+                self.receiver.recv().ok()
+            }
+            next => next.ok(),
+        };
+        match next {
+            Some(Some(buf)) => {
                 self.current_buf = buf;
                 self.pos = 0;
                 true
@@ -126,9 +209,206 @@ impl Read for ReadAheadReader {
     }
 }
 
+/// Chunk size policy of the read-ahead thread.
+///
+/// Fixed unless `stalls` is set, in which case the size doubles after
+/// `grow_after` consecutive sends that find the queue full, and halves once
+/// the consumer has found the queue empty `shrink_after` times.
+struct ChunkSizer {
+    size: usize,
+    min: usize,
+    max: usize,
+    grow_after: u32,
+    shrink_after: u32,
+    full_streak: u32,
+    stalls: Option<Arc<AtomicU32>>,
+}
+
+impl ChunkSizer {
+    fn new(config: &ReadAheadConfig, stalls: Option<Arc<AtomicU32>>) -> Self {
+        // A zero-sized read would look like EOF
+        let min = config.min_chunk_size.max(1);
+        ChunkSizer {
+            size: if stalls.is_some() { min } else { config.chunk_size },
+            min,
+            max: config.max_chunk_size.max(min),
+            grow_after: config.grow_after,
+            shrink_after: config.shrink_after,
+            full_streak: 0,
+            stalls,
+        }
+    }
+
+    /// Sends a chunk, then adjusts the size of the next one.
+    ///
+    /// Returns `false` once the receiver has been dropped.
+    fn send(&mut self, sender: &SyncSender<Option<Vec<u8>>>, buf: Vec<u8>) -> bool {
+        if self.stalls.is_none() {
+            return sender.send(Some(buf)).is_ok();
+        }
+        let was_full = match sender.try_send(Some(buf)) {
+            Ok(()) => false,
+            Err(TrySendError::Full(msg)) => {
+                if sender.send(msg).is_err() {
+                    return false;
+                }
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => return false,
+        };
+        self.adjust(was_full);
+        true
+    }
+
+    fn adjust(&mut self, was_full: bool) {
+        let Some(stalls) = &self.stalls else {
+            return;
+        };
+        self.full_streak = if was_full { self.full_streak + 1 } else { 0 };
+
+        if stalls.load(Ordering::Relaxed) >= self.shrink_after {
+            stalls.store(0, Ordering::Relaxed);
+            self.size = (self.size / 2).max(self.min);
+            self.full_streak = 0;
+        } else if self.full_streak >= self.grow_after {
+            self.size = self.size.saturating_mul(2).min(self.max);
+            self.full_streak = 0;
+        }
+    }
+}
+
+/// A reader that buffers the first bytes of a stream for inspection.
+///
+/// The buffered bytes are served again by [`Read`] before the rest of the
+/// stream, so the magic of a pipe or socket can be checked without seeking.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Read;
+/// use mrt_ingester::readahead::PeekReader;
+///
+/// let mut reader = PeekReader::new(&b"MRT data"[..], 3).unwrap();
+/// assert_eq!(reader.peeked(), b"MRT");
+///
+/// let mut all = Vec::new();
+/// reader.read_to_end(&mut all).unwrap();
+/// assert_eq!(all, b"MRT data");
+/// ```
+pub struct PeekReader<R> {
+    inner: R,
+    peeked: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> PeekReader<R> {
+    /// Reads up to `n` bytes from `inner` into the peek buffer.
+    ///
+    /// Fewer bytes are buffered if the stream ends first.
+    pub fn new(mut inner: R, n: usize) -> std::io::Result<Self> {
+        let mut peeked = vec![0u8; n];
+        let len = read_up_to(&mut inner, &mut peeked)?;
+        peeked.truncate(len);
+        Ok(PeekReader {
+            inner,
+            peeked,
+            pos: 0,
+        })
+    }
+
+    /// The buffered leading bytes, including any already re-read.
+    pub fn peeked(&self) -> &[u8] {
+        &self.peeked
+    }
+}
+
+impl<R: Read> Read for PeekReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let pending = &self.peeked[self.pos..];
+        if pending.is_empty() {
+            return self.inner.read(buf);
+        }
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Wraps `reader` in a decompressor chosen by its magic bytes.
+///
+/// The leading bytes are peeked with a [`PeekReader`] and replayed, so the
+/// returned reader yields the whole decompressed stream, or the untouched
+/// input if no enabled compression format matches. This is the detection
+/// used by [`open_mrt_file`], made available for pipes and other readers.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::BufReader;
+/// use mrt_ingester::readahead::detect_and_wrap;
+///
+/// let stream = detect_and_wrap(std::io::stdin()).unwrap();
+/// let mut reader = BufReader::new(stream);
+///
+/// while let Ok(Some((header, record))) = mrt_ingester::read(&mut reader) {
+///     // Process record
+/// }
+/// ```
+pub fn detect_and_wrap<R: Read + Send + 'static>(
+    reader: R,
+) -> std::io::Result<Box<dyn Read + Send>> {
+    let stream = PeekReader::new(reader, MAGIC_LEN)?;
+
+    #[cfg(feature = "bzip2")]
+    if is_bzip2(stream.peeked()) {
+        return Ok(Box::new(bzip2::read::MultiBzDecoder::new(stream)));
+    }
+
+    #[cfg(feature = "gzip")]
+    if stream.peeked().starts_with(&GZIP_MAGIC) {
+        return Ok(Box::new(flate2::read::MultiGzDecoder::new(stream)));
+    }
+
+    #[cfg(feature = "zstd")]
+    if stream.peeked().starts_with(&ZSTD_MAGIC) {
+        return Ok(Box::new(zstd::stream::read::Decoder::new(stream)?));
+    }
+
+    Ok(Box::new(stream))
+}
+
+/// Number of leading bytes inspected for compression detection.
+const MAGIC_LEN: usize = 10;
+
+/// gzip member header: ID1, ID2 and the deflate compression method.
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 3] = [0x1F, 0x8B, 0x08];
+
+/// Zstandard frame magic number (little-endian 0xFD2FB528).
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Check for a bzip2 stream header.
+///
+/// `BZh` alone is not enough: a raw MRT file whose first timestamp falls in
+/// April 2005 starts with the same three bytes. Also require the block size
+/// digit and the first block (or end-of-stream) magic.
+#[cfg(feature = "bzip2")]
+fn is_bzip2(magic: &[u8]) -> bool {
+    const BLOCK_MAGIC: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+    const EOS_MAGIC: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+
+    magic.len() == MAGIC_LEN
+        && magic.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&magic[3])
+        && (magic[4..] == BLOCK_MAGIC || magic[4..] == EOS_MAGIC)
+}
+
 /// Convenience function to create a high-performance reader for MRT files.
 ///
 /// Returns a `BufReader` wrapping a `ReadAheadReader` with optimized settings.
+/// Compressed input is decompressed transparently (see the module documentation).
 ///
 /// # Example
 ///
@@ -140,6 +420,557 @@ impl Read for ReadAheadReader {
 /// }
 /// ```
 pub fn open_mrt_file<P: AsRef<Path>>(path: P) -> std::io::Result<BufReader<ReadAheadReader>> {
-    let reader = ReadAheadReader::open(path)?;
-    Ok(BufReader::with_capacity(64 * 1024, reader))
+    open_mrt_file_with_config(path, ReadAheadConfig::default())
+}
+
+/// Like [`open_mrt_file`], yielding only records whose type is in `types`.
+///
+/// Returns a [`RecordReader`] with a [`RecordTypeFilter`](crate::RecordTypeFilter)
+/// built from `types`, so the bodies of other records are read past without
+/// being buffered or parsed.
+///
+/// # Example
+///
+/// ```no_run
+/// // BGP4MP and BGP4MP_ET records only
+/// let reader = mrt_ingester::readahead::open_mrt_file_typed("mixed.mrt.gz", &[16, 17]).unwrap();
+///
+/// for result in reader {
+///     let (header, record) = result.unwrap();
+///     // Process record
+/// }
+/// ```
+pub fn open_mrt_file_typed<P: AsRef<Path>>(
+    path: P,
+    types: &[u16],
+) -> std::io::Result<RecordReader<BufReader<ReadAheadReader>>> {
+    let filter = types.iter().copied().collect();
+    Ok(RecordReader::new(open_mrt_file(path)?).with_filter(filter))
+}
+
+/// Opens a file path, or stdin for `None` and `"-"`, for MRT parsing.
+///
+/// The entry point for command-line tools: the input is decompressed
+/// transparently as in [`detect_and_wrap`], read ahead in a background thread
+/// with the default [`ReadAheadConfig`], and buffered.
+///
+/// # Example
+///
+/// ```no_run
+/// let path = std::env::args().nth(1);
+/// let mut input = mrt_ingester::readahead::open_input(path.as_deref()).unwrap();
+///
+/// while let Ok(Some((header, record))) = mrt_ingester::read(&mut input) {
+///     // Process record
+/// }
+/// ```
+pub fn open_input(path: Option<&str>) -> std::io::Result<Box<dyn Read>> {
+    let source: Box<dyn Read + Send> = match path {
+        None | Some("-") => Box::new(std::io::stdin()),
+        Some(path) => Box::new(File::open(path)?),
+    };
+    let config = ReadAheadConfig::default();
+    let reader = ReadAheadReader::from_reader_with_config(detect_and_wrap(source)?, &config);
+    Ok(Box::new(BufReader::with_capacity(config.bufreader_capacity, reader)))
+}
+
+/// Creates a file for writing MRT data, or stdout for `"-"`, compressing by
+/// file extension.
+///
+/// The output counterpart of [`open_input`]: `.gz`, `.zst` and `.bz2` paths
+/// are written through a gzip, zstd or bzip2 encoder, which needs the
+/// matching cargo feature. Other paths are written raw. A compressed
+/// extension whose feature is disabled fails with `InvalidInput` rather than
+/// writing uncompressed data under a misleading name; the file is then not
+/// created, so an existing file at `path` is left untouched.
+///
+/// Call [`OutputWriter::finish`] when done to write the compression trailer
+/// and surface any error doing so.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut input = mrt_ingester::readahead::open_input(Some("updates.mrt.gz")).unwrap();
+/// let mut output = mrt_ingester::readahead::create_output("filtered.mrt.gz").unwrap();
+///
+/// while let Some((header, record)) = mrt_ingester::read(&mut input).unwrap() {
+///     if header.record_type == 17 {
+///         mrt_ingester::write(&mut output, header.timestamp, header.extended, &record).unwrap();
+///     }
+/// }
+/// output.finish().unwrap();
+/// ```
+pub fn create_output(path: &str) -> std::io::Result<OutputWriter> {
+    if path == "-" {
+        return Ok(OutputWriter::new(Output::Stdout(BufWriter::new(std::io::stdout()))));
+    }
+    let compression = [".gz", ".zst", ".bz2"]
+        .into_iter()
+        .find(|extension| path.ends_with(extension));
+    // Reject an unsupported extension before truncating the file
+    match compression {
+        #[cfg(feature = "gzip")]
+        Some(".gz") => {}
+        #[cfg(feature = "zstd")]
+        Some(".zst") => {}
+        #[cfg(feature = "bzip2")]
+        Some(".bz2") => {}
+        Some(extension) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("writing {} output needs the matching compression feature", extension),
+            ));
+        }
+        None => {}
+    }
+    let file = BufWriter::new(File::create(path)?);
+
+    let output = match compression {
+        #[cfg(feature = "gzip")]
+        Some(".gz") => Output::Gzip(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        )),
+        #[cfg(feature = "zstd")]
+        Some(".zst") => Output::Zstd(zstd::stream::write::Encoder::new(file, 0)?),
+        #[cfg(feature = "bzip2")]
+        Some(".bz2") => Output::Bzip2(bzip2::write::BzEncoder::new(
+            file,
+            bzip2::Compression::default(),
+        )),
+        _ => Output::File(file),
+    };
+    Ok(OutputWriter::new(output))
+}
+
+/// A file or stdout opened by [`create_output`], compressing if needed.
+///
+/// Dropping the writer finishes the output too, but ignores any error;
+/// call [`finish`](Self::finish) to check that everything was written.
+pub struct OutputWriter {
+    // Only `None` once finish() has taken it
+    output: Option<Output>,
+}
+
+enum Output {
+    Stdout(BufWriter<std::io::Stdout>),
+    File(BufWriter<File>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<BufWriter<File>>),
+}
+
+impl OutputWriter {
+    fn new(output: Output) -> Self {
+        OutputWriter {
+            output: Some(output),
+        }
+    }
+
+    /// Write the compression trailer, if any, and flush the output.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        match self.output.take() {
+            Some(output) => output.finish(),
+            None => Ok(()),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self.output.as_mut().expect("output is only taken by finish") {
+            Output::Stdout(w) => w,
+            Output::File(w) => w,
+            #[cfg(feature = "gzip")]
+            Output::Gzip(w) => w,
+            #[cfg(feature = "zstd")]
+            Output::Zstd(w) => w,
+            #[cfg(feature = "bzip2")]
+            Output::Bzip2(w) => w,
+        }
+    }
+}
+
+impl Output {
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            Output::Stdout(mut w) => w.flush(),
+            Output::File(mut w) => w.flush(),
+            #[cfg(feature = "gzip")]
+            Output::Gzip(w) => w.finish()?.flush(),
+            #[cfg(feature = "zstd")]
+            Output::Zstd(w) => w.finish()?.flush(),
+            #[cfg(feature = "bzip2")]
+            Output::Bzip2(w) => w.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for OutputWriter {
+    fn drop(&mut self) {
+        if let Some(output) = self.output.take() {
+            let _ = output.finish();
+        }
+    }
+}
+
+/// Tuning parameters for [`open_mrt_file_with_config`].
+///
+/// The best values depend on the storage: larger chunks and deeper queues
+/// help on network filesystems, while the defaults suit local NVMe.
+///
+/// With `adaptive` set, the background thread starts with small reads so the
+/// first records arrive quickly, and grows them while it stays ahead of the
+/// parser: after `grow_after` consecutive chunks that find the queue full,
+/// the read size doubles, up to `max_chunk_size`. Each time the parser has
+/// waited on an empty queue `shrink_after` times, the size halves again, down
+/// to `min_chunk_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadAheadConfig {
+    /// Size of each chunk read by the background thread in bytes (default 4 MB);
+    /// ignored in adaptive mode
+    pub chunk_size: usize,
+    /// Number of chunks buffered ahead of the parser (default 2)
+    pub queue_depth: usize,
+    /// Capacity of the `BufReader` wrapping the read-ahead reader (default 64 KB)
+    pub bufreader_capacity: usize,
+    /// Adjust the chunk size to the parser's pace (default `false`)
+    pub adaptive: bool,
+    /// Initial and smallest chunk size in adaptive mode (default 1 MB)
+    pub min_chunk_size: usize,
+    /// Largest chunk size in adaptive mode (default 16 MB)
+    pub max_chunk_size: usize,
+    /// Consecutive sends onto a full queue before the chunk size doubles (default 4)
+    pub grow_after: u32,
+    /// Parser waits on an empty queue before the chunk size halves (default 2)
+    pub shrink_after: u32,
+}
+
+impl Default for ReadAheadConfig {
+    fn default() -> Self {
+        ReadAheadConfig {
+            chunk_size: 4 * 1024 * 1024,
+            queue_depth: 2,
+            bufreader_capacity: 64 * 1024,
+            adaptive: false,
+            min_chunk_size: 1024 * 1024,
+            max_chunk_size: 16 * 1024 * 1024,
+            grow_after: 4,
+            shrink_after: 2,
+        }
+    }
+}
+
+/// Like [`open_mrt_file`], with explicit read-ahead settings.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::readahead::{open_mrt_file_with_config, ReadAheadConfig};
+///
+/// let config = ReadAheadConfig {
+///     chunk_size: 16 * 1024 * 1024,
+///     queue_depth: 4,
+///     ..Default::default()
+/// };
+/// let mut reader = open_mrt_file_with_config("large_file.mrt", config).unwrap();
+///
+/// while let Ok(Some((header, record))) = mrt_ingester::read(&mut reader) {
+///     // Process record
+/// }
+/// ```
+pub fn open_mrt_file_with_config<P: AsRef<Path>>(
+    path: P,
+    config: ReadAheadConfig,
+) -> std::io::Result<BufReader<ReadAheadReader>> {
+    let stream = detect_and_wrap(File::open(path.as_ref())?)?;
+    let reader = ReadAheadReader::from_reader_with_config(stream, &config);
+    Ok(BufReader::with_capacity(config.bufreader_capacity, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_up_to_short_input() {
+        let mut data: &[u8] = &[1, 2, 3];
+        let mut buf = [0u8; MAGIC_LEN];
+        assert_eq!(read_up_to(&mut data, &mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        // Small chunks force several hand-offs between the threads
+        let mut reader = ReadAheadReader::from_reader(Cursor::new(data.clone()), 64, 2);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_open_mrt_file_with_config() {
+        let path = std::env::temp_dir().join(format!("mrt_ingester_config_{}", std::process::id()));
+        let record: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]; // NULL record
+        std::fs::write(&path, record.repeat(100)).unwrap();
+
+        let config = ReadAheadConfig {
+            chunk_size: 7,
+            queue_depth: 1,
+            bufreader_capacity: 5,
+            ..Default::default()
+        };
+        let mut reader = open_mrt_file_with_config(&path, config).unwrap();
+        assert_eq!(reader.capacity(), 5);
+        let mut count = 0;
+        while crate::read(&mut reader).unwrap().is_some() {
+            count += 1;
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(count, 100);
+    }
+
+    #[test]
+    fn test_open_mrt_file_typed() {
+        let path = std::env::temp_dir().join(format!("mrt_ingester_typed_{}", std::process::id()));
+        let null: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]; // NULL record
+        let start: &[u8] = &[0, 0, 0, 2, 0, 1, 0, 0, 0, 0, 0, 0]; // START record
+        std::fs::write(&path, [null, start, null].concat()).unwrap();
+
+        let reader = open_mrt_file_typed(&path, &[1]).unwrap();
+        let timestamps: Vec<u32> = reader.map(|r| r.unwrap().0.timestamp).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(timestamps, [2]);
+    }
+
+    #[test]
+    fn test_open_input_path() {
+        let path = std::env::temp_dir().join(format!("mrt_ingester_input_{}", std::process::id()));
+        let record: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]; // NULL record
+        std::fs::write(&path, record.repeat(3)).unwrap();
+
+        let mut input = open_input(path.to_str()).unwrap();
+        let mut count = 0;
+        while crate::read(&mut input).unwrap().is_some() {
+            count += 1;
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(count, 3);
+
+        assert!(open_input(Some("/nonexistent/mrt_ingester_input")).is_err());
+    }
+
+    #[test]
+    fn test_adaptive_from_reader() {
+        let data: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        let config = ReadAheadConfig {
+            queue_depth: 1,
+            adaptive: true,
+            min_chunk_size: 16,
+            max_chunk_size: 4096,
+            grow_after: 1,
+            shrink_after: 1,
+            ..Default::default()
+        };
+        let source = Cursor::new(data.clone());
+        let mut reader = ReadAheadReader::from_reader_with_config(source, &config);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_chunk_sizer_adjust() {
+        let config = ReadAheadConfig {
+            min_chunk_size: 4,
+            max_chunk_size: 16,
+            grow_after: 2,
+            shrink_after: 2,
+            ..Default::default()
+        };
+        let stalls = Arc::new(AtomicU32::new(0));
+        let mut sizer = ChunkSizer::new(&config, Some(stalls.clone()));
+        assert_eq!(sizer.size, 4);
+
+        // Growth needs consecutive full sends
+        sizer.adjust(true);
+        sizer.adjust(false);
+        sizer.adjust(true);
+        assert_eq!(sizer.size, 4);
+        sizer.adjust(true);
+        assert_eq!(sizer.size, 8);
+        for _ in 0..4 {
+            sizer.adjust(true);
+        }
+        assert_eq!(sizer.size, 16);
+
+        // Consumer stalls take precedence and shrink the size
+        stalls.store(2, Ordering::Relaxed);
+        sizer.adjust(true);
+        assert_eq!(sizer.size, 8);
+        assert_eq!(stalls.load(Ordering::Relaxed), 0);
+
+        let fixed = ChunkSizer::new(&config, None);
+        assert_eq!(fixed.size, config.chunk_size);
+    }
+
+    #[test]
+    fn test_peek_reader_replays_bytes() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut reader = PeekReader::new(data.as_slice(), MAGIC_LEN).unwrap();
+        assert_eq!(reader.peeked(), &data[..MAGIC_LEN]);
+
+        // Reads straddling the end of the peek buffer
+        let mut buf = [0u8; 7];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[..7]);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &data[7..]);
+
+        let reader = PeekReader::new(&[1u8, 2][..], MAGIC_LEN).unwrap();
+        assert_eq!(reader.peeked(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_detect_and_wrap_raw() {
+        let record: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]; // NULL record
+        let mut stream = detect_and_wrap(Cursor::new(record.repeat(3))).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        assert_eq!(out, record.repeat(3));
+
+        let mut empty = detect_and_wrap(std::io::empty()).unwrap();
+        assert_eq!(empty.read(&mut [0u8; 4]).unwrap(), 0);
+    }
+
+    /// Write `data` with [`create_output`] and read it back with
+    /// [`open_input`].
+    fn create_output_round_trip(extension: &str) -> std::io::Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("mrt_ingester_output_{}{}", std::process::id(), extension));
+        let path = path.to_str().unwrap();
+        let record: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]; // NULL record
+
+        let result = create_output(path).and_then(|mut output| {
+            output.write_all(&record.repeat(50))?;
+            output.finish()
+        });
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(path);
+            return Err(e);
+        }
+
+        let mut out = Vec::new();
+        open_input(Some(path)).unwrap().read_to_end(&mut out).unwrap();
+        let on_disk = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(out, record.repeat(50));
+        assert_eq!(extension.is_empty(), on_disk == out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_output() {
+        create_output_round_trip("").unwrap();
+        for (extension, enabled) in [
+            (".gz", cfg!(feature = "gzip")),
+            (".zst", cfg!(feature = "zstd")),
+            (".bz2", cfg!(feature = "bzip2")),
+        ] {
+            match create_output_round_trip(extension) {
+                Ok(()) => assert!(enabled),
+                Err(e) => {
+                    assert!(!enabled);
+                    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_output_unsupported_keeps_existing_file() {
+        let path = std::env::temp_dir()
+            .join(format!("mrt_ingester_existing_{}.unsupported.gz", std::process::id()));
+        std::fs::write(&path, b"keep me").unwrap();
+        let result = create_output(path.to_str().unwrap()).map(drop);
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        if cfg!(feature = "gzip") {
+            // Supported, so the file was replaced
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(contents, b"keep me");
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_detection() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        // Two members, as produced by concatenating .gz files
+        let mut compressed = Vec::new();
+        for part in [&b"mrt "[..], b"payload"] {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(part).unwrap();
+            compressed.extend(encoder.finish().unwrap());
+        }
+
+        let mut stream = detect_and_wrap(Cursor::new(compressed)).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"mrt payload");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_detection() {
+        let compressed = zstd::encode_all(&b"mrt payload"[..], 1).unwrap();
+
+        let mut stream = detect_and_wrap(Cursor::new(compressed)).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"mrt payload");
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_bzip2_detection() {
+        use bzip2::write::BzEncoder;
+        use std::io::Write;
+
+        let mut encoder = BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+        encoder.write_all(b"mrt payload").unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(is_bzip2(&compressed[..MAGIC_LEN]));
+
+        // Raw MRT record with a timestamp of 0x425A6831 ("BZh1")
+        let raw: &[u8] = &[0x42, 0x5A, 0x68, 0x31, 0x00, 0x0D, 0x00, 0x01, 0x00, 0x00];
+        assert!(!is_bzip2(raw));
+
+        let path = std::env::temp_dir().join(format!("mrt_ingester_{}.bz2", std::process::id()));
+        std::fs::write(&path, &compressed).unwrap();
+        let mut reader = ReadAheadReader::open(&path).unwrap();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(out, b"mrt payload");
+    }
 }
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Iterator over the records of an MRT stream.
+
+use std::io::Read;
+
+use crate::{discard_body, parse_body, read_body_into, read_header};
+use crate::{Header, ReadOptions, Record, RecordTypeFilter};
+
+/// Iterator that reads records from a stream, reusing one body buffer.
+///
+/// Yields `io::Result<(Header, Record)>` and stops after the first error or
+/// at a clean end of stream.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::RecordReader;
+///
+/// let file = std::fs::File::open("rib.mrt").unwrap();
+/// // RIB records are often tens of kilobytes; size the buffer up front
+/// let reader = RecordReader::with_capacity(std::io::BufReader::new(file), 64 * 1024);
+///
+/// for result in reader {
+///     let (header, record) = result.unwrap();
+///     println!("type {}", header.record_type);
+/// }
+/// ```
+pub struct RecordReader<R> {
+    inner: R,
+    body_buf: Vec<u8>,
+    options: ReadOptions,
+    filter: Option<RecordTypeFilter>,
+    done: bool,
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Wrap a stream positioned at the start of a record.
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(inner, 0)
+    }
+
+    /// Wrap a stream, pre-allocating `capacity` bytes for record bodies.
+    ///
+    /// The buffer still grows for larger records; pre-sizing it to the
+    /// typical record size avoids reallocations on the first few records.
+    pub fn with_capacity(inner: R, capacity: usize) -> Self {
+        RecordReader {
+            inner,
+            body_buf: Vec::with_capacity(capacity),
+            options: ReadOptions::default(),
+            filter: None,
+            done: false,
+        }
+    }
+
+    /// Parse every record with `options` instead of the defaults.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mrt_ingester::{ReadOptions, RecordReader};
+    ///
+    /// let file = std::fs::File::open("rib.mrt").unwrap();
+    /// let options = ReadOptions {
+    ///     strict: true,
+    ///     ..Default::default()
+    /// };
+    /// let reader = RecordReader::new(std::io::BufReader::new(file)).with_options(options);
+    /// ```
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Yield only records whose type is in `filter`.
+    ///
+    /// The bodies of other records are read past without being buffered or
+    /// parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mrt_ingester::RecordReader;
+    ///
+    /// let file = std::fs::File::open("mixed.mrt").unwrap();
+    /// let filter = "bgp4mp,bgp4mp_et".parse().unwrap();
+    /// for result in RecordReader::new(std::io::BufReader::new(file)).with_filter(filter) {
+    ///     let (header, record) = result.unwrap();
+    ///     // Process BGP4MP record
+    /// }
+    /// ```
+    pub fn with_filter(mut self, filter: RecordTypeFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Current capacity of the body buffer in bytes.
+    pub fn capacity(&self) -> usize {
+        self.body_buf.capacity()
+    }
+
+    /// Read the next record.
+    ///
+    /// Returns `Ok(None)` at a clean end of stream, like [`read`](crate::read).
+    pub fn read(&mut self) -> std::io::Result<Option<(Header, Record)>> {
+        let Some(filter) = &self.filter else {
+            return crate::read_with_options(&mut self.inner, &mut self.body_buf, &self.options);
+        };
+        while let Some(header) = read_header(&mut self.inner)? {
+            if filter.matches(&header) {
+                read_body_into(&mut self.inner, &mut self.body_buf, &header)?;
+                let record = parse_body(&header, &self.body_buf, &self.options, None)?;
+                return Ok(Some((header, record)));
+            }
+            discard_body(&mut self.inner, &header)?;
+        }
+        Ok(None)
+    }
+
+    /// Consume the reader, returning the wrapped stream.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = std::io::Result<(Header, Record)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_capacity() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x20, // type = 32 (ISIS)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x03, // length = 3
+            0x83, 0x1B, 0x01, // PDU
+            0x00, 0x00, 0x00, 0x02, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+        ];
+        let reader = RecordReader::with_capacity(data, 4096);
+        assert!(reader.capacity() >= 4096);
+
+        let timestamps: Vec<u32> = reader.map(|r| r.unwrap().0.timestamp).collect();
+        assert_eq!(timestamps, [1, 2]);
+    }
+
+    #[test]
+    fn test_stops_after_error() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x04, // length = 4, body missing
+        ];
+        let mut reader = RecordReader::new(data);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_with_options() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0xFF, // type = 255 (unknown)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x01, // length = 1
+            0xAB, // body
+        ];
+        assert!(RecordReader::new(data).next().unwrap().is_err());
+
+        let options = ReadOptions {
+            skip_unknown_types: true,
+            ..Default::default()
+        };
+        let mut reader = RecordReader::with_capacity(data, 16).with_options(options);
+        let (_, record) = reader.next().unwrap().unwrap();
+        assert_eq!(record.record_type(), 255);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_with_filter() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // timestamp
+            0x00, 0xFF, // type = 255 (unknown, never parsed)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x02, // length = 2
+            0xAB, 0xCD, // body
+            0x00, 0x00, 0x00, 0x02, // timestamp
+            0x00, 0x20, // type = 32 (ISIS)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x03, // length = 3
+            0x83, 0x1B, 0x01, // PDU
+            0x00, 0x00, 0x00, 0x03, // timestamp
+            0x00, 0x00, // type = 0 (NULL)
+            0x00, 0x00, // subtype
+            0x00, 0x00, 0x00, 0x00, // length = 0
+        ];
+        let reader = RecordReader::new(data).with_filter("isis".parse().unwrap());
+        let records: Vec<(Header, Record)> = reader.map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0.timestamp, 2);
+        assert_eq!(records[0].1, Record::ISIS(vec![0x83, 0x1B, 0x01]));
+
+        // Skipped bodies are still checked for truncation
+        let reader = RecordReader::new(&data[..13]).with_filter("isis".parse().unwrap());
+        assert!(reader.last().unwrap().is_err());
+    }
+}
@@ -4,14 +4,29 @@
 //!
 //! This module handles the deprecated BGP record type which only supports
 //! IPv4 peers and 16-bit AS numbers. For modern BGP data, use `bgp4mp`.
+//!
+//! BGP protocol helpers shared by all record types live in submodules:
+//! [`attributes`] decodes path attribute blobs, [`message`] frames raw
+//! BGP messages and [`linkstate`] decodes BGP-LS NLRI.
 
 #![allow(non_camel_case_types)]
 
+pub mod attributes;
+pub mod linkstate;
+pub mod message;
+
+pub use attributes::{
+    as_path_len, attributes_iter, Attribute, AttributeAnomaly, AttributeIter, MpReachNlri,
+    MpUnreachNlri, PathAttributes, PathAttributesRef, PmsiTunnel, RawAttributeRef, VpnPrefix,
+};
+
 use crate::address::read_ipv4;
+use crate::records::HasPeer;
 use crate::Header;
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind, Read};
-use std::net::Ipv4Addr;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr};
 
 /// BGP subtype constants
 mod subtypes {
@@ -25,11 +40,27 @@ mod subtypes {
     pub const KEEPALIVE: u16 = 7;
 }
 
+/// Name of a subtype, or `None` if it is not known.
+pub(crate) fn subtype_name(sub_type: u16) -> Option<&'static str> {
+    Some(match sub_type {
+        subtypes::NULL => "NULL",
+        subtypes::UPDATE => "UPDATE",
+        subtypes::PREF_UPDATE => "PREF_UPDATE",
+        subtypes::STATE_CHANGE => "STATE_CHANGE",
+        subtypes::SYNC => "SYNC",
+        subtypes::OPEN => "OPEN",
+        subtypes::NOTIFY => "NOTIFY",
+        subtypes::KEEPALIVE => "KEEPALIVE",
+        _ => return None,
+    })
+}
+
 /// Legacy BGP record enum.
 ///
 /// Represents different BGP message types captured in MRT format.
 /// This is a deprecated record type; prefer `BGP4MP` for new implementations.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(non_camel_case_types)]
 pub enum BGP {
     /// Null subtype
@@ -71,12 +102,39 @@ impl BGP {
             _ => Err(Error::new(ErrorKind::InvalidData, "invalid BGP subtype")),
         }
     }
+
+    /// The MRT subtype of this record.
+    pub fn sub_type(&self) -> u16 {
+        match self {
+            BGP::NULL => subtypes::NULL,
+            BGP::UPDATE(_) => subtypes::UPDATE,
+            BGP::PREF_UPDATE => subtypes::PREF_UPDATE,
+            BGP::STATE_CHANGE(_) => subtypes::STATE_CHANGE,
+            BGP::SYNC(_) => subtypes::SYNC,
+            BGP::OPEN(_) => subtypes::OPEN,
+            BGP::NOTIFY(_) => subtypes::NOTIFY,
+            BGP::KEEPALIVE(_) => subtypes::KEEPALIVE,
+        }
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            BGP::NULL | BGP::PREF_UPDATE => Ok(()),
+            BGP::STATE_CHANGE(x) => x.write(stream),
+            BGP::SYNC(x) => x.write(stream),
+            BGP::UPDATE(x) | BGP::OPEN(x) | BGP::NOTIFY(x) | BGP::KEEPALIVE(x) => {
+                x.write(stream)
+            }
+        }
+    }
 }
 
 /// BGP message record for IPv4 peers.
 ///
 /// Used for UPDATE, OPEN, NOTIFY, and KEEPALIVE message types.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MESSAGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -118,12 +176,22 @@ impl MESSAGE {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_all(&self.peer_ip.octets())?;
+        stream.write_u16::<BigEndian>(self.local_as)?;
+        stream.write_all(&self.local_ip.octets())?;
+        stream.write_all(&self.message)
+    }
 }
 
 /// BGP state change notification.
 ///
 /// Records when a BGP session changes state (e.g., from Established to Idle).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct STATE_CHANGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -156,12 +224,21 @@ impl STATE_CHANGE {
             new_state,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_all(&self.peer_ip.octets())?;
+        stream.write_u16::<BigEndian>(self.old_state)?;
+        stream.write_u16::<BigEndian>(self.new_state)
+    }
 }
 
 /// BGP RIB synchronization record.
 ///
 /// Deprecated record type used to indicate RIB recording boundaries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SYNC {
     /// View number for multi-view RIB recordings
     pub view_number: u16,
@@ -188,6 +265,40 @@ impl SYNC {
             filename,
         })
     }
+
+    /// The filename up to its first NUL, decoded lossily as UTF-8.
+    ///
+    /// `filename` keeps the raw bytes, including the NUL terminator and any
+    /// padding, so that records re-encode unchanged.
+    pub fn filename_str(&self) -> Cow<'_, str> {
+        crate::records::nul_terminated_str(&self.filename)
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.view_number)?;
+        stream.write_all(&self.filename)
+    }
+}
+
+impl HasPeer for MESSAGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        IpAddr::V4(self.peer_ip)
+    }
+}
+
+impl HasPeer for STATE_CHANGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        IpAddr::V4(self.peer_ip)
+    }
 }
 
 #[cfg(test)]
@@ -209,7 +320,7 @@ mod tests {
             0x00, 0x01, // old_state = 1
             0x00, 0x06, // new_state = 6 (Established)
         ];
-        let result = BGP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = BGP::parse(&header, &mut &data[..]).unwrap();
         match result {
             BGP::STATE_CHANGE(sc) => {
                 assert_eq!(sc.peer_as, 100);
@@ -237,7 +348,7 @@ mod tests {
             10, 0, 0, 1, // local_ip
             0x01, 0x02, 0x03, 0x04, // message
         ];
-        let result = BGP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = BGP::parse(&header, &mut &data[..]).unwrap();
         match result {
             BGP::UPDATE(msg) => {
                 assert_eq!(msg.peer_as, 100);
@@ -263,11 +374,12 @@ mod tests {
             0x00, 0x01, // view_number = 1
             b't', b'e', b's', b't', b'.', b'm', b'r', b't', 0x00, 0x00, // filename
         ];
-        let result = BGP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = BGP::parse(&header, &mut &data[..]).unwrap();
         match result {
             BGP::SYNC(sync) => {
                 assert_eq!(sync.view_number, 1);
                 assert_eq!(sync.filename.len(), 10);
+                assert_eq!(sync.filename_str(), "test.mrt");
             }
             _ => panic!("Expected SYNC"),
         }
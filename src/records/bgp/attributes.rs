@@ -0,0 +1,1899 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! BGP path attribute decoding.
+//!
+//! MRT records carry BGP path attributes as raw bytes (for example
+//! `RIBEntry.attributes` or the attributes inside a BGP UPDATE). This module
+//! splits such a blob into individual attributes and provides typed accessors
+//! for the commonly used ones.
+
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{Prefix, AFI};
+
+/// Path attribute type codes
+pub mod attr_types {
+    /// ORIGIN (RFC 4271)
+    pub const ORIGIN: u8 = 1;
+    /// AS_PATH (RFC 4271)
+    pub const AS_PATH: u8 = 2;
+    /// NEXT_HOP (RFC 4271)
+    pub const NEXT_HOP: u8 = 3;
+    /// MULTI_EXIT_DISC (RFC 4271)
+    pub const MULTI_EXIT_DISC: u8 = 4;
+    /// LOCAL_PREF (RFC 4271)
+    pub const LOCAL_PREF: u8 = 5;
+    /// ATOMIC_AGGREGATE (RFC 4271)
+    pub const ATOMIC_AGGREGATE: u8 = 6;
+    /// AGGREGATOR (RFC 4271, RFC 6793)
+    pub const AGGREGATOR: u8 = 7;
+    /// COMMUNITIES (RFC 1997)
+    pub const COMMUNITIES: u8 = 8;
+    /// ORIGINATOR_ID (RFC 4456)
+    pub const ORIGINATOR_ID: u8 = 9;
+    /// CLUSTER_LIST (RFC 4456)
+    pub const CLUSTER_LIST: u8 = 10;
+    /// MP_REACH_NLRI (RFC 4760)
+    pub const MP_REACH_NLRI: u8 = 14;
+    /// MP_UNREACH_NLRI (RFC 4760)
+    pub const MP_UNREACH_NLRI: u8 = 15;
+    /// EXTENDED_COMMUNITIES (RFC 4360)
+    pub const EXTENDED_COMMUNITIES: u8 = 16;
+    /// AS4_PATH (RFC 6793)
+    pub const AS4_PATH: u8 = 17;
+    /// AS4_AGGREGATOR (RFC 6793)
+    pub const AS4_AGGREGATOR: u8 = 18;
+    /// PMSI_TUNNEL (RFC 6514)
+    pub const PMSI_TUNNEL: u8 = 22;
+    /// AIGP (RFC 7311)
+    pub const AIGP: u8 = 26;
+    /// LARGE_COMMUNITY (RFC 8092)
+    pub const LARGE_COMMUNITY: u8 = 32;
+    /// OTC, Only to Customer (RFC 9234)
+    pub const OTC: u8 = 35;
+}
+
+/// Attribute flag bits (RFC 4271 section 4.3)
+pub mod attr_flags {
+    /// Optional (1) or well-known (0)
+    pub const OPTIONAL: u8 = 0x80;
+    /// Transitive (1) or non-transitive (0)
+    pub const TRANSITIVE: u8 = 0x40;
+    /// Partial (1) or complete (0)
+    pub const PARTIAL: u8 = 0x20;
+    /// Two-byte (1) or one-byte (0) attribute length
+    pub const EXTENDED_LENGTH: u8 = 0x10;
+}
+
+/// Subsequent address family identifiers (RFC 4760 and the IANA SAFI registry)
+pub mod safis {
+    /// Unicast forwarding
+    pub const UNICAST: u8 = 1;
+    /// Multicast forwarding
+    pub const MULTICAST: u8 = 2;
+    /// NLRI with MPLS labels (RFC 8277)
+    pub const MPLS_LABEL: u8 = 4;
+    /// BGP-LS link-state NLRI (RFC 7752)
+    pub const BGP_LS: u8 = 71;
+    /// MPLS-labeled VPN address (RFC 4364, RFC 4659)
+    pub const MPLS_VPN: u8 = 128;
+    /// Multicast for BGP/MPLS IP VPNs (RFC 6514)
+    pub const MULTICAST_VPN: u8 = 129;
+}
+
+/// Subsequent address family identifier, the typed form of the [`safis`]
+/// values.
+///
+/// Record fields keep the SAFI as a raw `u8`; convert with
+/// [`from_u8`](Self::from_u8) and [`to_u8`](Self::to_u8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Safi {
+    /// Unicast forwarding (1)
+    Unicast,
+    /// Multicast forwarding (2)
+    Multicast,
+    /// NLRI with MPLS labels (4)
+    MplsLabel,
+    /// BGP-LS link-state NLRI (71)
+    BgpLs,
+    /// MPLS-labeled VPN address (128)
+    MplsVpn,
+    /// Multicast for BGP/MPLS IP VPNs (129)
+    MulticastVpn,
+    /// Any other SAFI value
+    Other(u8),
+}
+
+impl Safi {
+    /// Convert a SAFI wire value; unnamed values become [`Safi::Other`].
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            safis::UNICAST => Safi::Unicast,
+            safis::MULTICAST => Safi::Multicast,
+            safis::MPLS_LABEL => Safi::MplsLabel,
+            safis::BGP_LS => Safi::BgpLs,
+            safis::MPLS_VPN => Safi::MplsVpn,
+            safis::MULTICAST_VPN => Safi::MulticastVpn,
+            other => Safi::Other(other),
+        }
+    }
+
+    /// The SAFI value as written on the wire (the inverse of
+    /// [`from_u8`](Self::from_u8)).
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            Safi::Unicast => safis::UNICAST,
+            Safi::Multicast => safis::MULTICAST,
+            Safi::MplsLabel => safis::MPLS_LABEL,
+            Safi::BgpLs => safis::BGP_LS,
+            Safi::MplsVpn => safis::MPLS_VPN,
+            Safi::MulticastVpn => safis::MULTICAST_VPN,
+            Safi::Other(value) => *value,
+        }
+    }
+}
+
+/// ORIGIN attribute value (RFC 4271 section 5.1.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// Learned via an interior gateway protocol (0)
+    Igp,
+    /// Learned via EGP (1)
+    Egp,
+    /// Learned by some other means (2)
+    Incomplete,
+}
+
+/// AS_PATH segment type (RFC 4271, RFC 5065).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
+pub enum SegmentType {
+    /// Unordered set of ASes (type 1)
+    AS_SET,
+    /// Ordered sequence of ASes (type 2)
+    AS_SEQUENCE,
+    /// Ordered confederation member ASes (type 3)
+    AS_CONFED_SEQUENCE,
+    /// Unordered confederation member ASes (type 4)
+    AS_CONFED_SET,
+}
+
+impl SegmentType {
+    /// Parse a segment type from its wire value.
+    fn from_u8(value: u8) -> std::io::Result<Self> {
+        match value {
+            1 => Ok(SegmentType::AS_SET),
+            2 => Ok(SegmentType::AS_SEQUENCE),
+            3 => Ok(SegmentType::AS_CONFED_SEQUENCE),
+            4 => Ok(SegmentType::AS_CONFED_SET),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid AS_PATH segment type")),
+        }
+    }
+
+    /// Returns `true` for the confederation segment types.
+    #[inline]
+    pub fn is_confed(&self) -> bool {
+        matches!(self, SegmentType::AS_CONFED_SEQUENCE | SegmentType::AS_CONFED_SET)
+    }
+
+    /// The wire value of this segment type.
+    fn to_u8(self) -> u8 {
+        match self {
+            SegmentType::AS_SET => 1,
+            SegmentType::AS_SEQUENCE => 2,
+            SegmentType::AS_CONFED_SEQUENCE => 3,
+            SegmentType::AS_CONFED_SET => 4,
+        }
+    }
+}
+
+/// Placeholder for 4-byte AS numbers on 2-byte sessions (RFC 6793)
+const AS_TRANS: u32 = 23456;
+
+/// Narrow an AS number to 2 bytes, substituting AS_TRANS if it does not fit.
+fn as2(asn: u32) -> u16 {
+    u16::try_from(asn).unwrap_or(AS_TRANS as u16)
+}
+
+/// A single AS_PATH segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsPathSegment {
+    /// Segment type
+    pub segment_type: SegmentType,
+    /// AS numbers in this segment (widened to 32 bits)
+    pub asns: Vec<u32>,
+}
+
+/// Decoded AS_PATH or AS4_PATH attribute.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AsPath {
+    /// Path segments in wire order
+    pub segments: Vec<AsPathSegment>,
+}
+
+impl AsPath {
+    /// Decode an AS_PATH attribute value.
+    ///
+    /// Format, repeated per segment:
+    /// - 1 byte: segment type
+    /// - 1 byte: number of ASes
+    /// - 2 or 4 bytes per AS, depending on `as4`
+    pub fn parse(value: &[u8], as4: bool) -> std::io::Result<Self> {
+        let asn_size = if as4 { 4 } else { 2 };
+        let mut segments = Vec::new();
+        let mut rest = value;
+
+        while !rest.is_empty() {
+            if rest.len() < 2 {
+                return Err(Error::new(ErrorKind::InvalidData, "truncated AS_PATH segment"));
+            }
+            let segment_type = SegmentType::from_u8(rest[0])?;
+            let count = rest[1] as usize;
+            let len = count * asn_size;
+            if rest.len() < 2 + len {
+                return Err(Error::new(ErrorKind::InvalidData, "truncated AS_PATH segment"));
+            }
+
+            let asns = rest[2..2 + len]
+                .chunks_exact(asn_size)
+                .map(|c| {
+                    if as4 {
+                        u32::from_be_bytes([c[0], c[1], c[2], c[3]])
+                    } else {
+                        u16::from_be_bytes([c[0], c[1]]) as u32
+                    }
+                })
+                .collect();
+            rest = &rest[2 + len..];
+
+            segments.push(AsPathSegment { segment_type, asns });
+        }
+
+        Ok(AsPath { segments })
+    }
+
+    /// Encode as an AS_PATH attribute value (the inverse of [`parse`](Self::parse)).
+    ///
+    /// With `as4 = false`, AS numbers above 65535 are written as AS_TRANS
+    /// (23456). Fails with `InvalidInput` if a segment holds more than 255
+    /// ASes.
+    pub fn encode(&self, as4: bool) -> std::io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for segment in &self.segments {
+            let count = u8::try_from(segment.asns.len()).map_err(|_| {
+                Error::new(ErrorKind::InvalidInput, "too many ASes in AS_PATH segment")
+            })?;
+            out.push(segment.segment_type.to_u8());
+            out.push(count);
+            for &asn in &segment.asns {
+                if as4 {
+                    out.extend_from_slice(&asn.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&as2(asn).to_be_bytes());
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Path length as used for route selection (RFC 4271 section 9.1.2.2).
+    ///
+    /// An AS_SEQUENCE counts each AS, an AS_SET counts as one, and
+    /// confederation segments do not count.
+    pub fn hop_count(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|s| match s.segment_type {
+                SegmentType::AS_SEQUENCE => s.asns.len(),
+                SegmentType::AS_SET => 1,
+                SegmentType::AS_CONFED_SEQUENCE | SegmentType::AS_CONFED_SET => 0,
+            })
+            .sum()
+    }
+
+    /// The origin AS: the last AS of the path.
+    ///
+    /// Returns `None` for an empty path, or if the path ends in an AS_SET or
+    /// a confederation segment, where the origin is not a single AS.
+    pub fn origin_asn(&self) -> Option<u32> {
+        let last = self.segments.last()?;
+        match last.segment_type {
+            SegmentType::AS_SEQUENCE => last.asns.last().copied(),
+            _ => None,
+        }
+    }
+}
+
+/// AS path length of a raw attribute blob, without decoding the path.
+///
+/// Finds the AS_PATH attribute and returns its length with the same counting
+/// rules as [`AsPath::hop_count`]: each AS in an AS_SEQUENCE counts, an
+/// AS_SET counts as one, and confederation segments are ignored. Returns
+/// `Ok(None)` if no AS_PATH is present. Nothing is allocated.
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::bgp::as_path_len;
+///
+/// // AS_PATH: AS_SEQUENCE 65001 65002, AS_SET {65003 65004}
+/// let blob: &[u8] = &[
+///     0x40, 0x02, 0x0C, 0x02, 0x02, 0xFD, 0xE9, 0xFD, 0xEA, 0x01, 0x02, 0xFD, 0xEB, 0xFD, 0xEC,
+/// ];
+/// assert_eq!(as_path_len(blob, false).unwrap(), Some(3));
+/// ```
+pub fn as_path_len(attributes: &[u8], as4: bool) -> std::io::Result<Option<usize>> {
+    let asn_size = if as4 { 4 } else { 2 };
+    for attr in attributes_iter(attributes) {
+        let attr = attr?;
+        if attr.type_code != attr_types::AS_PATH {
+            continue;
+        }
+
+        let mut len = 0;
+        let mut rest = attr.value;
+        while !rest.is_empty() {
+            if rest.len() < 2 {
+                return Err(Error::new(ErrorKind::InvalidData, "truncated AS_PATH segment"));
+            }
+            let segment_type = SegmentType::from_u8(rest[0])?;
+            let count = rest[1] as usize;
+            let segment_len = 2 + count * asn_size;
+            if rest.len() < segment_len {
+                return Err(Error::new(ErrorKind::InvalidData, "truncated AS_PATH segment"));
+            }
+            len += match segment_type {
+                SegmentType::AS_SEQUENCE => count,
+                SegmentType::AS_SET => 1,
+                SegmentType::AS_CONFED_SEQUENCE | SegmentType::AS_CONFED_SET => 0,
+            };
+            rest = &rest[segment_len..];
+        }
+        return Ok(Some(len));
+    }
+    Ok(None)
+}
+
+/// A single BGP path attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// Attribute flags (optional, transitive, partial, extended length)
+    pub flags: u8,
+    /// Attribute type code
+    pub type_code: u8,
+    /// Raw attribute value
+    pub value: Vec<u8>,
+}
+
+/// A path attribute borrowed from a raw attribute blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawAttributeRef<'a> {
+    /// Attribute flags (optional, transitive, partial, extended length)
+    pub flags: u8,
+    /// Attribute type code
+    pub type_code: u8,
+    /// Raw attribute value
+    pub value: &'a [u8],
+}
+
+impl RawAttributeRef<'_> {
+    /// Copy into an owned [`Attribute`].
+    pub fn to_attribute(&self) -> Attribute {
+        Attribute {
+            flags: self.flags,
+            type_code: self.type_code,
+            value: self.value.to_vec(),
+        }
+    }
+
+    /// Whether the flags match what RFC 4271 expects for this attribute type.
+    ///
+    /// Always `true` for type codes this module does not know.
+    pub fn flags_ok(&self) -> bool {
+        self.anomalies().iter().all(|a| !a.is_flag_anomaly())
+    }
+
+    /// Flag and length oddities of this attribute, see [`AttributeAnomaly`].
+    pub fn anomalies(&self) -> Vec<AttributeAnomaly> {
+        let mut anomalies = Vec::new();
+        push_anomalies(self.flags, self.type_code, self.value.len(), &mut anomalies);
+        anomalies
+    }
+}
+
+/// A deviation from RFC 4271 attribute encoding rules.
+///
+/// Reported by [`PathAttributes::anomalies`] and
+/// [`RawAttributeRef::anomalies`]. These are not parse errors: real-world BGP
+/// data contains plenty of them and the attributes still decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeAnomaly {
+    /// Optional bit disagrees with the attribute being well-known or optional
+    OptionalBit { type_code: u8 },
+    /// Transitive bit disagrees with the attribute category
+    TransitiveBit { type_code: u8 },
+    /// Partial bit set on a well-known or optional non-transitive attribute
+    PartialBit { type_code: u8 },
+    /// Extended-Length bit set on a value shorter than 256 bytes
+    ExtendedLength { type_code: u8 },
+    /// One of the four unused low-order flag bits is set
+    UnusedFlagBits { type_code: u8 },
+    /// Value length differs from the fixed length of the attribute type
+    Length { type_code: u8, length: usize },
+    /// Attribute type appears more than once
+    Duplicate { type_code: u8 },
+}
+
+impl AttributeAnomaly {
+    /// Type code of the attribute the anomaly concerns.
+    pub fn type_code(&self) -> u8 {
+        match *self {
+            AttributeAnomaly::OptionalBit { type_code }
+            | AttributeAnomaly::TransitiveBit { type_code }
+            | AttributeAnomaly::PartialBit { type_code }
+            | AttributeAnomaly::ExtendedLength { type_code }
+            | AttributeAnomaly::UnusedFlagBits { type_code }
+            | AttributeAnomaly::Length { type_code, .. }
+            | AttributeAnomaly::Duplicate { type_code } => type_code,
+        }
+    }
+
+    /// Whether the anomaly concerns the attribute flags byte.
+    pub fn is_flag_anomaly(&self) -> bool {
+        !matches!(
+            self,
+            AttributeAnomaly::Length { .. } | AttributeAnomaly::Duplicate { .. }
+        )
+    }
+}
+
+/// Expected `(optional, transitive)` bits of a known attribute type.
+fn expected_flags(type_code: u8) -> Option<(bool, bool)> {
+    use attr_types::*;
+
+    match type_code {
+        ORIGIN | AS_PATH | NEXT_HOP | LOCAL_PREF | ATOMIC_AGGREGATE => Some((false, true)),
+        AGGREGATOR | COMMUNITIES | EXTENDED_COMMUNITIES | AS4_PATH | AS4_AGGREGATOR
+        | PMSI_TUNNEL | LARGE_COMMUNITY | OTC => Some((true, true)),
+        MULTI_EXIT_DISC | ORIGINATOR_ID | CLUSTER_LIST | MP_REACH_NLRI | MP_UNREACH_NLRI
+        | AIGP => Some((true, false)),
+        _ => None,
+    }
+}
+
+/// Fixed value length of an attribute type, if it has one.
+fn expected_length(type_code: u8) -> Option<usize> {
+    use attr_types::*;
+
+    match type_code {
+        ORIGIN => Some(1),
+        NEXT_HOP | MULTI_EXIT_DISC | LOCAL_PREF | ORIGINATOR_ID | OTC => Some(4),
+        ATOMIC_AGGREGATE => Some(0),
+        AS4_AGGREGATOR => Some(8),
+        _ => None,
+    }
+}
+
+fn push_anomalies(flags: u8, type_code: u8, length: usize, out: &mut Vec<AttributeAnomaly>) {
+    if let Some((optional, transitive)) = expected_flags(type_code) {
+        if (flags & attr_flags::OPTIONAL != 0) != optional {
+            out.push(AttributeAnomaly::OptionalBit { type_code });
+        }
+        if (flags & attr_flags::TRANSITIVE != 0) != transitive {
+            out.push(AttributeAnomaly::TransitiveBit { type_code });
+        }
+        if flags & attr_flags::PARTIAL != 0 && !(optional && transitive) {
+            out.push(AttributeAnomaly::PartialBit { type_code });
+        }
+    }
+    if flags & attr_flags::EXTENDED_LENGTH != 0 && length < 256 {
+        out.push(AttributeAnomaly::ExtendedLength { type_code });
+    }
+    if flags & 0x0F != 0 {
+        out.push(AttributeAnomaly::UnusedFlagBits { type_code });
+    }
+    if expected_length(type_code).is_some_and(|expected| expected != length) {
+        out.push(AttributeAnomaly::Length { type_code, length });
+    }
+}
+
+/// Iterator over the attributes of a raw path attribute blob.
+///
+/// Created by [`attributes_iter`]. Yields an error and then stops if an
+/// attribute is truncated.
+#[derive(Debug, Clone)]
+pub struct AttributeIter<'a> {
+    rest: &'a [u8],
+}
+
+/// Iterate over a raw path attribute blob without allocating.
+///
+/// Each item borrows its value from `bytes`, so consumers can match on
+/// `type_code` and decode only the attributes they need. The extended-length
+/// flag is handled internally.
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::bgp::attributes::{attr_types, attributes_iter};
+///
+/// let blob: &[u8] = &[0x40, 0x01, 0x01, 0x00, 0x40, 0x03, 0x04, 192, 0, 2, 1];
+/// for attr in attributes_iter(blob) {
+///     let attr = attr.unwrap();
+///     if attr.type_code == attr_types::NEXT_HOP {
+///         assert_eq!(attr.value, &[192, 0, 2, 1]);
+///     }
+/// }
+/// ```
+pub fn attributes_iter(bytes: &[u8]) -> AttributeIter<'_> {
+    AttributeIter { rest: bytes }
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = std::io::Result<RawAttributeRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest;
+        if rest.is_empty() {
+            return None;
+        }
+        // Stop after reporting an error
+        self.rest = &[];
+
+        if rest.len() < 3 {
+            return Some(Err(Error::new(ErrorKind::InvalidData, "truncated path attribute header")));
+        }
+        let flags = rest[0];
+        let type_code = rest[1];
+
+        let (value_len, header_len) = if flags & attr_flags::EXTENDED_LENGTH != 0 {
+            if rest.len() < 4 {
+                return Some(Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "truncated path attribute header",
+                )));
+            }
+            (u16::from_be_bytes([rest[2], rest[3]]) as usize, 4)
+        } else {
+            (rest[2] as usize, 3)
+        };
+
+        if rest.len() < header_len + value_len {
+            return Some(Err(Error::new(ErrorKind::InvalidData, "truncated path attribute value")));
+        }
+        self.rest = &rest[header_len + value_len..];
+
+        Some(Ok(RawAttributeRef {
+            flags,
+            type_code,
+            value: &rest[header_len..header_len + value_len],
+        }))
+    }
+}
+
+/// Whether `raw` splits into exactly the given `(flags, type_code, value)`
+/// attributes.
+fn raw_matches<'b>(raw: &[u8], mut attributes: impl Iterator<Item = (u8, u8, &'b [u8])>) -> bool {
+    let mut parsed = attributes_iter(raw);
+    loop {
+        match (parsed.next(), attributes.next()) {
+            (None, None) => return true,
+            (Some(Ok(r)), Some((flags, type_code, value)))
+                if r.flags == flags && r.type_code == type_code && r.value == value => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Path attributes borrowed from a raw attribute blob.
+///
+/// The zero-copy counterpart of [`PathAttributes`]: attribute values borrow
+/// from the source bytes, which stay available through [`raw`](Self::raw).
+#[derive(Debug, Clone)]
+pub struct PathAttributesRef<'a> {
+    /// Whether AS numbers are 4 bytes wide
+    pub as4: bool,
+    /// Attributes in wire order
+    pub attributes: Vec<RawAttributeRef<'a>>,
+    raw: &'a [u8],
+}
+
+impl<'a> PathAttributesRef<'a> {
+    /// Split a raw path attribute blob into borrowed attributes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw path attributes
+    /// * `as4` - Whether AS numbers are encoded as 4 bytes
+    pub fn parse(bytes: &'a [u8], as4: bool) -> std::io::Result<Self> {
+        let attributes = attributes_iter(bytes).collect::<std::io::Result<_>>()?;
+
+        Ok(PathAttributesRef {
+            as4,
+            attributes,
+            raw: bytes,
+        })
+    }
+
+    /// The exact bytes passed to [`parse`](Self::parse), or `None` once
+    /// `attributes` has been changed and no longer matches them.
+    pub fn raw(&self) -> Option<&'a [u8]> {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|a| (a.flags, a.type_code, a.value));
+        raw_matches(self.raw, attributes).then_some(self.raw)
+    }
+
+    /// Returns the first attribute with the given type code, if present.
+    pub fn get(&self, type_code: u8) -> Option<&RawAttributeRef<'a>> {
+        self.attributes.iter().find(|a| a.type_code == type_code)
+    }
+
+    /// Copy into an owned [`PathAttributes`] with typed accessors.
+    pub fn to_path_attributes(&self) -> PathAttributes {
+        PathAttributes {
+            as4: self.as4,
+            attributes: self.attributes.iter().map(|a| a.to_attribute()).collect(),
+            raw: self.raw.to_vec(),
+        }
+    }
+}
+
+/// Decoded set of BGP path attributes.
+///
+/// The `as4` flag records whether AS numbers in AS_PATH and AGGREGATOR are
+/// encoded as 4 bytes (RFC 6793 "NEW" speakers, and all TABLE_DUMP_V2 RIB
+/// entries) or as 2 bytes (legacy sessions, TABLE_DUMP).
+#[derive(Debug, Clone)]
+pub struct PathAttributes {
+    /// Whether AS numbers are 4 bytes wide
+    pub as4: bool,
+    /// Attributes in wire order
+    pub attributes: Vec<Attribute>,
+    /// The blob the attributes were parsed from
+    raw: Vec<u8>,
+}
+
+impl PathAttributes {
+    /// Split a raw path attribute blob into individual attributes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw path attributes
+    /// * `as4` - Whether AS numbers are encoded as 4 bytes
+    pub fn parse(bytes: &[u8], as4: bool) -> std::io::Result<Self> {
+        let attributes = attributes_iter(bytes)
+            .map(|attr| attr.map(|attr| attr.to_attribute()))
+            .collect::<std::io::Result<_>>()?;
+
+        Ok(PathAttributes {
+            as4,
+            attributes,
+            raw: bytes.to_vec(),
+        })
+    }
+
+    /// The exact bytes passed to [`parse`](Self::parse).
+    ///
+    /// Useful for re-emitting attributes unchanged: decoded attributes do not
+    /// record every wire detail (for example an unneeded extended-length flag).
+    /// Returns `None` once `attributes` has been changed and no longer
+    /// matches the bytes; use [`encode`](Self::encode) then. The check is
+    /// one pass over the attributes.
+    pub fn raw(&self) -> Option<&[u8]> {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|a| (a.flags, a.type_code, a.value.as_slice()));
+        raw_matches(&self.raw, attributes).then_some(&self.raw)
+    }
+
+    /// Returns the first attribute with the given type code, if present.
+    pub fn get(&self, type_code: u8) -> Option<&Attribute> {
+        self.attributes.iter().find(|a| a.type_code == type_code)
+    }
+
+    /// AS_PATH attribute (type 2), decoded according to the `as4` flag.
+    pub fn as_path(&self) -> std::io::Result<Option<AsPath>> {
+        match self.get(attr_types::AS_PATH) {
+            None => Ok(None),
+            Some(attr) => AsPath::parse(&attr.value, self.as4).map(Some),
+        }
+    }
+
+    /// AS4_PATH attribute (type 17), always decoded with 4-byte AS numbers.
+    pub fn as4_path(&self) -> std::io::Result<Option<AsPath>> {
+        match self.get(attr_types::AS4_PATH) {
+            None => Ok(None),
+            Some(attr) => AsPath::parse(&attr.value, true).map(Some),
+        }
+    }
+
+    /// The full AS path, merging AS_PATH and AS4_PATH per RFC 6793 section 4.2.3.
+    ///
+    /// On a 2-byte session, ASes that do not fit in 16 bits appear in AS_PATH
+    /// as AS_TRANS (23456) and the real values are carried in AS4_PATH. This
+    /// keeps the leading `hop_count(AS_PATH) - hop_count(AS4_PATH)` ASes of
+    /// AS_PATH and appends the AS4_PATH segments.
+    ///
+    /// AS4_PATH is ignored when the attributes are `as4`-encoded, when it is
+    /// longer than AS_PATH, or when AS_PATH is absent. Confederation segments
+    /// in AS4_PATH are discarded as required by the RFC.
+    pub fn effective_as_path(&self) -> std::io::Result<Option<AsPath>> {
+        let as_path = match self.as_path()? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if self.as4 {
+            return Ok(Some(as_path));
+        }
+        let as4_path = match self.as4_path()? {
+            Some(path) => AsPath {
+                segments: path
+                    .segments
+                    .into_iter()
+                    .filter(|s| !s.segment_type.is_confed())
+                    .collect(),
+            },
+            None => return Ok(Some(as_path)),
+        };
+
+        let as_path_len = as_path.hop_count();
+        let as4_path_len = as4_path.hop_count();
+        if as_path_len < as4_path_len {
+            return Ok(Some(as_path));
+        }
+
+        // Keep the leading ASes of AS_PATH, honoring segment boundaries
+        let mut keep = as_path_len - as4_path_len;
+        let mut segments: Vec<AsPathSegment> = Vec::new();
+        for mut segment in as_path.segments {
+            match segment.segment_type {
+                SegmentType::AS_CONFED_SEQUENCE | SegmentType::AS_CONFED_SET => {
+                    segments.push(segment);
+                }
+                _ if keep == 0 => break,
+                SegmentType::AS_SET => {
+                    segments.push(segment);
+                    keep -= 1;
+                }
+                SegmentType::AS_SEQUENCE => {
+                    let take = keep.min(segment.asns.len());
+                    segment.asns.truncate(take);
+                    segments.push(segment);
+                    keep -= take;
+                }
+            }
+        }
+
+        // Join the seam if both sides are sequences
+        let mut as4_segments = as4_path.segments.into_iter().peekable();
+        if let Some(last) = segments.last_mut()
+            && last.segment_type == SegmentType::AS_SEQUENCE
+            && let Some(first) =
+                as4_segments.next_if(|s| s.segment_type == SegmentType::AS_SEQUENCE)
+        {
+            last.asns.extend(first.asns);
+        }
+        segments.extend(as4_segments);
+
+        Ok(Some(AsPath { segments }))
+    }
+
+    /// NEXT_HOP attribute (type 3).
+    ///
+    /// Returns `Ok(None)` when absent and an error when the value is not a
+    /// 4-byte IPv4 address.
+    pub fn next_hop(&self) -> std::io::Result<Option<Ipv4Addr>> {
+        let value = self.fixed::<4>(attr_types::NEXT_HOP, "invalid NEXT_HOP length")?;
+        Ok(value.map(Ipv4Addr::from))
+    }
+
+    /// ORIGIN attribute (type 1).
+    ///
+    /// Returns an error if the value is not exactly 1 byte or is not a
+    /// defined origin code.
+    pub fn origin(&self) -> std::io::Result<Option<Origin>> {
+        match self.fixed::<1>(attr_types::ORIGIN, "invalid ORIGIN length")? {
+            None => Ok(None),
+            Some([0]) => Ok(Some(Origin::Igp)),
+            Some([1]) => Ok(Some(Origin::Egp)),
+            Some([2]) => Ok(Some(Origin::Incomplete)),
+            Some(_) => Err(Error::new(ErrorKind::InvalidData, "invalid ORIGIN value")),
+        }
+    }
+
+    /// MULTI_EXIT_DISC attribute (type 4).
+    ///
+    /// Returns an error if the value is not exactly 4 bytes.
+    pub fn med(&self) -> std::io::Result<Option<u32>> {
+        let value =
+            self.fixed::<4>(attr_types::MULTI_EXIT_DISC, "invalid MULTI_EXIT_DISC length")?;
+        Ok(value.map(u32::from_be_bytes))
+    }
+
+    /// LOCAL_PREF attribute (type 5).
+    ///
+    /// Returns an error if the value is not exactly 4 bytes.
+    pub fn local_pref(&self) -> std::io::Result<Option<u32>> {
+        let value = self.fixed::<4>(attr_types::LOCAL_PREF, "invalid LOCAL_PREF length")?;
+        Ok(value.map(u32::from_be_bytes))
+    }
+
+    /// Whether the ATOMIC_AGGREGATE attribute (type 6) is present.
+    pub fn atomic_aggregate(&self) -> bool {
+        self.get(attr_types::ATOMIC_AGGREGATE).is_some()
+    }
+
+    /// AGGREGATOR attribute (type 7) as `(asn, address)`.
+    ///
+    /// The AS number is 2 bytes (6-byte value) or 4 bytes (8-byte value)
+    /// depending on the `as4` flag. On a 2-byte session the AS number may be
+    /// AS_TRANS (23456), with the real value carried separately in
+    /// [`as4_aggregator`](Self::as4_aggregator); the two are exposed as-is
+    /// and not merged.
+    pub fn aggregator(&self) -> std::io::Result<Option<(u32, Ipv4Addr)>> {
+        match self.get(attr_types::AGGREGATOR) {
+            None => Ok(None),
+            Some(attr) => decode_aggregator(&attr.value, self.as4).map(Some),
+        }
+    }
+
+    /// AS4_AGGREGATOR attribute (type 18) as `(asn, address)`.
+    ///
+    /// Always uses a 4-byte AS number regardless of the `as4` flag. Only
+    /// meaningful alongside an AGGREGATOR carrying AS_TRANS on a 2-byte
+    /// session.
+    pub fn as4_aggregator(&self) -> std::io::Result<Option<(u32, Ipv4Addr)>> {
+        match self.get(attr_types::AS4_AGGREGATOR) {
+            None => Ok(None),
+            Some(attr) => decode_aggregator(&attr.value, true).map(Some),
+        }
+    }
+
+    /// ORIGINATOR_ID attribute (type 9), set by route reflectors.
+    ///
+    /// Returns an error if the value is not a 4-byte router ID.
+    pub fn originator_id(&self) -> std::io::Result<Option<Ipv4Addr>> {
+        let value = self.fixed::<4>(attr_types::ORIGINATOR_ID, "invalid ORIGINATOR_ID length")?;
+        Ok(value.map(Ipv4Addr::from))
+    }
+
+    /// CLUSTER_LIST attribute (type 10) as the list of cluster IDs the
+    /// route has been reflected through, most recent first.
+    ///
+    /// Returns an error if the length is not a multiple of 4.
+    pub fn cluster_list(&self) -> std::io::Result<Option<Vec<Ipv4Addr>>> {
+        let attr = match self.get(attr_types::CLUSTER_LIST) {
+            None => return Ok(None),
+            Some(attr) => attr,
+        };
+        if attr.value.len() % 4 != 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid CLUSTER_LIST length"));
+        }
+        let ids = attr
+            .value
+            .chunks_exact(4)
+            .map(|id| Ipv4Addr::new(id[0], id[1], id[2], id[3]))
+            .collect();
+        Ok(Some(ids))
+    }
+
+    /// MP_REACH_NLRI attribute (type 14) in its full RFC 4760 form.
+    pub fn mp_reach_nlri(&self) -> std::io::Result<Option<MpReachNlri>> {
+        match self.get(attr_types::MP_REACH_NLRI) {
+            None => Ok(None),
+            Some(attr) => MpReachNlri::parse(&attr.value).map(Some),
+        }
+    }
+
+    /// MP_UNREACH_NLRI attribute (type 15).
+    pub fn mp_unreach_nlri(&self) -> std::io::Result<Option<MpUnreachNlri>> {
+        match self.get(attr_types::MP_UNREACH_NLRI) {
+            None => Ok(None),
+            Some(attr) => MpUnreachNlri::parse(&attr.value).map(Some),
+        }
+    }
+
+    /// PMSI_TUNNEL attribute (type 22), used by multicast VPNs and EVPN.
+    ///
+    /// Any tunnel type is accepted; the identifier is returned raw. Returns
+    /// an error if the value is shorter than the 5 fixed bytes.
+    pub fn pmsi_tunnel(&self) -> std::io::Result<Option<PmsiTunnel>> {
+        match self.get(attr_types::PMSI_TUNNEL) {
+            None => Ok(None),
+            Some(attr) => PmsiTunnel::parse(&attr.value).map(Some),
+        }
+    }
+
+    /// Accumulated IGP metric from the AIGP attribute (type 26, RFC 7311).
+    ///
+    /// The attribute holds a list of TLVs:
+    /// - 1 byte: TLV type
+    /// - 2 bytes: TLV length, including these 3 header bytes
+    /// - variable: value (8-byte metric for type 1)
+    ///
+    /// Returns the metric of the first type 1 TLV; other TLV types are
+    /// skipped. Returns `Ok(None)` if the attribute or the metric TLV is
+    /// absent, and an error if a TLV length is out of bounds or a type 1
+    /// TLV is not 11 bytes long.
+    pub fn aigp(&self) -> std::io::Result<Option<u64>> {
+        const AIGP_TLV: u8 = 1;
+
+        let attr = match self.get(attr_types::AIGP) {
+            None => return Ok(None),
+            Some(attr) => attr,
+        };
+        let mut rest = &attr.value[..];
+        while !rest.is_empty() {
+            if rest.len() < 3 {
+                return Err(Error::new(ErrorKind::InvalidData, "truncated AIGP TLV"));
+            }
+            let tlv_len = u16::from_be_bytes([rest[1], rest[2]]) as usize;
+            if tlv_len < 3 || tlv_len > rest.len() {
+                return Err(Error::new(ErrorKind::InvalidData, "invalid AIGP TLV length"));
+            }
+            if rest[0] == AIGP_TLV {
+                let metric: [u8; 8] = rest[3..tlv_len].try_into().map_err(|_| {
+                    Error::new(ErrorKind::InvalidData, "invalid AIGP metric length")
+                })?;
+                return Ok(Some(u64::from_be_bytes(metric)));
+            }
+            rest = &rest[tlv_len..];
+        }
+        Ok(None)
+    }
+
+    /// OTC attribute (type 35, RFC 9234): the AS that marked the route as
+    /// Only to Customer.
+    ///
+    /// Its presence on a route received from a customer or lateral peer
+    /// indicates a route leak. Returns an error if the value is not exactly
+    /// 4 bytes.
+    pub fn otc(&self) -> std::io::Result<Option<u32>> {
+        let value = self.fixed::<4>(attr_types::OTC, "invalid OTC length")?;
+        Ok(value.map(u32::from_be_bytes))
+    }
+
+    /// Serialize the attributes back into a path attribute blob.
+    ///
+    /// Attributes are written in ascending type code order. Flags are
+    /// normalized: known types get the optional and transitive bits RFC 4271
+    /// and later RFCs assign them, unknown types keep their own, the partial
+    /// bit is kept only on optional transitive attributes, and the extended
+    /// length bit is set exactly when a value is longer than 255 bytes.
+    ///
+    /// AS_PATH and AGGREGATOR are re-encoded when `as4` differs from
+    /// [`as4`](Self::as4). When narrowing to 2 bytes, AS numbers that do not
+    /// fit become AS_TRANS and the real values are carried in an AS4_PATH
+    /// (without confederation segments) or AS4_AGGREGATOR, replacing any
+    /// already present, as RFC 6793 section 4.2.2 describes. Fails with
+    /// `InvalidInput` if a value is longer than 65535 bytes, and with
+    /// `InvalidData` if an attribute that needs re-encoding is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mrt_ingester::bgp::PathAttributes;
+    ///
+    /// // NEXT_HOP, then ORIGIN with a stray extended-length bit
+    /// let data = [0x40, 0x03, 0x04, 10, 0, 0, 1, 0x50, 0x01, 0x00, 0x01, 0x00];
+    /// let attrs = PathAttributes::parse(&data, true).unwrap();
+    /// let encoded = attrs.encode(true).unwrap();
+    /// assert_eq!(encoded, [0x40, 0x01, 0x01, 0x00, 0x40, 0x03, 0x04, 10, 0, 0, 1]);
+    /// ```
+    pub fn encode(&self, as4: bool) -> std::io::Result<Vec<u8>> {
+        let narrowing = self.as4 && !as4;
+        let mut attributes = Vec::with_capacity(self.attributes.len());
+        // AS4_PATH and AS4_AGGREGATOR carrying ASes that narrowing replaced
+        let mut as4_attributes = Vec::new();
+        for attr in &self.attributes {
+            let value: Cow<[u8]> = match attr.type_code {
+                attr_types::AS_PATH if as4 != self.as4 => {
+                    let path = AsPath::parse(&attr.value, self.as4)?;
+                    let mut asns = path.segments.iter().flat_map(|s| &s.asns);
+                    if narrowing && asns.any(|&asn| asn > u16::MAX as u32) {
+                        let as4_path = AsPath {
+                            segments: path
+                                .segments
+                                .iter()
+                                .filter(|s| {
+                                    matches!(
+                                        s.segment_type,
+                                        SegmentType::AS_SEQUENCE | SegmentType::AS_SET
+                                    )
+                                })
+                                .cloned()
+                                .collect(),
+                        };
+                        as4_attributes.push((attr_types::AS4_PATH, as4_path.encode(true)?));
+                    }
+                    Cow::Owned(path.encode(as4)?)
+                }
+                attr_types::AGGREGATOR if as4 != self.as4 => {
+                    let (asn, ip) = decode_aggregator(&attr.value, self.as4)?;
+                    if narrowing && asn > u16::MAX as u32 {
+                        let value = encode_aggregator(asn, ip, true);
+                        as4_attributes.push((attr_types::AS4_AGGREGATOR, value));
+                    }
+                    Cow::Owned(encode_aggregator(asn, ip, as4))
+                }
+                _ => Cow::Borrowed(&attr.value),
+            };
+            attributes.push((attr.flags, attr.type_code, value));
+        }
+        for (type_code, value) in as4_attributes {
+            attributes.retain(|(_, t, _)| *t != type_code);
+            let flags = attr_flags::OPTIONAL | attr_flags::TRANSITIVE;
+            attributes.push((flags, type_code, Cow::Owned(value)));
+        }
+        attributes.sort_by_key(|(_, type_code, _)| *type_code);
+
+        let mut out = Vec::new();
+        for (flags, type_code, value) in attributes {
+            write_attribute(&mut out, flags, type_code, &value)?;
+        }
+        Ok(out)
+    }
+
+    /// Flag, length and duplicate-type oddities, in wire order.
+    ///
+    /// Nothing here is treated as an error; see [`AttributeAnomaly`].
+    /// AGGREGATOR is not length-checked since its size depends on `as4`.
+    pub fn anomalies(&self) -> Vec<AttributeAnomaly> {
+        let mut anomalies = Vec::new();
+        let mut seen = [false; 256];
+        for attr in &self.attributes {
+            push_anomalies(attr.flags, attr.type_code, attr.value.len(), &mut anomalies);
+            if std::mem::replace(&mut seen[attr.type_code as usize], true) {
+                anomalies.push(AttributeAnomaly::Duplicate {
+                    type_code: attr.type_code,
+                });
+            }
+        }
+        anomalies
+    }
+
+    /// Fetch a fixed-size attribute value, erroring with `msg` on a length mismatch.
+    fn fixed<const N: usize>(
+        &self,
+        type_code: u8,
+        msg: &str,
+    ) -> std::io::Result<Option<[u8; N]>> {
+        match self.get(type_code) {
+            None => Ok(None),
+            Some(attr) => attr
+                .value
+                .as_slice()
+                .try_into()
+                .map(Some)
+                .map_err(|_| Error::new(ErrorKind::InvalidData, msg)),
+        }
+    }
+}
+
+/// MP_REACH_NLRI attribute (RFC 4760 section 3).
+///
+/// This is the full form found in BGP UPDATE messages. TABLE_DUMP_V2 RIB
+/// entries use an abbreviated form holding only the next hop length and
+/// next hop (RFC 6396 section 4.3.4), which this type does not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MpReachNlri {
+    /// Address family identifier (kept raw; not limited to IPv4/IPv6)
+    pub afi: u16,
+    /// Subsequent address family identifier (see [`safis`])
+    pub safi: u8,
+    /// Raw next hop field
+    pub next_hop: Vec<u8>,
+    /// Raw NLRI field
+    pub nlri: Vec<u8>,
+}
+
+/// PMSI_TUNNEL attribute (RFC 6514 section 5).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PmsiTunnel {
+    /// Flags (bit 0x01: leaf information required)
+    pub flags: u8,
+    /// Tunnel type, e.g. 6 for ingress replication (IANA "P-Multicast
+    /// Service Interface Tunnel (PMSI Tunnel) Tunnel Types")
+    pub tunnel_type: u8,
+    /// MPLS label (high-order 20 bits of the 3-byte field)
+    pub label: u32,
+    /// Tunnel identifier, whose format depends on `tunnel_type`
+    pub tunnel_id: Vec<u8>,
+}
+
+impl PmsiTunnel {
+    /// Parse a PMSI_TUNNEL attribute value.
+    ///
+    /// Format:
+    /// - 1 byte: flags
+    /// - 1 byte: tunnel type
+    /// - 3 bytes: MPLS label field
+    /// - remaining: tunnel identifier
+    pub fn parse(value: &[u8]) -> std::io::Result<Self> {
+        if value.len() < 5 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated PMSI_TUNNEL"));
+        }
+        Ok(PmsiTunnel {
+            flags: value[0],
+            tunnel_type: value[1],
+            label: u32::from_be_bytes([0, value[2], value[3], value[4]]) >> 4,
+            tunnel_id: value[5..].to_vec(),
+        })
+    }
+}
+
+/// A VPN route from an MPLS VPN NLRI (RFC 4364 section 4.3.4, RFC 8277).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpnPrefix {
+    /// Route distinguisher
+    pub rd: [u8; 8],
+    /// MPLS label values (20 bits each), outermost first
+    pub labels: Vec<u32>,
+    /// The IP prefix
+    pub prefix: Prefix,
+}
+
+impl MpReachNlri {
+    /// Parse an MP_REACH_NLRI attribute value.
+    ///
+    /// Format:
+    /// - 2 bytes: AFI
+    /// - 1 byte: SAFI
+    /// - 1 byte: next hop length
+    /// - variable: next hop
+    /// - 1 byte: reserved
+    /// - remaining: NLRI
+    pub fn parse(value: &[u8]) -> std::io::Result<Self> {
+        if value.len() < 5 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated MP_REACH_NLRI"));
+        }
+        let afi = u16::from_be_bytes([value[0], value[1]]);
+        let safi = value[2];
+        let next_hop_len = value[3] as usize;
+        // Next hop plus the reserved byte
+        if value.len() < 5 + next_hop_len {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated MP_REACH_NLRI next hop"));
+        }
+        let next_hop = value[4..4 + next_hop_len].to_vec();
+        let nlri = value[5 + next_hop_len..].to_vec();
+
+        Ok(MpReachNlri {
+            afi,
+            safi,
+            next_hop,
+            nlri,
+        })
+    }
+
+    /// Whether the SAFI carries route-distinguisher-prefixed VPN addresses.
+    fn is_vpn(&self) -> bool {
+        matches!(self.safi, safis::MPLS_VPN | safis::MULTICAST_VPN)
+    }
+
+    /// The next hop address.
+    ///
+    /// For VPN SAFIs the next hop is prefixed by an 8-byte route
+    /// distinguisher (always zero), which is stripped. When both a global
+    /// and a link-local IPv6 address are present, the global one is returned.
+    pub fn decoded_next_hop(&self) -> std::io::Result<IpAddr> {
+        let next_hop = if self.is_vpn() {
+            if !matches!(self.next_hop.len(), 12 | 24 | 48) {
+                return Err(Error::new(ErrorKind::InvalidData, "invalid VPN next hop length"));
+            }
+            &self.next_hop[8..]
+        } else {
+            &self.next_hop[..]
+        };
+
+        match next_hop.len() {
+            4 => Ok(IpAddr::V4(Ipv4Addr::new(
+                next_hop[0],
+                next_hop[1],
+                next_hop[2],
+                next_hop[3],
+            ))),
+            // Global address, optionally followed by a link-local address
+            // (itself RD-prefixed for VPNs)
+            16 | 32 | 40 => {
+                let octets: [u8; 16] = next_hop[..16].try_into().unwrap();
+                Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid next hop length")),
+        }
+    }
+
+    /// Decode the NLRI of an MPLS VPN route (SAFI 128).
+    ///
+    /// Each NLRI holds a label stack (3 bytes per label, ending at the
+    /// bottom-of-stack bit), a route distinguisher and the prefix.
+    pub fn vpn_prefixes(&self) -> std::io::Result<Vec<VpnPrefix>> {
+        if self.safi != safis::MPLS_VPN {
+            return Err(Error::new(ErrorKind::InvalidInput, "not an MPLS VPN SAFI"));
+        }
+        decode_vpn_prefixes(&AFI::from_u16(self.afi)?, &self.nlri)
+    }
+
+    /// Decode the NLRI of a unicast or multicast route as plain prefixes.
+    ///
+    /// Returns an `InvalidInput` error for other SAFIs, whose NLRI carries
+    /// labels or route distinguishers.
+    pub fn prefixes(&self) -> std::io::Result<Vec<Prefix>> {
+        decode_nlri_prefixes(self.afi, self.safi, &self.nlri)
+    }
+}
+
+/// MP_UNREACH_NLRI attribute (RFC 4760 section 4).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MpUnreachNlri {
+    /// Address family identifier (kept raw; not limited to IPv4/IPv6)
+    pub afi: u16,
+    /// Subsequent address family identifier (see [`safis`])
+    pub safi: u8,
+    /// Raw withdrawn routes field
+    pub withdrawn: Vec<u8>,
+}
+
+impl MpUnreachNlri {
+    /// Parse an MP_UNREACH_NLRI attribute value.
+    ///
+    /// Format:
+    /// - 2 bytes: AFI
+    /// - 1 byte: SAFI
+    /// - remaining: withdrawn routes
+    pub fn parse(value: &[u8]) -> std::io::Result<Self> {
+        if value.len() < 3 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated MP_UNREACH_NLRI"));
+        }
+        Ok(MpUnreachNlri {
+            afi: u16::from_be_bytes([value[0], value[1]]),
+            safi: value[2],
+            withdrawn: value[3..].to_vec(),
+        })
+    }
+
+    /// Decode the withdrawn routes of a unicast or multicast SAFI as plain
+    /// prefixes, as [`MpReachNlri::prefixes`] does.
+    pub fn prefixes(&self) -> std::io::Result<Vec<Prefix>> {
+        decode_nlri_prefixes(self.afi, self.safi, &self.withdrawn)
+    }
+}
+
+/// Decode NLRI of a plain-prefix SAFI.
+fn decode_nlri_prefixes(afi: u16, safi: u8, nlri: &[u8]) -> std::io::Result<Vec<Prefix>> {
+    if !matches!(safi, safis::UNICAST | safis::MULTICAST) {
+        return Err(Error::new(ErrorKind::InvalidInput, "not a unicast or multicast SAFI"));
+    }
+    decode_prefixes(&AFI::from_u16(afi)?, nlri)
+}
+
+/// Decode a sequence of length-prefixed prefixes (RFC 4271 section 4.3).
+///
+/// Format, repeated:
+/// - 1 byte: prefix length in bits
+/// - `ceil(length / 8)` bytes: prefix
+pub(crate) fn decode_prefixes(afi: &AFI, mut bytes: &[u8]) -> std::io::Result<Vec<Prefix>> {
+    let mut prefixes = Vec::new();
+    while let Some((&bits, tail)) = bytes.split_first() {
+        let len = (bits as usize).div_ceil(8);
+        if tail.len() < len {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated NLRI prefix"));
+        }
+        prefixes.push(Prefix::from_wire(afi, bits, &tail[..len])?);
+        bytes = &tail[len..];
+    }
+    Ok(prefixes)
+}
+
+/// Decode MPLS VPN NLRI; see [`MpReachNlri::vpn_prefixes`].
+pub(crate) fn decode_vpn_prefixes(afi: &AFI, nlri: &[u8]) -> std::io::Result<Vec<VpnPrefix>> {
+    let mut prefixes = Vec::new();
+    let mut rest = nlri;
+    while let Some((&bits, tail)) = rest.split_first() {
+        let truncated = || Error::new(ErrorKind::InvalidData, "truncated VPN NLRI");
+        let len = (bits as usize).div_ceil(8);
+        if tail.len() < len {
+            return Err(truncated());
+        }
+        let (mut nlri, next) = tail.split_at(len);
+        rest = next;
+
+        let mut labels = Vec::new();
+        loop {
+            if nlri.len() < 3 {
+                return Err(truncated());
+            }
+            let raw = u32::from_be_bytes([0, nlri[0], nlri[1], nlri[2]]);
+            nlri = &nlri[3..];
+            labels.push(raw >> 4);
+            // Bottom of stack, or the 0x800000 withdrawal label (RFC 3107)
+            if raw & 1 != 0 || raw == 0x800000 {
+                break;
+            }
+        }
+
+        if nlri.len() < 8 {
+            return Err(truncated());
+        }
+        let rd: [u8; 8] = nlri[..8].try_into().unwrap();
+        let prefix_bits = (bits as usize)
+            .checked_sub(8 * (len - nlri.len() + 8))
+            .ok_or_else(truncated)?;
+        let prefix = Prefix::from_wire(afi, prefix_bits as u8, &nlri[8..])?;
+
+        prefixes.push(VpnPrefix { rd, labels, prefix });
+    }
+
+    Ok(prefixes)
+}
+
+/// Decode an AGGREGATOR / AS4_AGGREGATOR value.
+fn decode_aggregator(value: &[u8], as4: bool) -> std::io::Result<(u32, Ipv4Addr)> {
+    match (as4, value.len()) {
+        (false, 6) => Ok((
+            u16::from_be_bytes([value[0], value[1]]) as u32,
+            Ipv4Addr::new(value[2], value[3], value[4], value[5]),
+        )),
+        (true, 8) => Ok((
+            u32::from_be_bytes([value[0], value[1], value[2], value[3]]),
+            Ipv4Addr::new(value[4], value[5], value[6], value[7]),
+        )),
+        _ => Err(Error::new(ErrorKind::InvalidData, "invalid AGGREGATOR length")),
+    }
+}
+
+/// Encode an AGGREGATOR value (the inverse of [`decode_aggregator`]).
+fn encode_aggregator(asn: u32, ip: Ipv4Addr, as4: bool) -> Vec<u8> {
+    let mut value = match as4 {
+        true => asn.to_be_bytes().to_vec(),
+        false => as2(asn).to_be_bytes().to_vec(),
+    };
+    value.extend_from_slice(&ip.octets());
+    value
+}
+
+/// Append one attribute with normalized flags (see [`PathAttributes::encode`]).
+fn write_attribute(
+    out: &mut Vec<u8>,
+    flags: u8,
+    type_code: u8,
+    value: &[u8],
+) -> std::io::Result<()> {
+    let len = u16::try_from(value.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "attribute value too long"))?;
+    let (optional, transitive) = expected_flags(type_code).unwrap_or((
+        flags & attr_flags::OPTIONAL != 0,
+        flags & attr_flags::TRANSITIVE != 0,
+    ));
+
+    let mut normalized = 0;
+    if optional {
+        normalized |= attr_flags::OPTIONAL;
+    }
+    if transitive {
+        normalized |= attr_flags::TRANSITIVE;
+    }
+    if optional && transitive {
+        normalized |= flags & attr_flags::PARTIAL;
+    }
+
+    if len > 255 {
+        out.extend_from_slice(&[normalized | attr_flags::EXTENDED_LENGTH, type_code]);
+        out.extend_from_slice(&len.to_be_bytes());
+    } else {
+        out.extend_from_slice(&[normalized, type_code, len as u8]);
+    }
+    out.extend_from_slice(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_attributes() {
+        let data: &[u8] = &[
+            0x40, 0x03, 0x04, 10, 0, 0, 1, // NEXT_HOP = 10.0.0.1
+            0x90, 0x07, 0x00, 0x06, 0xFD, 0xE8, 192, 0, 2, 1, // AGGREGATOR (extended length)
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        assert_eq!(attrs.attributes.len(), 2);
+        assert_eq!(attrs.next_hop().unwrap(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(
+            attrs.aggregator().unwrap(),
+            Some((65000, Ipv4Addr::new(192, 0, 2, 1)))
+        );
+        assert_eq!(attrs.as4_aggregator().unwrap(), None);
+    }
+
+    #[test]
+    fn test_raw_preserves_source_bytes() {
+        let data: &[u8] = &[
+            0x50, 0x01, 0x00, 0x01, 0x00, // ORIGIN with a needless extended length
+            0x40, 0x03, 0x04, 10, 0, 0, 1, // NEXT_HOP = 10.0.0.1
+        ];
+        let mut attrs = PathAttributes::parse(data, false).unwrap();
+        assert_eq!(attrs.raw(), Some(data));
+        assert_eq!(attrs.origin().unwrap(), Some(Origin::Igp));
+
+        let mut borrowed = PathAttributesRef::parse(data, false).unwrap();
+        assert_eq!(borrowed.raw(), Some(data));
+        assert_eq!(borrowed.get(attr_types::NEXT_HOP).unwrap().value, &[10, 0, 0, 1]);
+
+        let owned = borrowed.to_path_attributes();
+        assert_eq!(owned.raw(), Some(data));
+        assert_eq!(owned.next_hop().unwrap(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+
+        // Changed attributes no longer match the source bytes
+        attrs.attributes[1].value = vec![10, 0, 0, 2];
+        assert_eq!(attrs.raw(), None);
+        attrs.attributes[1].value = vec![10, 0, 0, 1];
+        assert_eq!(attrs.raw(), Some(data));
+        attrs.attributes.pop();
+        assert_eq!(attrs.raw(), None);
+        borrowed.attributes.pop();
+        assert_eq!(borrowed.raw(), None);
+        assert_eq!(borrowed.to_path_attributes().raw(), None);
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let data: &[u8] = &[
+            0x40, 0x01, 0x01, 0x00, // ORIGIN, well-formed
+            0xC0, 0x03, 0x04, 10, 0, 0, 1, // NEXT_HOP with the Optional bit
+            0x50, 0x06, 0x00, 0x00, // ATOMIC_AGGREGATE, needless extended length
+            0x80, 0x04, 0x02, 0x00, 0x01, // MED of 2 bytes
+            0x40, 0x01, 0x01, 0x02, // second ORIGIN
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        assert!(attrs.atomic_aggregate());
+        assert_eq!(
+            attrs.anomalies(),
+            vec![
+                AttributeAnomaly::OptionalBit { type_code: 3 },
+                AttributeAnomaly::ExtendedLength { type_code: 6 },
+                AttributeAnomaly::Length { type_code: 4, length: 2 },
+                AttributeAnomaly::Duplicate { type_code: 1 },
+            ]
+        );
+
+        let flags: Vec<bool> = attributes_iter(data).map(|a| a.unwrap().flags_ok()).collect();
+        assert_eq!(flags, [true, false, false, true, true]);
+    }
+
+    #[test]
+    fn test_as_path_as4() {
+        let data: &[u8] = &[
+            0x40, 0x02, 0x0C, // AS_PATH, length 12
+            0x02, 0x02, 0x00, 0x00, 0xFD, 0xE8, 0x00, 0x01, 0x00, 0x00, // SEQ 65000 65536
+            0x01, 0x00, // empty SET
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let path = attrs.as_path().unwrap().unwrap();
+        assert_eq!(path.segments.len(), 2);
+        assert_eq!(path.segments[0].segment_type, SegmentType::AS_SEQUENCE);
+        assert_eq!(path.segments[0].asns, vec![65000, 65536]);
+        assert_eq!(path.hop_count(), 3);
+    }
+
+    #[test]
+    fn test_as_path_len() {
+        let data: &[u8] = &[
+            0x40, 0x01, 0x01, 0x00, // ORIGIN
+            0x40, 0x02, 0x10, // AS_PATH, length 16
+            0x02, 0x02, 0x00, 0x00, 0xFD, 0xE8, 0x00, 0x01, 0x00, 0x00, // SEQ 65000 65536
+            0x03, 0x01, 0x00, 0x00, 0xFD, 0xE9, // CONFED_SEQ 65001
+        ];
+        let expected = PathAttributes::parse(data, true).unwrap().as_path().unwrap().unwrap();
+        assert_eq!(as_path_len(data, true).unwrap(), Some(expected.hop_count()));
+        assert_eq!(as_path_len(data, true).unwrap(), Some(2));
+
+        assert_eq!(as_path_len(&data[..4], true).unwrap(), None);
+        // AS_SEQUENCE claims two ASes but carries one
+        let truncated: &[u8] = &[0x40, 0x02, 0x04, 0x02, 0x02, 0xFD, 0xE8];
+        assert!(as_path_len(truncated, false).is_err());
+    }
+
+    #[test]
+    fn test_effective_as_path_merges_as4_path() {
+        let data: &[u8] = &[
+            0x40, 0x02, 0x08, 0x02, 0x03, 0x00, 0x64, 0x5B, 0xA0, 0x5B, 0xA0, // AS_PATH 100 AS_TRANS AS_TRANS
+            0xC0, 0x11, 0x0A, 0x02, 0x02, 0x00, 0x03, 0x0D, 0x40, 0x00, 0x04, 0x93, 0xE0, // AS4_PATH 200000 300000
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        let path = attrs.effective_as_path().unwrap().unwrap();
+        assert_eq!(path.segments.len(), 1);
+        assert_eq!(path.segments[0].asns, vec![100, 200000, 300000]);
+
+        // A 4-byte session ignores AS4_PATH entirely
+        let data: &[u8] = &[
+            0x40, 0x02, 0x0A, 0x02, 0x02, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x5B, 0xA0, // AS_PATH 100 23456
+            0xC0, 0x11, 0x06, 0x02, 0x01, 0x00, 0x03, 0x0D, 0x40, // AS4_PATH 200000
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let path = attrs.effective_as_path().unwrap().unwrap();
+        assert_eq!(path.segments[0].asns, vec![100, 23456]);
+    }
+
+    #[test]
+    fn test_effective_as_path_replaces_whole_path() {
+        // AS_PATH and AS4_PATH of equal length: every AS_TRANS is replaced
+        let data: &[u8] = &[
+            0x40, 0x02, 0x04, 0x02, 0x01, 0x5B, 0xA0, // AS_PATH AS_TRANS
+            0xC0, 0x11, 0x06, 0x02, 0x01, 0x00, 0x03, 0x0D, 0x40, // AS4_PATH 200000
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        let path = attrs.effective_as_path().unwrap().unwrap();
+        assert_eq!(path.segments.len(), 1);
+        assert_eq!(path.segments[0].asns, vec![200000]);
+    }
+
+    #[test]
+    fn test_effective_as_path_ignores_longer_as4_path() {
+        let data: &[u8] = &[
+            0x40, 0x02, 0x04, 0x02, 0x01, 0x5B, 0xA0, // AS_PATH AS_TRANS
+            0xC0, 0x11, 0x0A, 0x02, 0x02, 0x00, 0x03, 0x0D, 0x40, 0x00, 0x04, 0x93, 0xE0, // AS4_PATH 200000 300000
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        let path = attrs.effective_as_path().unwrap().unwrap();
+        assert_eq!(path.segments[0].asns, vec![23456]);
+    }
+
+    #[test]
+    fn test_effective_as_path_keeps_set_boundary() {
+        let data: &[u8] = &[
+            0x40, 0x02, 0x0A, // AS_PATH, length 10
+            0x02, 0x01, 0x00, 0x64, // SEQ 100
+            0x01, 0x02, 0x00, 0xC8, 0x5B, 0xA0, // SET {200, AS_TRANS}
+            0xC0, 0x11, 0x06, 0x02, 0x01, 0x00, 0x03, 0x0D, 0x40, // AS4_PATH SEQ 200000
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        let path = attrs.effective_as_path().unwrap().unwrap();
+        assert_eq!(path.segments.len(), 1);
+        assert_eq!(path.segments[0].asns, vec![100, 200000]);
+    }
+
+    #[test]
+    fn test_well_known_scalars() {
+        let data: &[u8] = &[
+            0x40, 0x01, 0x01, 0x02, // ORIGIN = INCOMPLETE
+            0x80, 0x04, 0x04, 0x00, 0x00, 0x00, 0x64, // MED = 100
+            0x40, 0x05, 0x04, 0x00, 0x00, 0x00, 0xC8, // LOCAL_PREF = 200
+            0x40, 0x06, 0x00, // ATOMIC_AGGREGATE
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.origin().unwrap(), Some(Origin::Incomplete));
+        assert_eq!(attrs.med().unwrap(), Some(100));
+        assert_eq!(attrs.local_pref().unwrap(), Some(200));
+        assert!(attrs.atomic_aggregate());
+
+        let attrs = PathAttributes::parse(&[], true).unwrap();
+        assert_eq!(attrs.origin().unwrap(), None);
+        assert_eq!(attrs.med().unwrap(), None);
+        assert!(!attrs.atomic_aggregate());
+    }
+
+    #[test]
+    fn test_malformed_scalars_error() {
+        let data: &[u8] = &[
+            0x40, 0x01, 0x02, 0x00, 0x00, // ORIGIN with length 2
+            0x40, 0x05, 0x03, 0x00, 0x00, 0x64, // LOCAL_PREF with length 3
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert!(attrs.origin().is_err());
+        assert!(attrs.local_pref().is_err());
+
+        let attrs = PathAttributes::parse(&[0x40, 0x01, 0x01, 0x07], true).unwrap();
+        assert!(attrs.origin().is_err());
+    }
+
+    #[test]
+    fn test_parse_attributes_truncated() {
+        let data: &[u8] = &[0x40, 0x03, 0x04, 10, 0];
+        assert!(PathAttributes::parse(data, true).is_err());
+    }
+
+    #[test]
+    fn test_aggregator_as4() {
+        let data: &[u8] = &[
+            0xC0, 0x07, 0x08, 0x00, 0x01, 0x00, 0x00, 192, 0, 2, 1, // AGGREGATOR AS 65536
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(
+            attrs.aggregator().unwrap(),
+            Some((65536, Ipv4Addr::new(192, 0, 2, 1)))
+        );
+
+        // Same bytes decoded as a 2-byte session are malformed
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        assert!(attrs.aggregator().is_err());
+    }
+
+    #[test]
+    fn test_as_trans_with_as4_aggregator() {
+        let data: &[u8] = &[
+            0xC0, 0x07, 0x06, 0x5B, 0xA0, 192, 0, 2, 1, // AGGREGATOR AS_TRANS
+            0xC0, 0x12, 0x08, 0x00, 0x03, 0x0D, 0x40, 192, 0, 2, 1, // AS4_AGGREGATOR 200000
+        ];
+        let attrs = PathAttributes::parse(data, false).unwrap();
+        assert_eq!(attrs.aggregator().unwrap().unwrap().0, 23456);
+        assert_eq!(attrs.as4_aggregator().unwrap().unwrap().0, 200000);
+    }
+
+    #[test]
+    fn test_route_reflector_attributes() {
+        let data: &[u8] = &[
+            0x80, 0x09, 0x04, 10, 0, 0, 7, // ORIGINATOR_ID 10.0.0.7
+            0x80, 0x0A, 0x08, 10, 0, 0, 1, 10, 0, 0, 2, // CLUSTER_LIST 10.0.0.1, 10.0.0.2
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.originator_id().unwrap(), Some(Ipv4Addr::new(10, 0, 0, 7)));
+        assert_eq!(
+            attrs.cluster_list().unwrap(),
+            Some(vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)])
+        );
+
+        let attrs = PathAttributes::parse(&[], true).unwrap();
+        assert_eq!(attrs.originator_id().unwrap(), None);
+        assert_eq!(attrs.cluster_list().unwrap(), None);
+
+        let data: &[u8] = &[
+            0x80, 0x09, 0x03, 10, 0, 0, // ORIGINATOR_ID with length 3
+            0x80, 0x0A, 0x06, 10, 0, 0, 1, 10, 0, // CLUSTER_LIST with length 6
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert!(attrs.originator_id().is_err());
+        assert!(attrs.cluster_list().is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let mut data = vec![
+            0x40, 0x02, 0x06, 0x02, 0x01, 0x00, 0x01, 0x11, 0x70, // AS_PATH 70000
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+            0xC0, 0x08, 0xFF, // COMMUNITIES, length 255 (grows to 259 below)
+        ];
+        data.extend((0..255u32).map(|i| i as u8));
+        let mut attrs = PathAttributes::parse(&data, true).unwrap();
+        attrs.attributes[2].value.extend_from_slice(&[1, 2, 3, 4]);
+
+        let encoded = attrs.encode(true).unwrap();
+        // Sorted by type code; COMMUNITIES now needs the extended length bit
+        assert_eq!(&encoded[..4], &[0x40, 0x01, 0x01, 0x00]);
+        assert_eq!(&encoded[4..13], &[0x40, 0x02, 0x06, 0x02, 0x01, 0x00, 0x01, 0x11, 0x70]);
+        assert_eq!(&encoded[13..17], &[0xD0, 0x08, 0x01, 0x03]);
+        assert_eq!(encoded.len(), 17 + 259);
+
+        let decoded = PathAttributes::parse(&encoded, true).unwrap();
+        assert!(decoded.anomalies().is_empty());
+        assert_eq!(decoded.get(attr_types::COMMUNITIES).unwrap().value.len(), 259);
+        assert_eq!(decoded.as_path().unwrap(), attrs.as_path().unwrap());
+        assert_eq!(decoded.encode(true).unwrap(), encoded);
+
+        // Re-encoding for a 2-byte session narrows AS_PATH to AS_TRANS
+        let narrow = PathAttributes::parse(&attrs.encode(false).unwrap(), false).unwrap();
+        assert_eq!(narrow.as_path().unwrap().unwrap().segments[0].asns, vec![AS_TRANS]);
+        assert_eq!(narrow.as4_path().unwrap(), attrs.as_path().unwrap());
+        assert_eq!(narrow.effective_as_path().unwrap(), attrs.as_path().unwrap());
+
+        attrs.attributes[2].value = vec![0; 65536];
+        assert_eq!(attrs.encode(true).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_encode_narrow_synthesizes_as4_attributes() {
+        let data: &[u8] = &[
+            0x40, 0x02, 0x10, // AS_PATH, length 16
+            0x03, 0x01, 0x00, 0x00, 0xFD, 0xE8, // AS_CONFED_SEQUENCE 65000
+            0x02, 0x02, 0x00, 0x00, 0x00, 0x64, 0x00, 0x01, 0x11, 0x70, // AS_SEQUENCE 100 70000
+            0xC0, 0x07, 0x08, 0x00, 0x03, 0x0D, 0x40, 192, 0, 2, 1, // AGGREGATOR 200000
+            0xC0, 0x11, 0x06, 0x02, 0x01, 0x00, 0x00, 0x00, 0x01, // stale AS4_PATH 1
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let narrow = PathAttributes::parse(&attrs.encode(false).unwrap(), false).unwrap();
+
+        let as_path = narrow.as_path().unwrap().unwrap();
+        assert_eq!(as_path.segments[0].asns, vec![65000]);
+        assert_eq!(as_path.segments[1].asns, vec![100, AS_TRANS]);
+        // The stale AS4_PATH is replaced, and confederation segments left out
+        let as4_path = narrow.as4_path().unwrap().unwrap();
+        assert_eq!(as4_path.segments.len(), 1);
+        assert_eq!(as4_path.segments[0].asns, vec![100, 70000]);
+        assert_eq!(
+            narrow.aggregator().unwrap(),
+            Some((AS_TRANS, Ipv4Addr::new(192, 0, 2, 1)))
+        );
+        assert_eq!(
+            narrow.as4_aggregator().unwrap(),
+            Some((200000, Ipv4Addr::new(192, 0, 2, 1)))
+        );
+        assert_eq!(
+            narrow.attributes.iter().map(|a| a.type_code).collect::<Vec<_>>(),
+            [2, 7, 17, 18]
+        );
+
+        // Nothing is synthesized when every AS fits in 2 bytes
+        let data: &[u8] = &[0x40, 0x02, 0x06, 0x02, 0x01, 0x00, 0x00, 0x00, 0x64];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let narrow = PathAttributes::parse(&attrs.encode(false).unwrap(), false).unwrap();
+        assert_eq!(narrow.attributes.len(), 1);
+    }
+
+    #[test]
+    fn test_pmsi_tunnel() {
+        let data: &[u8] = &[
+            0xC0, 0x16, 0x09, // PMSI_TUNNEL, length 9
+            0x00, // flags
+            0x06, // tunnel type: ingress replication
+            0x00, 0x3E, 0x81, // label 1000, bottom of stack
+            192, 0, 2, 1, // tunnel endpoint
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let tunnel = attrs.pmsi_tunnel().unwrap().unwrap();
+        assert_eq!(tunnel.flags, 0);
+        assert_eq!(tunnel.tunnel_type, 6);
+        assert_eq!(tunnel.label, 1000);
+        assert_eq!(tunnel.tunnel_id, &[192, 0, 2, 1]);
+        assert!(attrs.anomalies().is_empty());
+
+        // Unknown tunnel type without an identifier
+        let tunnel = PmsiTunnel::parse(&[0x01, 0xFE, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(tunnel.tunnel_type, 0xFE);
+        assert!(tunnel.tunnel_id.is_empty());
+
+        assert!(PmsiTunnel::parse(&[0x00, 0x06, 0x00, 0x3E]).is_err());
+        assert_eq!(PathAttributes::parse(&[], true).unwrap().pmsi_tunnel().unwrap(), None);
+    }
+
+    #[test]
+    fn test_aigp() {
+        let data: &[u8] = &[
+            0x80, 0x1A, 0x0F, // AIGP, length 15
+            0x05, 0x00, 0x04, 0xAA, // unknown TLV type 5, length 4
+            0x01, 0x00, 0x0B, 0, 0, 0, 0, 0, 0, 0x01, 0x2C, // AIGP TLV, metric 300
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.aigp().unwrap(), Some(300));
+        assert!(attrs.anomalies().is_empty());
+
+        // Only an unknown TLV
+        let data: &[u8] = &[0x80, 0x1A, 0x04, 0x05, 0x00, 0x04, 0xAA];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.aigp().unwrap(), None);
+        assert_eq!(PathAttributes::parse(&[], true).unwrap().aigp().unwrap(), None);
+
+        for value in [
+            &[0x01, 0x00][..],               // truncated TLV header
+            &[0x01, 0x00, 0x02],             // length below header size
+            &[0x01, 0x00, 0x0B, 0, 0, 0, 1], // length past attribute end
+            &[0x01, 0x00, 0x07, 0, 0, 0, 1], // 4-byte metric
+        ] {
+            let mut data = vec![0x80, 0x1A, value.len() as u8];
+            data.extend_from_slice(value);
+            let attrs = PathAttributes::parse(&data, true).unwrap();
+            assert_eq!(attrs.aigp().unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_otc() {
+        let data: &[u8] = &[
+            0xC0, 0x23, 0x04, // OTC, length 4
+            0x00, 0x00, 0xFD, 0xE9, // AS 65001
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.otc().unwrap(), Some(65001));
+        assert!(attrs.anomalies().is_empty());
+        assert_eq!(PathAttributes::parse(&[], true).unwrap().otc().unwrap(), None);
+
+        let data: &[u8] = &[0xC0, 0x23, 0x02, 0xFD, 0xE9];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        assert_eq!(attrs.otc().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_attributes_iter() {
+        let data: &[u8] = &[
+            0x40, 0x01, 0x01, 0x00, // ORIGIN = IGP
+            0x50, 0x02, 0x00, 0x06, 0x02, 0x01, 0x00, 0x00, 0x00, 0x64, // AS_PATH, extended length
+            0x40, 0x03, 0x04, 10, // truncated NEXT_HOP
+        ];
+        let mut iter = attributes_iter(data);
+        let origin = iter.next().unwrap().unwrap();
+        assert_eq!((origin.type_code, origin.value), (attr_types::ORIGIN, &[0x00][..]));
+        let as_path = iter.next().unwrap().unwrap();
+        assert_eq!(as_path.type_code, attr_types::AS_PATH);
+        assert_eq!(as_path.value.len(), 6);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        assert!(attributes_iter(&[]).next().is_none());
+    }
+
+    #[test]
+    fn test_mp_reach_vpnv4() {
+        let data: &[u8] = &[
+            0x80, 0x0E, 0x20, // MP_REACH_NLRI, length 32
+            0x00, 0x01, // AFI = IPv4
+            0x80, // SAFI = MPLS VPN
+            0x0C, // next hop length = 12
+            0, 0, 0, 0, 0, 0, 0, 0, 192, 0, 2, 1, // RD 0:0, 192.0.2.1
+            0x00, // reserved
+            0x70, // 112 bits: label + RD + /24
+            0x00, 0x06, 0x41, // label 100, bottom of stack
+            0x00, 0x00, 0xFD, 0xE8, 0x00, 0x00, 0x00, 0x01, // RD 65000:1
+            10, 1, 2, // 10.1.2.0/24
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let mp = attrs.mp_reach_nlri().unwrap().unwrap();
+        assert_eq!((mp.afi, mp.safi), (1, safis::MPLS_VPN));
+        assert_eq!(mp.decoded_next_hop().unwrap(), IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+
+        let prefixes = mp.vpn_prefixes().unwrap();
+        assert_eq!(prefixes.len(), 1);
+        assert_eq!(prefixes[0].rd, [0x00, 0x00, 0xFD, 0xE8, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(prefixes[0].labels, vec![100]);
+        assert_eq!(prefixes[0].prefix.to_string(), "10.1.2.0/24");
+
+        // Default route: label and RD with no prefix bytes
+        let mp = MpReachNlri {
+            nlri: vec![
+                0x58, // 88 bits: label + RD + /0
+                0x00, 0x06, 0x41, // label 100, bottom of stack
+                0x00, 0x00, 0xFD, 0xE8, 0x00, 0x00, 0x00, 0x01, // RD 65000:1
+            ],
+            ..mp
+        };
+        let prefixes = mp.vpn_prefixes().unwrap();
+        assert_eq!(prefixes.len(), 1);
+        assert_eq!(prefixes[0].prefix.to_string(), "0.0.0.0/0");
+    }
+
+    #[test]
+    fn test_mp_reach_ipv6_next_hop() {
+        let mut value = vec![0x00, 0x02, 0x01, 0x20]; // IPv6 unicast, next hop length 32
+        value.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        value.extend_from_slice(&"fe80::1".parse::<Ipv6Addr>().unwrap().octets());
+        value.extend_from_slice(&[0x00, 0x20, 0x20, 0x01, 0x0D, 0xB8]); // reserved, 2001:db8::/32
+        let mp = MpReachNlri::parse(&value).unwrap();
+        assert_eq!(mp.decoded_next_hop().unwrap(), "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(mp.nlri, &[0x20, 0x20, 0x01, 0x0D, 0xB8]);
+        assert_eq!(mp.vpn_prefixes().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        assert!(MpReachNlri::parse(&value[..20]).is_err());
+    }
+
+    #[test]
+    fn test_mp_unreach_prefixes() {
+        let data: &[u8] = &[
+            0x80, 0x0F, 0x0F, // MP_UNREACH_NLRI, length 15
+            0x00, 0x02, // AFI = IPv6
+            0x01, // SAFI = unicast
+            0x20, 0x20, 0x01, 0x0D, 0xB8, // 2001:db8::/32
+            0x30, 0x20, 0x01, 0x0D, 0xB8, 0x00, 0x01, // 2001:db8:1::/48
+        ];
+        let attrs = PathAttributes::parse(data, true).unwrap();
+        let mp = attrs.mp_unreach_nlri().unwrap().unwrap();
+        assert_eq!((mp.afi, mp.safi), (2, safis::UNICAST));
+        let prefixes: Vec<String> = mp.prefixes().unwrap().iter().map(|p| p.to_string()).collect();
+        assert_eq!(prefixes, ["2001:db8::/32", "2001:db8:1::/48"]);
+
+        let truncated = MpUnreachNlri {
+            withdrawn: vec![0x30, 0x20, 0x01],
+            ..mp.clone()
+        };
+        assert_eq!(truncated.prefixes().unwrap_err().kind(), ErrorKind::InvalidData);
+        let vpn = MpUnreachNlri {
+            safi: safis::MPLS_VPN,
+            ..mp
+        };
+        assert_eq!(vpn.prefixes().unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(MpUnreachNlri::parse(&[0x00, 0x02]).is_err());
+    }
+}
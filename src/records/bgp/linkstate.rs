@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! BGP-LS NLRI decoding (RFC 7752).
+//!
+//! BGP-LS carries link-state topology in NLRI of AFI 16388. Each NLRI is a
+//! type and length followed by a protocol header and descriptor TLVs. This
+//! module decodes that framing; the contents of the descriptor TLVs are
+//! left raw.
+
+use std::io::{Error, ErrorKind};
+
+use crate::AFI;
+
+/// AFI of BGP-LS NLRI (RFC 7752 section 3.2), as found in
+/// [`RIB_GENERIC::afi_raw`](crate::tabledump::RIB_GENERIC::afi_raw)
+pub const BGP_LS_AFI: u16 = 16388;
+
+/// BGP-LS NLRI type codes
+pub mod nlri_types {
+    /// Node NLRI
+    pub const NODE: u16 = 1;
+    /// Link NLRI
+    pub const LINK: u16 = 2;
+    /// IPv4 Topology Prefix NLRI
+    pub const IPV4_PREFIX: u16 = 3;
+    /// IPv6 Topology Prefix NLRI
+    pub const IPV6_PREFIX: u16 = 4;
+}
+
+/// BGP-LS Protocol-ID values (RFC 7752, RFC 9086)
+pub mod protocol_ids {
+    /// IS-IS Level 1
+    pub const ISIS_L1: u8 = 1;
+    /// IS-IS Level 2
+    pub const ISIS_L2: u8 = 2;
+    /// OSPFv2
+    pub const OSPFV2: u8 = 3;
+    /// Direct
+    pub const DIRECT: u8 = 4;
+    /// Static configuration
+    pub const STATIC: u8 = 5;
+    /// OSPFv3
+    pub const OSPFV3: u8 = 6;
+    /// BGP (RFC 9086)
+    pub const BGP: u8 = 7;
+}
+
+/// Descriptor TLV type codes
+pub mod tlv_types {
+    /// Local Node Descriptors
+    pub const LOCAL_NODE_DESCRIPTORS: u16 = 256;
+    /// Remote Node Descriptors
+    pub const REMOTE_NODE_DESCRIPTORS: u16 = 257;
+    /// Link Local/Remote Identifiers
+    pub const LINK_IDENTIFIERS: u16 = 258;
+    /// IPv4 interface address
+    pub const IPV4_INTERFACE_ADDRESS: u16 = 259;
+    /// IPv4 neighbor address
+    pub const IPV4_NEIGHBOR_ADDRESS: u16 = 260;
+    /// IPv6 interface address
+    pub const IPV6_INTERFACE_ADDRESS: u16 = 261;
+    /// IPv6 neighbor address
+    pub const IPV6_NEIGHBOR_ADDRESS: u16 = 262;
+    /// Multi-Topology Identifier
+    pub const MULTI_TOPOLOGY_ID: u16 = 263;
+    /// OSPF Route Type
+    pub const OSPF_ROUTE_TYPE: u16 = 264;
+    /// IP Reachability Information
+    pub const IP_REACHABILITY_INFORMATION: u16 = 265;
+}
+
+/// A BGP-LS NLRI (RFC 7752 section 3.2).
+///
+/// Format:
+/// - 2 bytes: NLRI type
+/// - 2 bytes: length of the rest
+/// - remaining: protocol header and descriptor TLVs, see [`LinkStateDescriptors`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LinkStateNlri {
+    /// Node NLRI
+    Node(LinkStateDescriptors),
+    /// Link NLRI
+    Link(LinkStateDescriptors),
+    /// Topology Prefix NLRI, with the address family of the prefix
+    Prefix {
+        /// `IPV4` for NLRI type 3, `IPV6` for type 4
+        afi: AFI,
+        /// Protocol header and descriptors
+        descriptors: LinkStateDescriptors,
+    },
+    /// Unrecognized NLRI type, kept as the undecoded value
+    Unknown { nlri_type: u16, data: Vec<u8> },
+}
+
+impl LinkStateNlri {
+    /// Parse a single NLRI that fills `bytes`.
+    pub fn parse(bytes: &[u8]) -> std::io::Result<Self> {
+        match Self::parse_all(bytes)?.as_slice() {
+            [nlri] => Ok(nlri.clone()),
+            _ => Err(Error::new(ErrorKind::InvalidData, "expected exactly one BGP-LS NLRI")),
+        }
+    }
+
+    /// Parse a sequence of NLRI, as found in MP_REACH_NLRI.
+    pub fn parse_all(bytes: &[u8]) -> std::io::Result<Vec<Self>> {
+        split_tlvs(bytes, "BGP-LS NLRI")?
+            .into_iter()
+            .map(|(nlri_type, value)| Self::decode(nlri_type, value))
+            .collect()
+    }
+
+    fn decode(nlri_type: u16, value: &[u8]) -> std::io::Result<Self> {
+        Ok(match nlri_type {
+            nlri_types::NODE => LinkStateNlri::Node(LinkStateDescriptors::parse(value)?),
+            nlri_types::LINK => LinkStateNlri::Link(LinkStateDescriptors::parse(value)?),
+            nlri_types::IPV4_PREFIX | nlri_types::IPV6_PREFIX => LinkStateNlri::Prefix {
+                afi: if nlri_type == nlri_types::IPV4_PREFIX {
+                    AFI::IPV4
+                } else {
+                    AFI::IPV6
+                },
+                descriptors: LinkStateDescriptors::parse(value)?,
+            },
+            _ => LinkStateNlri::Unknown {
+                nlri_type,
+                data: value.to_vec(),
+            },
+        })
+    }
+
+    /// The NLRI type code.
+    pub fn nlri_type(&self) -> u16 {
+        match self {
+            LinkStateNlri::Node(_) => nlri_types::NODE,
+            LinkStateNlri::Link(_) => nlri_types::LINK,
+            LinkStateNlri::Prefix { afi: AFI::IPV6, .. } => nlri_types::IPV6_PREFIX,
+            LinkStateNlri::Prefix { .. } => nlri_types::IPV4_PREFIX,
+            LinkStateNlri::Unknown { nlri_type, .. } => *nlri_type,
+        }
+    }
+
+    /// The protocol header and descriptors, or `None` for unknown types.
+    pub fn descriptors(&self) -> Option<&LinkStateDescriptors> {
+        match self {
+            LinkStateNlri::Node(descriptors)
+            | LinkStateNlri::Link(descriptors)
+            | LinkStateNlri::Prefix { descriptors, .. } => Some(descriptors),
+            LinkStateNlri::Unknown { .. } => None,
+        }
+    }
+}
+
+/// The body shared by the Node, Link and Prefix NLRI types.
+///
+/// Format:
+/// - 1 byte: protocol_id
+/// - 8 bytes: identifier
+/// - remaining: descriptor TLVs (2-byte type, 2-byte length, value)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LinkStateDescriptors {
+    /// Source protocol of the information, see [`protocol_ids`]
+    pub protocol_id: u8,
+    /// Routing universe identifier
+    pub identifier: u64,
+    /// Descriptor TLVs in wire order
+    pub tlvs: Vec<LinkStateTlv>,
+}
+
+impl LinkStateDescriptors {
+    /// Parse the value of a Node, Link or Prefix NLRI.
+    pub fn parse(bytes: &[u8]) -> std::io::Result<Self> {
+        if bytes.len() < 9 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated BGP-LS NLRI header"));
+        }
+        let tlvs = split_tlvs(&bytes[9..], "BGP-LS descriptor TLV")?
+            .into_iter()
+            .map(|(tlv_type, value)| LinkStateTlv {
+                tlv_type,
+                value: value.to_vec(),
+            })
+            .collect();
+        Ok(LinkStateDescriptors {
+            protocol_id: bytes[0],
+            identifier: u64::from_be_bytes(bytes[1..9].try_into().unwrap()),
+            tlvs,
+        })
+    }
+
+    /// The first TLV of type `tlv_type`, see [`tlv_types`].
+    pub fn tlv(&self, tlv_type: u16) -> Option<&LinkStateTlv> {
+        self.tlvs.iter().find(|tlv| tlv.tlv_type == tlv_type)
+    }
+}
+
+/// A descriptor TLV with its value undecoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LinkStateTlv {
+    /// TLV type code
+    pub tlv_type: u16,
+    /// Raw value bytes
+    pub value: Vec<u8>,
+}
+
+/// Split `bytes` into `(type, value)` pairs of 2-byte type, 2-byte length TLVs.
+fn split_tlvs<'a>(mut bytes: &'a [u8], what: &str) -> std::io::Result<Vec<(u16, &'a [u8])>> {
+    let mut tlvs = Vec::new();
+    while !bytes.is_empty() {
+        let truncated = || Error::new(ErrorKind::InvalidData, format!("truncated {}", what));
+        if bytes.len() < 4 {
+            return Err(truncated());
+        }
+        let tlv_type = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let len = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        let value = bytes.get(4..4 + len).ok_or_else(truncated)?;
+        tlvs.push((tlv_type, value));
+        bytes = &bytes[4 + len..];
+    }
+    Ok(tlvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_and_prefix_nlri() {
+        let data: &[u8] = &[
+            0x00, 0x01, // type: Node
+            0x00, 0x15, // length: 21
+            0x02, // protocol_id: IS-IS Level 2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A, // identifier: 42
+            0x01, 0x00, // TLV type: Local Node Descriptors
+            0x00, 0x08, // TLV length
+            0x02, 0x00, 0x00, 0x04, 0x00, 0x00, 0xFD, 0xE8, // AS 65000 sub-TLV
+            0x00, 0x04, // type: IPv6 Topology Prefix
+            0x00, 0x09, // length: 9
+            0x03, // protocol_id: OSPFv2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // identifier: 0
+            0x00, 0x63, // type: unknown
+            0x00, 0x02, // length
+            0xAB, 0xCD, // value
+        ];
+
+        let nlri = LinkStateNlri::parse_all(data).unwrap();
+        assert_eq!(nlri.len(), 3);
+        let LinkStateNlri::Node(node) = &nlri[0] else {
+            panic!("expected Node NLRI, got {:?}", nlri[0]);
+        };
+        assert_eq!(node.protocol_id, protocol_ids::ISIS_L2);
+        assert_eq!(node.identifier, 42);
+        let local = node.tlv(tlv_types::LOCAL_NODE_DESCRIPTORS).unwrap();
+        assert_eq!(local.value, [0x02, 0x00, 0x00, 0x04, 0x00, 0x00, 0xFD, 0xE8]);
+        assert!(node.tlv(tlv_types::REMOTE_NODE_DESCRIPTORS).is_none());
+
+        assert_eq!(nlri[1].nlri_type(), nlri_types::IPV6_PREFIX);
+        assert!(matches!(&nlri[1], LinkStateNlri::Prefix { afi: AFI::IPV6, .. }));
+        assert!(nlri[1].descriptors().unwrap().tlvs.is_empty());
+        assert_eq!(
+            nlri[2],
+            LinkStateNlri::Unknown {
+                nlri_type: 0x63,
+                data: vec![0xAB, 0xCD]
+            }
+        );
+
+        assert_eq!(LinkStateNlri::parse(&data[..25]).unwrap(), nlri[0]);
+        assert!(LinkStateNlri::parse(data).is_err());
+    }
+
+    #[test]
+    fn test_parse_truncated_nlri() {
+        // Length runs past the end
+        assert!(LinkStateNlri::parse_all(&[0x00, 0x02, 0x00, 0x10, 0x00]).is_err());
+        // Too short for the protocol header
+        let err = LinkStateNlri::parse_all(&[0x00, 0x02, 0x00, 0x01, 0x00]).unwrap_err();
+        assert_eq!(err.to_string(), "truncated BGP-LS NLRI header");
+        // Truncated descriptor TLV
+        let mut data = vec![0x00, 0x01, 0x00, 0x0B, 0x01];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0x01, 0x00]);
+        let err = LinkStateNlri::parse_all(&data).unwrap_err();
+        assert_eq!(err.to_string(), "truncated BGP-LS descriptor TLV");
+    }
+}
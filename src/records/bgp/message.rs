@@ -0,0 +1,536 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! BGP message framing.
+//!
+//! BGP4MP MESSAGE records carry raw BGP PDUs (RFC 4271 section 4.1). Each
+//! PDU starts with a 16-byte all-ones marker, a 2-byte total length and a
+//! 1-byte message type.
+//!
+//! The original BGP-4 specification (RFC 1771) let the marker carry
+//! authentication data, and some old dumps contain such markers. The marker
+//! is therefore only checked when
+//! [`ReadOptions::strict_marker`](crate::ReadOptions::strict_marker) is set.
+
+use std::io::{Error, ErrorKind};
+
+use super::attributes::{
+    attr_types, attributes_iter, decode_prefixes, decode_vpn_prefixes, safis, MpReachNlri,
+    MpUnreachNlri,
+};
+use crate::{Prefix, ReadOptions, AFI};
+
+/// Length of the BGP message header (marker, length and type)
+pub const HEADER_LEN: usize = 19;
+
+/// Length of the all-ones marker at the start of every BGP message
+pub const MARKER_LEN: usize = 16;
+
+/// BGP message type codes
+pub mod message_types {
+    /// OPEN (RFC 4271)
+    pub const OPEN: u8 = 1;
+    /// UPDATE (RFC 4271)
+    pub const UPDATE: u8 = 2;
+    /// NOTIFICATION (RFC 4271)
+    pub const NOTIFICATION: u8 = 3;
+    /// KEEPALIVE (RFC 4271)
+    pub const KEEPALIVE: u8 = 4;
+    /// ROUTE-REFRESH (RFC 2918)
+    pub const ROUTE_REFRESH: u8 = 5;
+}
+
+/// Returns `true` if `bytes` starts with the 16-byte all-ones marker.
+pub fn validate_marker(bytes: &[u8]) -> bool {
+    bytes.len() >= MARKER_LEN && bytes[..MARKER_LEN].iter().all(|&b| b == 0xFF)
+}
+
+/// Split a buffer holding one or more concatenated BGP messages.
+///
+/// Some capture tools write every PDU from one TCP segment into a single
+/// BGP4MP MESSAGE record. This walks the buffer using each header's length
+/// field and returns one slice per PDU, header included.
+///
+/// Returns an `InvalidData` error if a length is below the header size or
+/// the last PDU is truncated. Markers are not checked; see
+/// [`split_pdus_with_options`].
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::bgp::message::split_pdus;
+///
+/// let keepalive = [&[0xFF; 16][..], &[0x00, 0x13, 0x04]].concat();
+/// let bundle = [keepalive.as_slice(), keepalive.as_slice()].concat();
+/// assert_eq!(split_pdus(&bundle).unwrap().len(), 2);
+/// ```
+pub fn split_pdus(bytes: &[u8]) -> std::io::Result<Vec<&[u8]>> {
+    split_pdus_with_options(bytes, &ReadOptions::default())
+}
+
+/// Split a buffer holding one or more concatenated BGP messages, checking
+/// each marker if `options` asks for it.
+///
+/// Returns an `InvalidData` error for the same framing errors as
+/// [`split_pdus`], and with [`ReadOptions::strict_marker`] for a marker that
+/// is not all ones.
+pub fn split_pdus_with_options<'a>(
+    bytes: &'a [u8],
+    options: &ReadOptions,
+) -> std::io::Result<Vec<&'a [u8]>> {
+    let mut pdus = Vec::new();
+    let mut rest = bytes;
+
+    while !rest.is_empty() {
+        if rest.len() < HEADER_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated BGP message header"));
+        }
+        if options.strict_marker && !validate_marker(rest) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "BGP message at byte {} has a marker that is not all ones: {:02x?}",
+                    bytes.len() - rest.len(),
+                    &rest[..MARKER_LEN]
+                ),
+            ));
+        }
+        let length = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+        if length < HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("BGP message length {} is shorter than its header", length),
+            ));
+        }
+        if length > rest.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated BGP message"));
+        }
+
+        let (pdu, tail) = rest.split_at(length);
+        pdus.push(pdu);
+        rest = tail;
+    }
+
+    Ok(pdus)
+}
+
+/// BGP NOTIFICATION message (RFC 4271 section 4.5).
+///
+/// Format (after the 19-byte message header):
+/// - 1 byte: error code
+/// - 1 byte: error subcode
+/// - remaining: data
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Notification {
+    /// Error code
+    pub error_code: u8,
+    /// Error subcode (0 when unspecific)
+    pub error_subcode: u8,
+    /// Diagnostic data, interpreted according to the code and subcode
+    pub data: Vec<u8>,
+}
+
+impl Notification {
+    /// Parse a NOTIFICATION from the message body following the header.
+    pub fn parse(body: &[u8]) -> std::io::Result<Self> {
+        if body.len() < 2 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated NOTIFICATION message"));
+        }
+        Ok(Notification {
+            error_code: body[0],
+            error_subcode: body[1],
+            data: body[2..].to_vec(),
+        })
+    }
+
+    /// Parse a NOTIFICATION from a whole PDU, header included.
+    ///
+    /// Fails if the PDU is not a single well-formed NOTIFICATION message.
+    #[inline]
+    pub fn from_pdu(pdu: &[u8]) -> std::io::Result<Self> {
+        Self::from_pdu_with_options(pdu, &ReadOptions::default())
+    }
+
+    /// Parse a NOTIFICATION from a whole PDU, checking the marker if
+    /// `options` asks for it.
+    pub fn from_pdu_with_options(pdu: &[u8], options: &ReadOptions) -> std::io::Result<Self> {
+        match split_pdus_with_options(pdu, options)?.as_slice() {
+            [pdu] if pdu[HEADER_LEN - 1] == message_types::NOTIFICATION => {
+                Notification::parse(&pdu[HEADER_LEN..])
+            }
+            _ => Err(Error::new(ErrorKind::InvalidData, "not a NOTIFICATION message")),
+        }
+    }
+
+    /// Name of the error code, or `"UNKNOWN"`.
+    pub fn error_code_name(&self) -> &'static str {
+        match self.error_code {
+            1 => "Message Header Error",
+            2 => "OPEN Message Error",
+            3 => "UPDATE Message Error",
+            4 => "Hold Timer Expired",
+            5 => "Finite State Machine Error",
+            6 => "Cease",
+            7 => "ROUTE-REFRESH Message Error",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// Name of the error subcode within its error code, or `"UNKNOWN"`.
+    ///
+    /// Subcode 0 is `"Unspecific"` for every code.
+    pub fn subcode_name(&self) -> &'static str {
+        match (self.error_code, self.error_subcode) {
+            (_, 0) => "Unspecific",
+            (1, 1) => "Connection Not Synchronized",
+            (1, 2) => "Bad Message Length",
+            (1, 3) => "Bad Message Type",
+            (2, 1) => "Unsupported Version Number",
+            (2, 2) => "Bad Peer AS",
+            (2, 3) => "Bad BGP Identifier",
+            (2, 4) => "Unsupported Optional Parameter",
+            (2, 5) => "Authentication Failure",
+            (2, 6) => "Unacceptable Hold Time",
+            (2, 7) => "Unsupported Capability",
+            (2, 11) => "Role Mismatch",
+            (3, 1) => "Malformed Attribute List",
+            (3, 2) => "Unrecognized Well-known Attribute",
+            (3, 3) => "Missing Well-known Attribute",
+            (3, 4) => "Attribute Flags Error",
+            (3, 5) => "Attribute Length Error",
+            (3, 6) => "Invalid ORIGIN Attribute",
+            (3, 7) => "AS Routing Loop",
+            (3, 8) => "Invalid NEXT_HOP Attribute",
+            (3, 9) => "Optional Attribute Error",
+            (3, 10) => "Invalid Network Field",
+            (3, 11) => "Malformed AS_PATH",
+            (5, 1) => "Receive Unexpected Message in OpenSent State",
+            (5, 2) => "Receive Unexpected Message in OpenConfirm State",
+            (5, 3) => "Receive Unexpected Message in Established State",
+            (6, 1) => "Maximum Number of Prefixes Reached",
+            (6, 2) => "Administrative Shutdown",
+            (6, 3) => "Peer De-configured",
+            (6, 4) => "Administrative Reset",
+            (6, 5) => "Connection Rejected",
+            (6, 6) => "Other Configuration Change",
+            (6, 7) => "Connection Collision Resolution",
+            (6, 8) => "Out of Resources",
+            (6, 9) => "Hard Reset",
+            (6, 10) => "BFD Down",
+            (7, 1) => "Invalid Message Length",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+/// BGP UPDATE message (RFC 4271 section 4.3).
+///
+/// Format (after the 19-byte message header):
+/// - 2 bytes: withdrawn routes length
+/// - variable: withdrawn routes
+/// - 2 bytes: total path attribute length
+/// - variable: path attributes
+/// - remaining: NLRI
+///
+/// The fields are kept raw. Add-Path UPDATEs (RFC 7911), whose prefixes
+/// carry path identifiers, are not decoded by the prefix accessors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Update {
+    /// Raw withdrawn routes field (IPv4 unicast)
+    pub withdrawn_routes: Vec<u8>,
+    /// Raw path attributes
+    pub path_attributes: Vec<u8>,
+    /// Raw NLRI field (IPv4 unicast)
+    pub nlri: Vec<u8>,
+}
+
+impl Update {
+    /// Parse an UPDATE from the message body following the header.
+    pub fn parse(body: &[u8]) -> std::io::Result<Self> {
+        let truncated = || Error::new(ErrorKind::InvalidData, "truncated UPDATE message");
+        let (withdrawn_routes, rest) = split_u16_prefixed(body).ok_or_else(truncated)?;
+        let (path_attributes, nlri) = split_u16_prefixed(rest).ok_or_else(truncated)?;
+        Ok(Update {
+            withdrawn_routes: withdrawn_routes.to_vec(),
+            path_attributes: path_attributes.to_vec(),
+            nlri: nlri.to_vec(),
+        })
+    }
+
+    /// Parse an UPDATE from a whole PDU, header included.
+    ///
+    /// Fails if the PDU is not a single well-formed UPDATE message.
+    #[inline]
+    pub fn from_pdu(pdu: &[u8]) -> std::io::Result<Self> {
+        Self::from_pdu_with_options(pdu, &ReadOptions::default())
+    }
+
+    /// Parse an UPDATE from a whole PDU, checking the marker if `options`
+    /// asks for it.
+    pub fn from_pdu_with_options(pdu: &[u8], options: &ReadOptions) -> std::io::Result<Self> {
+        match split_pdus_with_options(pdu, options)?.as_slice() {
+            [pdu] if pdu[HEADER_LEN - 1] == message_types::UPDATE => {
+                Update::parse(&pdu[HEADER_LEN..])
+            }
+            _ => Err(Error::new(ErrorKind::InvalidData, "not an UPDATE message")),
+        }
+    }
+
+    /// Every announced prefix with its AFI and SAFI.
+    ///
+    /// Merges the NLRI field, which is IPv4 unicast, with the prefixes of
+    /// each MP_REACH_NLRI attribute, in that order. MPLS VPN routes are
+    /// reduced to their IP prefix; use [`MpReachNlri::vpn_prefixes`] to keep
+    /// the route distinguisher and labels.
+    ///
+    /// Returns an `InvalidData` error for an MP_REACH_NLRI of any other
+    /// family, such as BGP-LS or a non-IP AFI, unless its NLRI is empty.
+    pub fn all_announced_prefixes(&self) -> std::io::Result<Vec<(AFI, u8, Prefix)>> {
+        let mut prefixes = Vec::new();
+        push_prefixes(&mut prefixes, 1, safis::UNICAST, &self.nlri)?;
+        for attr in attributes_iter(&self.path_attributes) {
+            let attr = attr?;
+            if attr.type_code == attr_types::MP_REACH_NLRI {
+                let mp = MpReachNlri::parse(attr.value)?;
+                push_prefixes(&mut prefixes, mp.afi, mp.safi, &mp.nlri)?;
+            }
+        }
+        Ok(prefixes)
+    }
+
+    /// Every withdrawn prefix with its AFI and SAFI.
+    ///
+    /// The withdrawal counterpart of
+    /// [`all_announced_prefixes`](Self::all_announced_prefixes), covering the
+    /// withdrawn routes field and each MP_UNREACH_NLRI attribute.
+    pub fn all_withdrawn_prefixes(&self) -> std::io::Result<Vec<(AFI, u8, Prefix)>> {
+        let mut prefixes = Vec::new();
+        push_prefixes(&mut prefixes, 1, safis::UNICAST, &self.withdrawn_routes)?;
+        for attr in attributes_iter(&self.path_attributes) {
+            let attr = attr?;
+            if attr.type_code == attr_types::MP_UNREACH_NLRI {
+                let mp = MpUnreachNlri::parse(attr.value)?;
+                push_prefixes(&mut prefixes, mp.afi, mp.safi, &mp.withdrawn)?;
+            }
+        }
+        Ok(prefixes)
+    }
+}
+
+/// Split a 2-byte length-prefixed field from the front of `bytes`.
+fn split_u16_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<2>()?;
+    let len = u16::from_be_bytes(*len) as usize;
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+/// Decode `nlri` and append its prefixes.
+///
+/// Empty NLRI is accepted for any family, as End-of-RIB markers (RFC 4724)
+/// are empty MP_UNREACH_NLRI attributes.
+fn push_prefixes(
+    out: &mut Vec<(AFI, u8, Prefix)>,
+    afi: u16,
+    safi: u8,
+    nlri: &[u8],
+) -> std::io::Result<()> {
+    if nlri.is_empty() {
+        return Ok(());
+    }
+    let unsupported = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("cannot decode prefixes of AFI {} SAFI {}", afi, safi),
+        )
+    };
+    let afi = AFI::from_u16(afi).map_err(|_| unsupported())?;
+    match safi {
+        safis::UNICAST | safis::MULTICAST => {
+            for prefix in decode_prefixes(&afi, nlri)? {
+                out.push((afi, safi, prefix));
+            }
+        }
+        safis::MPLS_VPN => {
+            for vpn in decode_vpn_prefixes(&afi, nlri)? {
+                out.push((afi, safi, vpn.prefix));
+            }
+        }
+        _ => return Err(unsupported()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pdu(msg_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF; MARKER_LEN];
+        data.extend_from_slice(&((HEADER_LEN + body.len()) as u16).to_be_bytes());
+        data.push(msg_type);
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn test_split_pdus() {
+        let update = pdu(2, &[0x00, 0x00, 0x00, 0x00]); // empty UPDATE
+        let keepalive = pdu(4, &[]);
+        let bundle = [update.as_slice(), keepalive.as_slice()].concat();
+
+        let pdus = split_pdus(&bundle).unwrap();
+        assert_eq!(pdus, vec![update.as_slice(), keepalive.as_slice()]);
+        assert!(split_pdus(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_split_pdus_errors() {
+        let keepalive = pdu(4, &[]);
+
+        // Truncated second PDU
+        let bundle = [keepalive.as_slice(), &keepalive[..10]].concat();
+        assert_eq!(split_pdus(&bundle).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // Bad marker, only rejected on request
+        let mut bad = keepalive.clone();
+        bad[3] = 0x00;
+        assert_eq!(split_pdus(&bad).unwrap(), vec![bad.as_slice()]);
+        let strict = ReadOptions {
+            strict_marker: true,
+            ..Default::default()
+        };
+        let bundle = [keepalive.as_slice(), bad.as_slice()].concat();
+        let err = split_pdus_with_options(&bundle, &strict).unwrap_err();
+        assert!(err.to_string().starts_with("BGP message at byte 19 has a marker"));
+
+        // Length below the header size would never advance
+        let mut short = keepalive.clone();
+        short[17] = 0x00;
+        assert!(split_pdus(&short).is_err());
+
+        // Length past the end of the buffer
+        let mut long = keepalive;
+        long[17] = 0x20;
+        assert!(split_pdus(&long).is_err());
+    }
+
+    #[test]
+    fn test_validate_marker() {
+        let mut update = pdu(2, &[0x00, 0x00, 0x00, 0x00]); // empty UPDATE
+        assert!(validate_marker(&update));
+        assert!(!validate_marker(&update[..15]));
+
+        // RFC 1771 authentication data in the marker
+        update[..MARKER_LEN].copy_from_slice(&[0x5A; MARKER_LEN]);
+        assert!(!validate_marker(&update));
+        assert!(Update::from_pdu(&update).is_ok());
+        let strict = ReadOptions {
+            strict_marker: true,
+            ..Default::default()
+        };
+        let err = Update::from_pdu_with_options(&update, &strict).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_notification() {
+        let data = pdu(
+            message_types::NOTIFICATION,
+            &[
+                0x06, // error code = Cease
+                0x02, // subcode = Administrative Shutdown
+                0x04, b'm', b'a', b'i', b'n', // shutdown communication (RFC 9003)
+            ],
+        );
+        let notification = Notification::from_pdu(&data).unwrap();
+        assert_eq!(notification.error_code, 6);
+        assert_eq!(notification.error_code_name(), "Cease");
+        assert_eq!(notification.subcode_name(), "Administrative Shutdown");
+        assert_eq!(notification.data, &[0x04, b'm', b'a', b'i', b'n']);
+
+        let hold_timer = Notification::parse(&[0x04, 0x00]).unwrap();
+        assert_eq!(hold_timer.error_code_name(), "Hold Timer Expired");
+        assert_eq!(hold_timer.subcode_name(), "Unspecific");
+
+        let unknown = Notification::parse(&[0x63, 0x01]).unwrap();
+        assert_eq!(unknown.error_code_name(), "UNKNOWN");
+        assert_eq!(unknown.subcode_name(), "UNKNOWN");
+
+        assert!(Notification::parse(&[0x06]).is_err());
+        assert!(Notification::from_pdu(&pdu(message_types::KEEPALIVE, &[])).is_err());
+    }
+
+    #[test]
+    fn test_update_prefixes() {
+        let data = pdu(
+            message_types::UPDATE,
+            &[
+                0x00, 0x04, // withdrawn routes length
+                0x18, 198, 51, 100, // 198.51.100.0/24
+                0x00, 0x1E, // total path attribute length = 30
+                0x80, 0x0E, 0x10, // MP_REACH_NLRI, length 16
+                0x00, 0x02, // AFI = IPv6
+                0x01, // SAFI = unicast
+                0x04, 0x00, 0x00, 0x00, 0x00, // next hop length 4, 0.0.0.0
+                0x00, // reserved
+                0x30, 0x20, 0x01, 0x0D, 0xB8, 0x00, 0x01, // 2001:db8:1::/48
+                0x80, 0x0F, 0x08, // MP_UNREACH_NLRI, length 8
+                0x00, 0x02, // AFI = IPv6
+                0x01, // SAFI = unicast
+                0x20, 0x20, 0x01, 0x0D, 0xB8, // 2001:db8::/32
+                0x18, 192, 0, 2, // NLRI: 192.0.2.0/24
+            ],
+        );
+        let update = Update::from_pdu(&data).unwrap();
+
+        let to_strings = |prefixes: Vec<(AFI, u8, Prefix)>| -> Vec<String> {
+            prefixes.iter().map(|(afi, safi, p)| format!("{:?}/{} {}", afi, safi, p)).collect()
+        };
+        assert_eq!(
+            to_strings(update.all_announced_prefixes().unwrap()),
+            ["IPV4/1 192.0.2.0/24", "IPV6/1 2001:db8:1::/48"]
+        );
+        assert_eq!(
+            to_strings(update.all_withdrawn_prefixes().unwrap()),
+            ["IPV4/1 198.51.100.0/24", "IPV6/1 2001:db8::/32"]
+        );
+
+        // Attribute length past the end of the body
+        assert!(Update::parse(&[0x00, 0x00, 0x00, 0x05, 0x40]).is_err());
+        assert!(Update::from_pdu(&pdu(message_types::KEEPALIVE, &[])).is_err());
+    }
+
+    #[test]
+    fn test_update_prefixes_other_families() {
+        let mut update = Update::parse(&[
+            0x00, 0x00, // withdrawn routes length
+            0x00, 0x26, // total path attribute length = 38
+            0x80, 0x0E, 0x1D, // MP_REACH_NLRI, length 29
+            0x00, 0x01, // AFI = IPv4
+            0x80, // SAFI = MPLS VPN
+            0x0C, // next hop length = 12
+            0, 0, 0, 0, 0, 0, 0, 0, 192, 0, 2, 1, // RD 0:0, 192.0.2.1
+            0x00, // reserved
+            0x58, // 88 bits: label + RD + /0
+            0x00, 0x06, 0x41, // label 100, bottom of stack
+            0x00, 0x00, 0xFD, 0xE8, 0x00, 0x00, 0x00, 0x01, // RD 65000:1
+            0x80, 0x0F, 0x03, // MP_UNREACH_NLRI, length 3
+            0x40, 0x04, // AFI = BGP-LS
+            0x47, // SAFI = BGP-LS, no routes (End-of-RIB)
+        ])
+        .unwrap();
+        let announced = update.all_announced_prefixes().unwrap();
+        assert_eq!(announced.len(), 1);
+        assert_eq!((announced[0].0, announced[0].1), (AFI::IPV4, safis::MPLS_VPN));
+        assert_eq!(announced[0].2.to_string(), "0.0.0.0/0");
+        assert!(update.all_withdrawn_prefixes().unwrap().is_empty());
+
+        // BGP-LS routes cannot be reduced to prefixes
+        update.path_attributes[34] = 0x05; // MP_UNREACH_NLRI length 5
+        update.path_attributes.extend_from_slice(&[0x00, 0x01]); // start of a link NLRI
+        let err = update.all_withdrawn_prefixes().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "cannot decode prefixes of AFI 16388 SAFI 71");
+    }
+}
@@ -7,11 +7,20 @@
 
 #![allow(non_camel_case_types)]
 
-use crate::address::{read_afi, read_ip_by_afi, read_prefix};
+use crate::address::{
+    afi_of, check_prefix, common_afi, read_afi, read_ip_by_afi, read_prefix, write_afi, write_ip,
+    write_u16_prefixed,
+};
+use crate::records::bgp::PathAttributes;
+use crate::records::HasPeer;
 use crate::Header;
+use crate::Prefix;
+use crate::Record;
 use crate::AFI;
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind, Read};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Write};
 use std::net::IpAddr;
 
 /// BGP4MP subtype constants
@@ -30,12 +39,33 @@ mod subtypes {
     pub const MESSAGE_AS4_LOCAL_ADDPATH: u16 = 11;
 }
 
+/// Name of a subtype, or `None` if it is not known.
+pub(crate) fn subtype_name(sub_type: u16) -> Option<&'static str> {
+    Some(match sub_type {
+        subtypes::STATE_CHANGE => "STATE_CHANGE",
+        subtypes::MESSAGE => "MESSAGE",
+        subtypes::ENTRY => "ENTRY",
+        subtypes::SNAPSHOT => "SNAPSHOT",
+        subtypes::MESSAGE_AS4 => "MESSAGE_AS4",
+        subtypes::STATE_CHANGE_AS4 => "STATE_CHANGE_AS4",
+        subtypes::MESSAGE_LOCAL => "MESSAGE_LOCAL",
+        subtypes::MESSAGE_AS4_LOCAL => "MESSAGE_AS4_LOCAL",
+        subtypes::MESSAGE_ADDPATH => "MESSAGE_ADDPATH",
+        subtypes::MESSAGE_AS4_ADDPATH => "MESSAGE_AS4_ADDPATH",
+        subtypes::MESSAGE_LOCAL_ADDPATH => "MESSAGE_LOCAL_ADDPATH",
+        subtypes::MESSAGE_AS4_LOCAL_ADDPATH => "MESSAGE_AS4_LOCAL_ADDPATH",
+        _ => return None,
+    })
+}
+
 /// BGP4MP record enum.
 ///
 /// The modern MRT format for BGP data, supporting IPv4/IPv6 peers
 /// and both 16-bit and 32-bit AS numbers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(non_camel_case_types)]
+#[non_exhaustive]
 pub enum BGP4MP {
     /// BGP state change (16-bit ASN)
     STATE_CHANGE(STATE_CHANGE),
@@ -61,6 +91,11 @@ pub enum BGP4MP {
     MESSAGE_LOCAL_ADDPATH(MESSAGE),
     /// Local BGP message with Add-Path (32-bit ASN)
     MESSAGE_AS4_LOCAL_ADDPATH(MESSAGE_AS4),
+    /// Unrecognized subtype, kept as the undecoded body
+    ///
+    /// Vendor-specific subtypes end up here; decode them with
+    /// [`SubtypeDecoders`].
+    Unknown { sub_type: u16, body: Vec<u8> },
 }
 
 impl BGP4MP {
@@ -72,13 +107,17 @@ impl BGP4MP {
     /// * `stream` - The input stream positioned at the record body
     #[inline]
     pub fn parse(header: &Header, stream: &mut impl Read) -> std::io::Result<Self> {
-        // Calculate actual body length for extended types
-        let body_length = if header.record_type == 17 {
-            // BGP4MP_ET
-            header.length.saturating_sub(4)
-        } else {
-            header.length
-        };
+        let mut record = Self::parse_subtype(header, stream)?;
+        if crate::is_extended_type(header.record_type) {
+            record.set_timestamp_micros(header.extended);
+        }
+        Ok(record)
+    }
+
+    #[inline]
+    fn parse_subtype(header: &Header, stream: &mut impl Read) -> std::io::Result<Self> {
+        // Body length excludes the microsecond timestamp of BGP4MP_ET
+        let body_length = crate::body_length(header);
 
         match header.sub_type {
             subtypes::STATE_CHANGE => Ok(BGP4MP::STATE_CHANGE(STATE_CHANGE::parse(stream)?)),
@@ -112,13 +151,391 @@ impl BGP4MP {
             subtypes::MESSAGE_AS4_LOCAL_ADDPATH => Ok(BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(
                 MESSAGE_AS4::parse(body_length, stream)?,
             )),
-            _ => Err(Error::new(ErrorKind::InvalidData, "invalid BGP4MP subtype")),
+            sub_type => Ok(BGP4MP::Unknown {
+                sub_type,
+                body: crate::read_bounded(stream, body_length as usize)?,
+            }),
+        }
+    }
+
+    /// Microseconds from the BGP4MP_ET header, for the message and state
+    /// change subtypes.
+    ///
+    /// The value lives in the `timestamp_micros` field of [`STATE_CHANGE`],
+    /// [`STATE_CHANGE_AS4`], [`MESSAGE`] and [`MESSAGE_AS4`]. It is set by
+    /// [`parse`](Self::parse) and is `None` for plain BGP4MP. `write` does
+    /// not emit it, as it belongs to the MRT header; pass it to
+    /// [`crate::write`] instead. Struct literals of these types must set the
+    /// field, usually to `None`.
+    pub fn timestamp_micros(&self) -> Option<u32> {
+        match self {
+            BGP4MP::STATE_CHANGE(x) => x.timestamp_micros,
+            BGP4MP::STATE_CHANGE_AS4(x) => x.timestamp_micros,
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => x.timestamp_micros,
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => x.timestamp_micros,
+            BGP4MP::ENTRY(_) | BGP4MP::SNAPSHOT(_) | BGP4MP::Unknown { .. } => None,
+        }
+    }
+
+    /// The raw BGP message bytes of the MESSAGE subtypes.
+    pub(crate) fn message(&self) -> Option<&[u8]> {
+        match self {
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => Some(&x.message),
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => Some(&x.message),
+            _ => None,
+        }
+    }
+
+    fn set_timestamp_micros(&mut self, micros: u32) {
+        match self {
+            BGP4MP::STATE_CHANGE(x) => x.timestamp_micros = Some(micros),
+            BGP4MP::STATE_CHANGE_AS4(x) => x.timestamp_micros = Some(micros),
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => x.timestamp_micros = Some(micros),
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => x.timestamp_micros = Some(micros),
+            BGP4MP::ENTRY(_) | BGP4MP::SNAPSHOT(_) | BGP4MP::Unknown { .. } => {}
+        }
+    }
+
+    /// The MRT subtype of this record.
+    pub fn sub_type(&self) -> u16 {
+        match self {
+            BGP4MP::STATE_CHANGE(_) => subtypes::STATE_CHANGE,
+            BGP4MP::MESSAGE(_) => subtypes::MESSAGE,
+            BGP4MP::ENTRY(_) => subtypes::ENTRY,
+            BGP4MP::SNAPSHOT(_) => subtypes::SNAPSHOT,
+            BGP4MP::MESSAGE_AS4(_) => subtypes::MESSAGE_AS4,
+            BGP4MP::STATE_CHANGE_AS4(_) => subtypes::STATE_CHANGE_AS4,
+            BGP4MP::MESSAGE_LOCAL(_) => subtypes::MESSAGE_LOCAL,
+            BGP4MP::MESSAGE_AS4_LOCAL(_) => subtypes::MESSAGE_AS4_LOCAL,
+            BGP4MP::MESSAGE_ADDPATH(_) => subtypes::MESSAGE_ADDPATH,
+            BGP4MP::MESSAGE_AS4_ADDPATH(_) => subtypes::MESSAGE_AS4_ADDPATH,
+            BGP4MP::MESSAGE_LOCAL_ADDPATH(_) => subtypes::MESSAGE_LOCAL_ADDPATH,
+            BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(_) => subtypes::MESSAGE_AS4_LOCAL_ADDPATH,
+            BGP4MP::Unknown { sub_type, .. } => *sub_type,
+        }
+    }
+
+    /// The BGP session of a MESSAGE or STATE_CHANGE record, in any of
+    /// their variants.
+    ///
+    /// 16-bit AS numbers are widened, so the same session yields the same
+    /// key from MESSAGE and MESSAGE_AS4 records. Returns `None` for ENTRY,
+    /// SNAPSHOT and unknown subtypes.
+    pub fn session_key(&self) -> Option<SessionKey> {
+        let (peer_address, peer_as, local_address, local_as) = match self {
+            BGP4MP::STATE_CHANGE(x) => {
+                (x.peer_address, x.peer_as as u32, x.local_address, x.local_as as u32)
+            }
+            BGP4MP::STATE_CHANGE_AS4(x) => (x.peer_address, x.peer_as, x.local_address, x.local_as),
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => {
+                (x.peer_address, x.peer_as as u32, x.local_address, x.local_as as u32)
+            }
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => {
+                (x.peer_address, x.peer_as, x.local_address, x.local_as)
+            }
+            BGP4MP::ENTRY(_) | BGP4MP::SNAPSHOT(_) | BGP4MP::Unknown { .. } => return None,
+        };
+        Some(SessionKey {
+            peer_address,
+            peer_as,
+            local_address,
+            local_as,
+        })
+    }
+
+    /// Set the peer AS of a MESSAGE or STATE_CHANGE record, in any of their
+    /// variants.
+    ///
+    /// Every other field is kept, so [`write`](Self::write) then reproduces
+    /// the original body with only the peer AS bytes changed. Fails with
+    /// `InvalidInput` if `asn` does not fit the AS field of a 16-bit variant,
+    /// or for ENTRY, SNAPSHOT and unknown subtypes.
+    pub fn set_peer_as(&mut self, asn: u32) -> std::io::Result<()> {
+        match self.as_fields_mut()? {
+            AsFieldsMut::As2 { peer_as, .. } => *peer_as = narrow_as(asn)?,
+            AsFieldsMut::As4 { peer_as, .. } => *peer_as = asn,
+        }
+        Ok(())
+    }
+
+    /// Set the local AS of a MESSAGE or STATE_CHANGE record, as
+    /// [`set_peer_as`](Self::set_peer_as) does for the peer AS.
+    pub fn set_local_as(&mut self, asn: u32) -> std::io::Result<()> {
+        match self.as_fields_mut()? {
+            AsFieldsMut::As2 { local_as, .. } => *local_as = narrow_as(asn)?,
+            AsFieldsMut::As4 { local_as, .. } => *local_as = asn,
+        }
+        Ok(())
+    }
+
+    /// The AS number fields of a MESSAGE or STATE_CHANGE record.
+    fn as_fields_mut(&mut self) -> std::io::Result<AsFieldsMut<'_>> {
+        Ok(match self {
+            BGP4MP::STATE_CHANGE(x) => AsFieldsMut::As2 {
+                peer_as: &mut x.peer_as,
+                local_as: &mut x.local_as,
+            },
+            BGP4MP::STATE_CHANGE_AS4(x) => AsFieldsMut::As4 {
+                peer_as: &mut x.peer_as,
+                local_as: &mut x.local_as,
+            },
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => AsFieldsMut::As2 {
+                peer_as: &mut x.peer_as,
+                local_as: &mut x.local_as,
+            },
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => AsFieldsMut::As4 {
+                peer_as: &mut x.peer_as,
+                local_as: &mut x.local_as,
+            },
+            BGP4MP::ENTRY(_) | BGP4MP::SNAPSHOT(_) | BGP4MP::Unknown { .. } => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("BGP4MP subtype {} has no AS numbers", self.sub_type()),
+                ));
+            }
+        })
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            BGP4MP::STATE_CHANGE(x) => x.write(stream),
+            BGP4MP::ENTRY(x) => x.write(stream),
+            BGP4MP::SNAPSHOT(x) => x.write(stream),
+            BGP4MP::STATE_CHANGE_AS4(x) => x.write(stream),
+            BGP4MP::MESSAGE(x)
+            | BGP4MP::MESSAGE_LOCAL(x)
+            | BGP4MP::MESSAGE_ADDPATH(x)
+            | BGP4MP::MESSAGE_LOCAL_ADDPATH(x) => x.write(stream),
+            BGP4MP::MESSAGE_AS4(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL(x)
+            | BGP4MP::MESSAGE_AS4_ADDPATH(x)
+            | BGP4MP::MESSAGE_AS4_LOCAL_ADDPATH(x) => x.write(stream),
+            BGP4MP::Unknown { body, .. } => stream.write_all(body),
         }
     }
 }
 
-/// BGP state change with 16-bit AS numbers.
+/// Identifies one BGP session in an update stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SessionKey {
+    /// Peer IP address
+    pub peer_address: IpAddr,
+    /// Peer AS number
+    pub peer_as: u32,
+    /// Local (collector) IP address
+    pub local_address: IpAddr,
+    /// Local AS number
+    pub local_as: u32,
+}
+
+/// Groups BGP4MP records by BGP session.
+///
+/// Feed every record of an update stream through [`feed`](Self::feed); it
+/// returns the [`SessionKey`] of each MESSAGE and STATE_CHANGE record, from
+/// plain BGP4MP or BGP4MP_ET, and counts the records seen per session.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use mrt_ingester::bgp4mp::SessionDemux;
+///
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// let mut demux = SessionDemux::new();
+/// let mut timelines = HashMap::new();
+/// while let Some((header, record)) = mrt_ingester::read(&mut reader).unwrap() {
+///     if let Some(key) = demux.feed(&record) {
+///         timelines.entry(key).or_insert_with(Vec::new).push(header.timestamp);
+///     }
+/// }
+/// println!("{} sessions", demux.len());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SessionDemux {
+    sessions: HashMap<SessionKey, u64>,
+}
+
+impl SessionDemux {
+    /// Create a demultiplexer with no sessions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the session of `record`, counting it, or `None` if it is not
+    /// a BGP4MP MESSAGE or STATE_CHANGE record.
+    pub fn feed(&mut self, record: &Record) -> Option<SessionKey> {
+        let key = match record {
+            Record::BGP4MP(x) | Record::BGP4MP_ET(x) => x.session_key()?,
+            _ => return None,
+        };
+        *self.sessions.entry(key).or_default() += 1;
+        Some(key)
+    }
+
+    /// Number of distinct sessions seen.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns `true` if no session has been seen.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Number of records seen for `key`.
+    pub fn count(&self, key: &SessionKey) -> u64 {
+        self.sessions.get(key).copied().unwrap_or(0)
+    }
+
+    /// The sessions seen and their record counts, in no particular order.
+    pub fn sessions(&self) -> impl Iterator<Item = (&SessionKey, u64)> + '_ {
+        self.sessions.iter().map(|(key, &count)| (key, count))
+    }
+}
+
+/// Mutable AS number fields of a 16-bit or 32-bit record variant.
+enum AsFieldsMut<'a> {
+    As2 {
+        peer_as: &'a mut u16,
+        local_as: &'a mut u16,
+    },
+    As4 {
+        peer_as: &'a mut u32,
+        local_as: &'a mut u32,
+    },
+}
+
+/// Narrow an AS number to a 16-bit AS field.
+fn narrow_as(asn: u32) -> std::io::Result<u16> {
+    u16::try_from(asn).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("AS number {} does not fit a 16-bit AS field", asn),
+        )
+    })
+}
+
+/// Decoders for vendor-specific BGP4MP subtypes.
+///
+/// Subtypes the crate does not know are parsed as [`BGP4MP::Unknown`] with
+/// the raw body. A `SubtypeDecoders` table maps such subtype numbers to
+/// caller-supplied functions, so proprietary extensions can be decoded
+/// without changes to the crate.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::bgp4mp::SubtypeDecoders;
+/// use mrt_ingester::{Header, Record};
+///
+/// // Vendor subtype 100 carries a 4-byte sequence number
+/// fn sequence(_header: &Header, body: &[u8]) -> std::io::Result<u32> {
+///     let bytes = body.try_into().map_err(|_| {
+///         std::io::Error::new(std::io::ErrorKind::InvalidData, "bad sequence length")
+///     })?;
+///     Ok(u32::from_be_bytes(bytes))
+/// }
+///
+/// let mut decoders = SubtypeDecoders::new();
+/// decoders.register(100, sequence).unwrap();
+///
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("updates.mrt").unwrap());
+/// while let Some((header, record)) = mrt_ingester::read(&mut reader).unwrap() {
+///     if let Record::BGP4MP(bgp4mp) | Record::BGP4MP_ET(bgp4mp) = &record {
+///         if let Some(seq) = decoders.decode(&header, bgp4mp) {
+///             println!("sequence {}", seq.unwrap());
+///         }
+///     }
+/// }
+/// ```
 #[derive(Debug, Clone)]
+pub struct SubtypeDecoders<T> {
+    decoders: HashMap<u16, SubtypeDecoder<T>>,
+}
+
+/// Decoder for one vendor subtype: takes the record header and raw body.
+pub type SubtypeDecoder<T> = fn(&Header, &[u8]) -> std::io::Result<T>;
+
+impl<T> Default for SubtypeDecoders<T> {
+    fn default() -> Self {
+        SubtypeDecoders {
+            decoders: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SubtypeDecoders<T> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `decoder` for `sub_type`, replacing any earlier one.
+    ///
+    /// Returns an `InvalidInput` error for a standard subtype, since those are
+    /// always decoded by the crate and never reach [`BGP4MP::Unknown`].
+    pub fn register(&mut self, sub_type: u16, decoder: SubtypeDecoder<T>) -> std::io::Result<()> {
+        if let Some(name) = subtype_name(sub_type) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("BGP4MP subtype {} is the standard {} subtype", sub_type, name),
+            ));
+        }
+        self.decoders.insert(sub_type, decoder);
+        Ok(())
+    }
+
+    /// Decode `record` with the decoder registered for its subtype.
+    ///
+    /// `header` is the header the record was read with. Returns `None` if
+    /// the record is not [`BGP4MP::Unknown`] or no decoder is registered for
+    /// its subtype.
+    pub fn decode(&self, header: &Header, record: &BGP4MP) -> Option<std::io::Result<T>> {
+        match record {
+            BGP4MP::Unknown { sub_type, body } => {
+                let decoder = self.decoders.get(sub_type)?;
+                Some(decoder(header, body))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// BGP state change with 16-bit AS numbers.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct STATE_CHANGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -134,6 +551,8 @@ pub struct STATE_CHANGE {
     pub old_state: u16,
     /// New BGP FSM state
     pub new_state: u16,
+    /// Microseconds from the BGP4MP_ET header; see [`BGP4MP::timestamp_micros`]
+    pub timestamp_micros: Option<u32>,
 }
 
 impl STATE_CHANGE {
@@ -167,12 +586,51 @@ impl STATE_CHANGE {
             local_address,
             old_state,
             new_state,
+            timestamp_micros: None,
         })
     }
+
+    /// Create a STATE_CHANGE record with interface index 0.
+    ///
+    /// Fails if the peer and local addresses are of different families.
+    pub fn new(
+        peer_as: u16,
+        local_as: u16,
+        peer_address: IpAddr,
+        local_address: IpAddr,
+        old_state: u16,
+        new_state: u16,
+    ) -> std::io::Result<Self> {
+        common_afi(&peer_address, &local_address)?;
+        Ok(STATE_CHANGE {
+            peer_as,
+            local_as,
+            interface: 0,
+            peer_address,
+            local_address,
+            old_state,
+            new_state,
+            timestamp_micros: None,
+        })
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.peer_address, &self.local_address)?;
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_u16::<BigEndian>(self.local_as)?;
+        stream.write_u16::<BigEndian>(self.interface)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.peer_address)?;
+        write_ip(stream, &self.local_address)?;
+        stream.write_u16::<BigEndian>(self.old_state)?;
+        stream.write_u16::<BigEndian>(self.new_state)
+    }
 }
 
 /// BGP message with 16-bit AS numbers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MESSAGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -186,6 +644,8 @@ pub struct MESSAGE {
     pub local_address: IpAddr,
     /// Raw BGP message bytes
     pub message: Vec<u8>,
+    /// Microseconds from the BGP4MP_ET header; see [`BGP4MP::timestamp_micros`]
+    pub timestamp_micros: Option<u32>,
 }
 
 impl MESSAGE {
@@ -205,12 +665,13 @@ impl MESSAGE {
         let local_as = stream.read_u16::<BigEndian>()?;
         let interface = stream.read_u16::<BigEndian>()?;
         let afi = read_afi(stream)?;
-        let peer_address = read_ip_by_afi(stream, &afi)?;
-        let local_address = read_ip_by_afi(stream, &afi)?;
 
         // Calculate header size: 2 + 2 + 2 + 2 + (afi.size() * 2)
         let header_size = 8 + (afi.size() * 2);
-        let message_len = body_length.saturating_sub(header_size) as usize;
+        check_message_header(body_length, header_size, &afi)?;
+        let peer_address = read_ip_by_afi(stream, &afi)?;
+        let local_address = read_ip_by_afi(stream, &afi)?;
+        let message_len = (body_length - header_size) as usize;
         let mut message = vec![0u8; message_len];
         stream.read_exact(&mut message)?;
 
@@ -221,12 +682,49 @@ impl MESSAGE {
             peer_address,
             local_address,
             message,
+            timestamp_micros: None,
+        })
+    }
+
+    /// Create a MESSAGE record with interface index 0.
+    ///
+    /// The AFI is inferred from the addresses when writing; fails if the
+    /// peer and local addresses are of different families.
+    pub fn new(
+        peer_as: u16,
+        local_as: u16,
+        peer_address: IpAddr,
+        local_address: IpAddr,
+        message: Vec<u8>,
+    ) -> std::io::Result<Self> {
+        common_afi(&peer_address, &local_address)?;
+        Ok(MESSAGE {
+            peer_as,
+            local_as,
+            interface: 0,
+            peer_address,
+            local_address,
+            message,
+            timestamp_micros: None,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.peer_address, &self.local_address)?;
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_u16::<BigEndian>(self.local_as)?;
+        stream.write_u16::<BigEndian>(self.interface)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.peer_address)?;
+        write_ip(stream, &self.local_address)?;
+        stream.write_all(&self.message)
+    }
 }
 
 /// BGP message with 32-bit AS numbers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MESSAGE_AS4 {
     /// Peer AS number (32-bit)
     pub peer_as: u32,
@@ -240,6 +738,8 @@ pub struct MESSAGE_AS4 {
     pub local_address: IpAddr,
     /// Raw BGP message bytes
     pub message: Vec<u8>,
+    /// Microseconds from the BGP4MP_ET header; see [`BGP4MP::timestamp_micros`]
+    pub timestamp_micros: Option<u32>,
 }
 
 impl MESSAGE_AS4 {
@@ -259,12 +759,13 @@ impl MESSAGE_AS4 {
         let local_as = stream.read_u32::<BigEndian>()?;
         let interface = stream.read_u16::<BigEndian>()?;
         let afi = read_afi(stream)?;
-        let peer_address = read_ip_by_afi(stream, &afi)?;
-        let local_address = read_ip_by_afi(stream, &afi)?;
 
         // Calculate header size: 4 + 4 + 2 + 2 + (afi.size() * 2)
         let header_size = 12 + (afi.size() * 2);
-        let message_len = body_length.saturating_sub(header_size) as usize;
+        check_message_header(body_length, header_size, &afi)?;
+        let peer_address = read_ip_by_afi(stream, &afi)?;
+        let local_address = read_ip_by_afi(stream, &afi)?;
+        let message_len = (body_length - header_size) as usize;
         let mut message = vec![0u8; message_len];
         stream.read_exact(&mut message)?;
 
@@ -275,12 +776,70 @@ impl MESSAGE_AS4 {
             peer_address,
             local_address,
             message,
+            timestamp_micros: None,
         })
     }
+
+    /// Create a MESSAGE_AS4 record with interface index 0.
+    ///
+    /// The AFI is inferred from the addresses when writing; fails if the
+    /// peer and local addresses are of different families.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mrt_ingester::bgp4mp::MESSAGE_AS4;
+    ///
+    /// let keepalive = [&[0xFF; 16][..], &[0x00, 0x13, 0x04]].concat();
+    /// let msg = MESSAGE_AS4::new(
+    ///     64512,
+    ///     65000,
+    ///     "192.0.2.1".parse().unwrap(),
+    ///     "192.0.2.2".parse().unwrap(),
+    ///     keepalive,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(msg.interface, 0);
+    ///
+    /// // The wire format has a single AFI for both addresses
+    /// assert!(MESSAGE_AS4::new(1, 2, "192.0.2.1".parse().unwrap(), "::1".parse().unwrap(), vec![])
+    ///     .is_err());
+    /// ```
+    pub fn new(
+        peer_as: u32,
+        local_as: u32,
+        peer_address: IpAddr,
+        local_address: IpAddr,
+        message: Vec<u8>,
+    ) -> std::io::Result<Self> {
+        common_afi(&peer_address, &local_address)?;
+        Ok(MESSAGE_AS4 {
+            peer_as,
+            local_as,
+            interface: 0,
+            peer_address,
+            local_address,
+            message,
+            timestamp_micros: None,
+        })
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.peer_address, &self.local_address)?;
+        stream.write_u32::<BigEndian>(self.peer_as)?;
+        stream.write_u32::<BigEndian>(self.local_as)?;
+        stream.write_u16::<BigEndian>(self.interface)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.peer_address)?;
+        write_ip(stream, &self.local_address)?;
+        stream.write_all(&self.message)
+    }
 }
 
 /// BGP state change with 32-bit AS numbers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct STATE_CHANGE_AS4 {
     /// Peer AS number (32-bit)
     pub peer_as: u32,
@@ -296,6 +855,8 @@ pub struct STATE_CHANGE_AS4 {
     pub old_state: u16,
     /// New BGP FSM state
     pub new_state: u16,
+    /// Microseconds from the BGP4MP_ET header; see [`BGP4MP::timestamp_micros`]
+    pub timestamp_micros: Option<u32>,
 }
 
 impl STATE_CHANGE_AS4 {
@@ -328,12 +889,68 @@ impl STATE_CHANGE_AS4 {
             local_address,
             old_state,
             new_state,
+            timestamp_micros: None,
         })
     }
+
+    /// Create a STATE_CHANGE_AS4 record with interface index 0.
+    ///
+    /// Fails if the peer and local addresses are of different families.
+    pub fn new(
+        peer_as: u32,
+        local_as: u32,
+        peer_address: IpAddr,
+        local_address: IpAddr,
+        old_state: u16,
+        new_state: u16,
+    ) -> std::io::Result<Self> {
+        common_afi(&peer_address, &local_address)?;
+        Ok(STATE_CHANGE_AS4 {
+            peer_as,
+            local_as,
+            interface: 0,
+            peer_address,
+            local_address,
+            old_state,
+            new_state,
+            timestamp_micros: None,
+        })
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.peer_address, &self.local_address)?;
+        stream.write_u32::<BigEndian>(self.peer_as)?;
+        stream.write_u32::<BigEndian>(self.local_as)?;
+        stream.write_u16::<BigEndian>(self.interface)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.peer_address)?;
+        write_ip(stream, &self.local_address)?;
+        stream.write_u16::<BigEndian>(self.old_state)?;
+        stream.write_u16::<BigEndian>(self.new_state)
+    }
+}
+
+/// Fail if a MESSAGE body is too short for the fixed fields its AFI implies.
+///
+/// No valid record is that short; it usually means a corrupt AFI or length,
+/// and reading on would desynchronize the following records.
+fn check_message_header(body_length: u32, header_size: u32, afi: &AFI) -> std::io::Result<()> {
+    if body_length < header_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "BGP4MP message record is {} bytes but its {:?} header needs {}",
+                body_length, afi, header_size
+            ),
+        ));
+    }
+    Ok(())
 }
 
 /// Deprecated snapshot pointer.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SNAPSHOT {
     /// View number for multi-view recordings
     pub view_number: u16,
@@ -355,10 +972,25 @@ impl SNAPSHOT {
             filename,
         })
     }
+
+    /// The filename up to its first NUL, decoded lossily as UTF-8.
+    ///
+    /// `filename` keeps the raw bytes, including the NUL terminator and any
+    /// padding, so that records re-encode unchanged.
+    pub fn filename_str(&self) -> Cow<'_, str> {
+        crate::records::nul_terminated_str(&self.filename)
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.view_number)?;
+        stream.write_all(&self.filename)
+    }
 }
 
 /// Deprecated RIB entry format.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ENTRY {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -392,7 +1024,11 @@ pub struct ENTRY {
 
 impl ENTRY {
     /// Parse an ENTRY record.
-    pub fn parse(_body_length: u32, stream: &mut impl Read) -> std::io::Result<Self> {
+    ///
+    /// The next hop carries its own AFI, which need not match the peer AFI:
+    /// old Zebra dumps hold IPv6 sessions with an IPv4 next hop. Returns an
+    /// `InvalidData` error if the fields do not add up to `body_length`.
+    pub fn parse(body_length: u32, stream: &mut impl Read) -> std::io::Result<Self> {
         let peer_as = stream.read_u16::<BigEndian>()?;
         let local_as = stream.read_u16::<BigEndian>()?;
         let interface = stream.read_u16::<BigEndian>()?;
@@ -415,6 +1051,20 @@ impl ENTRY {
 
         // Read attribute length and attributes
         let attr_len = stream.read_u16::<BigEndian>()? as usize;
+        let fixed_size = 24 + afi_enum.size() as usize * 2 + next_hop_afi.size() as usize;
+        if fixed_size + prefix.len() + attr_len != body_length as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "BGP4MP ENTRY length mismatch: record is {} bytes but fields need {} \
+                     (peer AFI {:?}, next hop AFI {:?})",
+                    body_length,
+                    fixed_size + prefix.len() + attr_len,
+                    afi_enum,
+                    next_hop_afi
+                ),
+            ));
+        }
         let mut attributes = vec![0u8; attr_len];
         stream.read_exact(&mut attributes)?;
 
@@ -435,6 +1085,96 @@ impl ENTRY {
             attributes,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.peer_address, &self.local_address)?;
+        check_prefix(self.prefix_length, &self.prefix)?;
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_u16::<BigEndian>(self.local_as)?;
+        stream.write_u16::<BigEndian>(self.interface)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.peer_address)?;
+        write_ip(stream, &self.local_address)?;
+        stream.write_u16::<BigEndian>(self.view_number)?;
+        stream.write_u16::<BigEndian>(self.status)?;
+        stream.write_u32::<BigEndian>(self.time_last_change)?;
+        write_afi(stream, &afi_of(&self.next_hop))?;
+        write_ip(stream, &self.next_hop)?;
+        stream.write_u16::<BigEndian>(self.afi)?;
+        stream.write_u8(self.safi)?;
+        stream.write_u8(self.prefix_length)?;
+        stream.write_all(&self.prefix)?;
+        write_u16_prefixed(stream, &self.attributes)
+    }
+
+    /// Returns the prefix as a [`Prefix`] in the family given by `afi`.
+    ///
+    /// `prefix` holds only the leading `ceil(prefix_length / 8)` bytes; the
+    /// rest of the address is zero-filled. Fails with `InvalidData` if `afi`
+    /// is not IPv4 or IPv6 or the prefix does not fit it.
+    pub fn prefix_addr(&self) -> std::io::Result<Prefix> {
+        let afi = AFI::from_u16(self.afi)?;
+        Prefix::from_wire(&afi, self.prefix_length, &self.prefix)
+    }
+
+    /// Decode `attributes` with `as4` as the AS number width.
+    ///
+    /// The deprecated ENTRY subtype only carries 16-bit AS numbers, so pass
+    /// `false`.
+    pub fn attributes_parsed(&self, as4: bool) -> std::io::Result<PathAttributes> {
+        PathAttributes::parse(&self.attributes, as4)
+    }
+}
+
+impl HasPeer for STATE_CHANGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        self.peer_address
+    }
+}
+
+impl HasPeer for MESSAGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        self.peer_address
+    }
+}
+
+impl HasPeer for MESSAGE_AS4 {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        self.peer_address
+    }
+}
+
+impl HasPeer for STATE_CHANGE_AS4 {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        self.peer_address
+    }
+}
+
+impl HasPeer for ENTRY {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        self.peer_address
+    }
 }
 
 #[cfg(test)]
@@ -461,7 +1201,7 @@ mod tests {
             0x00, 0x01, // old_state = 1
             0x00, 0x06, // new_state = 6
         ];
-        let result = BGP4MP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = BGP4MP::parse(&header, &mut &data[..]).unwrap();
         match result {
             BGP4MP::STATE_CHANGE(sc) => {
                 assert_eq!(sc.peer_as, 100);
@@ -470,6 +1210,7 @@ mod tests {
                 assert_eq!(sc.local_address, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
                 assert_eq!(sc.old_state, 1);
                 assert_eq!(sc.new_state, 6);
+                assert_eq!(sc.timestamp_micros, None);
             }
             _ => panic!("Expected STATE_CHANGE"),
         }
@@ -493,7 +1234,7 @@ mod tests {
             10, 0, 0, 1, // local_address
             0x01, 0x02, 0x03, 0x04, // message
         ];
-        let result = BGP4MP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = BGP4MP::parse(&header, &mut &data[..]).unwrap();
         match result {
             BGP4MP::MESSAGE_AS4(msg) => {
                 assert_eq!(msg.peer_as, 65000);
@@ -505,6 +1246,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_has_peer() {
+        fn peer(record: &impl HasPeer) -> (u32, IpAddr) {
+            (record.peer_as_u32(), record.peer_addr())
+        }
+
+        let v4: IpAddr = "192.0.2.1".parse().unwrap();
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let msg = MESSAGE::new(64500, 64501, v4, "192.0.2.2".parse().unwrap(), vec![]).unwrap();
+        assert_eq!(peer(&msg), (64500, v4));
+        let msg = MESSAGE_AS4::new(4200000000, 64501, v6, "2001:db8::2".parse().unwrap(), vec![])
+            .unwrap();
+        assert_eq!(peer(&msg), (4200000000, v6));
+
+        let legacy = crate::bgp::MESSAGE {
+            peer_as: 65001,
+            peer_ip: Ipv4Addr::new(192, 0, 2, 1),
+            local_as: 65002,
+            local_ip: Ipv4Addr::new(192, 0, 2, 2),
+            message: vec![],
+        };
+        assert_eq!(peer(&legacy), (65001, v4));
+        let plus = crate::bgp4plus::STATE_CHANGE {
+            peer_as: 65001,
+            peer_ip: "2001:db8::1".parse().unwrap(),
+            old_state: 1,
+            new_state: 6,
+        };
+        assert_eq!(peer(&plus), (65001, v6));
+    }
+
+    #[test]
+    fn test_set_as_numbers() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 16,
+            sub_type: 0, // STATE_CHANGE
+            length: 20,
+        };
+        let data: &[u8] = &[
+            0x00, 0x64, // peer_as = 100
+            0x00, 0xC8, // local_as = 200
+            0x00, 0x00, // interface = 0
+            0x00, 0x01, // AFI = IPv4
+            192, 168, 1, 1, // peer_address
+            10, 0, 0, 1, // local_address
+            0x00, 0x01, // old_state = 1
+            0x00, 0x06, // new_state = 6
+        ];
+        let mut record = BGP4MP::parse(&header, &mut &data[..]).unwrap();
+        record.set_peer_as(65001).unwrap();
+        let err = record.set_local_as(65536).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let mut out = Vec::new();
+        record.write(&mut out).unwrap();
+        let mut expected = data.to_vec();
+        expected[..2].copy_from_slice(&[0xFD, 0xE9]);
+        assert_eq!(out, expected);
+
+        let header = Header {
+            sub_type: 4, // MESSAGE_AS4
+            length: 24,
+            ..header
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, 0xFD, 0xE8, // peer_as = 65000
+            0x00, 0x00, 0xFD, 0xE9, // local_as = 65001
+            0x00, 0x00, // interface = 0
+            0x00, 0x01, // AFI = IPv4
+            192, 168, 1, 1, // peer_address
+            10, 0, 0, 1, // local_address
+            0x01, 0x02, 0x03, 0x04, // message
+        ];
+        let mut record = BGP4MP::parse(&header, &mut &data[..]).unwrap();
+        record.set_local_as(4_200_000_000).unwrap();
+        let mut out = Vec::new();
+        record.write(&mut out).unwrap();
+        let mut expected = data.to_vec();
+        expected[4..8].copy_from_slice(&4_200_000_000u32.to_be_bytes());
+        assert_eq!(out, expected);
+
+        let mut unknown = BGP4MP::Unknown {
+            sub_type: 42,
+            body: Vec::new(),
+        };
+        assert!(unknown.set_peer_as(1).is_err());
+    }
+
+    #[test]
+    fn test_message_as4_new_write() {
+        let msg = MESSAGE_AS4::new(
+            65000,
+            65001,
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            vec![0x01, 0x02, 0x03, 0x04],
+        )
+        .unwrap();
+        let record = BGP4MP::MESSAGE_AS4(msg);
+        assert_eq!(record.sub_type(), 4);
+
+        let mut out = Vec::new();
+        record.write(&mut out).unwrap();
+        assert_eq!(
+            out,
+            &[
+                0x00, 0x00, 0xFD, 0xE8, // peer_as = 65000
+                0x00, 0x00, 0xFD, 0xE9, // local_as = 65001
+                0x00, 0x00, // interface = 0
+                0x00, 0x01, // AFI = IPv4 (inferred)
+                192, 168, 1, 1, // peer_address
+                10, 0, 0, 1, // local_address
+                0x01, 0x02, 0x03, 0x04, // message
+            ]
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_mixed_families() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let err = MESSAGE_AS4::new(1, 2, v4, v6, Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(MESSAGE::new(1, 2, v6, v4, Vec::new()).is_err());
+        assert!(STATE_CHANGE_AS4::new(1, 2, v4, v6, 1, 6).is_err());
+
+        // Fields are public, so write re-checks
+        let mut msg = MESSAGE::new(1, 2, v4, v4, Vec::new()).unwrap();
+        msg.local_address = v6;
+        assert!(msg.write(&mut Vec::new()).is_err());
+    }
+
     #[test]
     fn test_parse_bgp4mp_message_ipv6() {
         let header = Header {
@@ -537,4 +1412,261 @@ mod tests {
             _ => panic!("Expected MESSAGE"),
         }
     }
+
+    #[test]
+    fn test_message_shorter_than_header() {
+        let mut data = vec![
+            0x00, 0x00, 0xFD, 0xE8, // peer_as = 65000
+            0x00, 0x00, 0xFD, 0xE9, // local_as = 65001
+            0x00, 0x00, // interface = 0
+            0x00, 0x02, // AFI = IPv6
+        ];
+        data.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        data.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        data.extend_from_slice(&[0xFF; 19]); // message
+
+        // Length sized for IPv4 addresses: 12 + 8 + 19
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 16,
+            sub_type: 4, // MESSAGE_AS4
+            length: 39,
+        };
+        let err = BGP4MP::parse(&header, &mut data.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("IPV6 header needs 44"), "{}", err);
+
+        let header = Header {
+            sub_type: 1, // MESSAGE
+            length: 39,
+            ..header
+        };
+        let err = BGP4MP::parse(&header, &mut &data[4..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// ENTRY body for an IPv6 session whose next hop is IPv4, as written by
+    /// old Zebra versions.
+    fn zebra_entry_v6_peer_v4_next_hop() -> Vec<u8> {
+        let mut data = vec![
+            0x00, 0x64, // peer_as = 100
+            0x00, 0xC8, // local_as = 200
+            0x00, 0x03, // interface = 3
+            0x00, 0x02, // afi = IPv6
+        ];
+        // peer: 2001:db8::1
+        data.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        // local: 2001:db8::2
+        data.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        data.extend_from_slice(&[
+            0x00, 0x00, // view_number
+            0x00, 0x01, // status
+            0x3B, 0x9A, 0xCA, 0x00, // time_last_change
+            0x00, 0x01, // next hop afi = IPv4
+            192, 0, 2, 1, // next hop
+            0x00, 0x02, // afi = IPv6
+            0x01, // safi = unicast
+            0x20, // prefix_length = 32
+            0x20, 0x01, 0x0d, 0xb8, // prefix
+            0x00, 0x04, // attr_len
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+        ]);
+        data
+    }
+
+    #[test]
+    fn test_entry_ipv6_peer_ipv4_next_hop() {
+        let data = zebra_entry_v6_peer_v4_next_hop();
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 16,
+            sub_type: 2, // ENTRY
+            length: data.len() as u32,
+        };
+
+        let result = BGP4MP::parse(&header, &mut data.as_slice()).unwrap();
+        let entry = match &result {
+            BGP4MP::ENTRY(entry) => entry,
+            _ => panic!("Expected ENTRY"),
+        };
+        assert!(entry.peer_address.is_ipv6());
+        assert_eq!(entry.next_hop, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(entry.afi, 2);
+        assert_eq!(entry.safi, 1);
+        assert_eq!(entry.prefix, vec![0x20, 0x01, 0x0d, 0xb8]);
+        assert_eq!(entry.attributes, vec![0x40, 0x01, 0x01, 0x00]);
+
+        let mut out = Vec::new();
+        result.write(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_entry_prefix_addr() {
+        let mut data = zebra_entry_v6_peer_v4_next_hop();
+        // Replace the /32 prefix and what follows it
+        data.truncate(data.len() - 11);
+        data.extend_from_slice(&[
+            0x30, // prefix_length = 48
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x42, // prefix
+            0x00, 0x04, // attr_len
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+        ]);
+
+        let mut entry = ENTRY::parse(data.len() as u32, &mut data.as_slice()).unwrap();
+        assert_eq!(entry.prefix_addr().unwrap().to_string(), "2001:db8:42::/48");
+
+        entry.afi = 3;
+        let err = entry.prefix_addr().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_entry_length_mismatch() {
+        let mut data = zebra_entry_v6_peer_v4_next_hop();
+        // Claim an IPv6 next hop while only four address bytes follow
+        data[49] = 0x02;
+        data.extend_from_slice(&[0; 12]);
+
+        let err = ENTRY::parse(data.len() as u32 - 12, &mut data.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("length mismatch"));
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let data: &[u8] = &[
+            0x00, 0x02, // view_number = 2
+            b'v', b'2', b'.', b'r', b'i', b'b', 0x00, 0x00, // filename
+        ];
+        let snapshot = SNAPSHOT::parse(data.len() as u32, &mut &data[..]).unwrap();
+        assert_eq!(snapshot.view_number, 2);
+        assert_eq!(snapshot.filename.len(), 8);
+        assert_eq!(snapshot.filename_str(), "v2.rib");
+
+        let mut out = Vec::new();
+        snapshot.write(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_session_demux() {
+        let peer: IpAddr = "192.0.2.1".parse().unwrap();
+        let local: IpAddr = "192.0.2.2".parse().unwrap();
+        let state_change = BGP4MP::STATE_CHANGE(STATE_CHANGE {
+            peer_as: 65001,
+            local_as: 65002,
+            interface: 0,
+            peer_address: peer,
+            local_address: local,
+            old_state: 1,
+            new_state: 6,
+            timestamp_micros: None,
+        });
+        let message_as4 = MESSAGE_AS4 {
+            peer_as: 65001,
+            local_as: 65002,
+            interface: 0,
+            peer_address: peer,
+            local_address: local,
+            message: vec![0xFF; 19],
+            timestamp_micros: Some(5),
+        };
+        let other = MESSAGE_AS4 {
+            peer_as: 4_200_000_000,
+            ..message_as4.clone()
+        };
+
+        let mut demux = SessionDemux::new();
+        let key = demux.feed(&Record::BGP4MP(state_change)).unwrap();
+        assert_eq!(key.peer_as, 65001);
+        assert_eq!(key.local_address, local);
+        // The 32-bit variant maps to the same session
+        let record = Record::BGP4MP_ET(BGP4MP::MESSAGE_AS4(message_as4));
+        assert_eq!(demux.feed(&record), Some(key));
+        let record = Record::BGP4MP(BGP4MP::MESSAGE_AS4(other));
+        assert_ne!(demux.feed(&record), Some(key));
+        assert_eq!(demux.feed(&Record::NULL), None);
+        let snapshot = BGP4MP::SNAPSHOT(SNAPSHOT {
+            view_number: 0,
+            filename: Vec::new(),
+        });
+        assert_eq!(demux.feed(&Record::BGP4MP(snapshot)), None);
+
+        assert_eq!(demux.len(), 2);
+        assert_eq!(demux.count(&key), 2);
+        assert_eq!(demux.sessions().map(|(_, count)| count).sum::<u64>(), 3);
+    }
+
+    #[test]
+    fn test_unknown_subtype() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 16,
+            sub_type: 42,
+            length: 3,
+        };
+        let data: &[u8] = &[0x01, 0x02, 0x03];
+        let result = BGP4MP::parse(&header, &mut &data[..]).unwrap();
+        match &result {
+            BGP4MP::Unknown { sub_type, body } => {
+                assert_eq!(*sub_type, 42);
+                assert_eq!(body, data);
+            }
+            _ => panic!("Expected Unknown"),
+        }
+        assert_eq!(result.sub_type(), 42);
+
+        let mut out = Vec::new();
+        result.write(&mut out).unwrap();
+        assert_eq!(out, data);
+
+        // A huge length on a short stream fails without allocating it
+        let header = Header {
+            length: u32::MAX,
+            ..header
+        };
+        let err = BGP4MP::parse(&header, &mut &data[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_subtype_decoders() {
+        fn sum(header: &Header, body: &[u8]) -> std::io::Result<u32> {
+            if body.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidData, "empty body"));
+            }
+            Ok(header.timestamp + body.iter().map(|&b| b as u32).sum::<u32>())
+        }
+
+        let mut decoders = SubtypeDecoders::new();
+        decoders.register(42, sum).unwrap();
+        let err = decoders.register(4, sum).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 16,
+            sub_type: 42,
+            length: 3,
+        };
+        let data: &[u8] = &[0x01, 0x02, 0x03];
+        let record = BGP4MP::parse(&header, &mut &data[..]).unwrap();
+        assert_eq!(decoders.decode(&header, &record).unwrap().unwrap(), 1006);
+
+        let empty = BGP4MP::Unknown {
+            sub_type: 42,
+            body: Vec::new(),
+        };
+        assert!(decoders.decode(&header, &empty).unwrap().is_err());
+        let other = BGP4MP::Unknown {
+            sub_type: 43,
+            body: data.to_vec(),
+        };
+        assert!(decoders.decode(&header, &other).is_none());
+    }
 }
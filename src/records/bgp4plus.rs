@@ -8,10 +8,12 @@
 #![allow(non_camel_case_types)]
 
 use crate::address::read_ipv6;
+use crate::records::HasPeer;
 use crate::Header;
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind, Read};
-use std::net::Ipv6Addr;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv6Addr};
 
 /// BGP4PLUS subtype constants
 mod subtypes {
@@ -25,11 +27,27 @@ mod subtypes {
     pub const KEEPALIVE: u16 = 7;
 }
 
+/// Name of a subtype, or `None` if it is not known.
+pub(crate) fn subtype_name(sub_type: u16) -> Option<&'static str> {
+    Some(match sub_type {
+        subtypes::NULL => "NULL",
+        subtypes::UPDATE => "UPDATE",
+        subtypes::PREF_UPDATE => "PREF_UPDATE",
+        subtypes::STATE_CHANGE => "STATE_CHANGE",
+        subtypes::SYNC => "SYNC",
+        subtypes::OPEN => "OPEN",
+        subtypes::NOTIFY => "NOTIFY",
+        subtypes::KEEPALIVE => "KEEPALIVE",
+        _ => return None,
+    })
+}
+
 /// Legacy BGP4+ record enum for IPv6 peers.
 ///
 /// Similar to `BGP` but uses IPv6 addresses. This is a deprecated record type;
 /// prefer `BGP4MP` for new implementations.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(non_camel_case_types)]
 pub enum BGP4PLUS {
     /// Null subtype
@@ -71,12 +89,39 @@ impl BGP4PLUS {
             _ => Err(Error::new(ErrorKind::InvalidData, "invalid BGP4PLUS subtype")),
         }
     }
+
+    /// The MRT subtype of this record.
+    pub fn sub_type(&self) -> u16 {
+        match self {
+            BGP4PLUS::NULL => subtypes::NULL,
+            BGP4PLUS::UPDATE(_) => subtypes::UPDATE,
+            BGP4PLUS::PREF_UPDATE => subtypes::PREF_UPDATE,
+            BGP4PLUS::STATE_CHANGE(_) => subtypes::STATE_CHANGE,
+            BGP4PLUS::SYNC(_) => subtypes::SYNC,
+            BGP4PLUS::OPEN(_) => subtypes::OPEN,
+            BGP4PLUS::NOTIFY(_) => subtypes::NOTIFY,
+            BGP4PLUS::KEEPALIVE(_) => subtypes::KEEPALIVE,
+        }
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            BGP4PLUS::NULL | BGP4PLUS::PREF_UPDATE => Ok(()),
+            BGP4PLUS::STATE_CHANGE(x) => x.write(stream),
+            BGP4PLUS::SYNC(x) => x.write(stream),
+            BGP4PLUS::UPDATE(x) | BGP4PLUS::OPEN(x) | BGP4PLUS::NOTIFY(x) | BGP4PLUS::KEEPALIVE(x) => {
+                x.write(stream)
+            }
+        }
+    }
 }
 
 /// BGP message record for IPv6 peers.
 ///
 /// Used for UPDATE, OPEN, NOTIFY, and KEEPALIVE message types.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MESSAGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -118,12 +163,22 @@ impl MESSAGE {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_all(&self.peer_ip.octets())?;
+        stream.write_u16::<BigEndian>(self.local_as)?;
+        stream.write_all(&self.local_ip.octets())?;
+        stream.write_all(&self.message)
+    }
 }
 
 /// BGP state change notification for IPv6 peers.
 ///
 /// Records when a BGP session changes state (e.g., from Established to Idle).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct STATE_CHANGE {
     /// Peer AS number (16-bit)
     pub peer_as: u16,
@@ -156,12 +211,21 @@ impl STATE_CHANGE {
             new_state,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        stream.write_all(&self.peer_ip.octets())?;
+        stream.write_u16::<BigEndian>(self.old_state)?;
+        stream.write_u16::<BigEndian>(self.new_state)
+    }
 }
 
 /// BGP RIB synchronization record.
 ///
 /// Deprecated record type used to indicate RIB recording boundaries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SYNC {
     /// View number for multi-view RIB recordings
     pub view_number: u16,
@@ -188,6 +252,40 @@ impl SYNC {
             filename,
         })
     }
+
+    /// The filename up to its first NUL, decoded lossily as UTF-8.
+    ///
+    /// `filename` keeps the raw bytes, including the NUL terminator and any
+    /// padding, so that records re-encode unchanged.
+    pub fn filename_str(&self) -> Cow<'_, str> {
+        crate::records::nul_terminated_str(&self.filename)
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.view_number)?;
+        stream.write_all(&self.filename)
+    }
+}
+
+impl HasPeer for MESSAGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        IpAddr::V6(self.peer_ip)
+    }
+}
+
+impl HasPeer for STATE_CHANGE {
+    fn peer_as_u32(&self) -> u32 {
+        self.peer_as as u32
+    }
+
+    fn peer_addr(&self) -> IpAddr {
+        IpAddr::V6(self.peer_ip)
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +320,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sync_filename_str() {
+        let sync = SYNC {
+            view_number: 0,
+            filename: b"rib.\xFF\0junk\0".to_vec(),
+        };
+        assert_eq!(sync.filename_str(), "rib.\u{FFFD}");
+
+        let unterminated = SYNC {
+            view_number: 0,
+            filename: b"rib.0".to_vec(),
+        };
+        assert_eq!(unterminated.filename_str(), "rib.0");
+    }
+
     #[test]
     fn test_parse_bgp4plus_message() {
         let header = Header {
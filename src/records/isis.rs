@@ -3,9 +3,12 @@
 //! IS-IS protocol MRT record parsing.
 //!
 //! IS-IS records contain raw IS-IS protocol data units (PDUs).
+//! [`pdu_type`] classifies a PDU and [`parse_lsp_tlvs`] splits the TLVs out
+//! of a link state PDU.
 
 use crate::Header;
-use std::io::Read;
+use std::io::{Error, ErrorKind, Read};
+use std::net::Ipv4Addr;
 
 /// Parse an IS-IS record, returning the raw PDU bytes.
 ///
@@ -21,20 +24,206 @@ use std::io::Read;
 ///
 /// The raw IS-IS PDU bytes.
 pub fn parse(header: &Header, stream: &mut impl Read) -> std::io::Result<Vec<u8>> {
-    // For extended types, the length field includes the 4-byte microseconds
-    // which has already been read, so we need to calculate actual body length
-    let body_length = if header.record_type == 33 {
-        // ISIS_ET
-        header.length.saturating_sub(4)
-    } else {
-        header.length
-    };
-
-    let mut data = vec![0u8; body_length as usize];
+    // The microseconds of ISIS_ET have already been read and are excluded
+    let mut data = vec![0u8; crate::body_length(header) as usize];
     stream.read_exact(&mut data)?;
     Ok(data)
 }
 
+/// IS-IS PDU type (ISO 10589 section 9).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PduType {
+    /// Level 1 LAN IS-IS Hello (15)
+    L1LanHello,
+    /// Level 2 LAN IS-IS Hello (16)
+    L2LanHello,
+    /// Point-to-Point IS-IS Hello (17)
+    P2pHello,
+    /// Level 1 Link State PDU (18)
+    L1Lsp,
+    /// Level 2 Link State PDU (20)
+    L2Lsp,
+    /// Level 1 Complete Sequence Numbers PDU (24)
+    L1Csnp,
+    /// Level 2 Complete Sequence Numbers PDU (25)
+    L2Csnp,
+    /// Level 1 Partial Sequence Numbers PDU (26)
+    L1Psnp,
+    /// Level 2 Partial Sequence Numbers PDU (27)
+    L2Psnp,
+}
+
+impl PduType {
+    /// Returns `true` for link state PDUs of either level.
+    pub fn is_lsp(&self) -> bool {
+        matches!(self, PduType::L1Lsp | PduType::L2Lsp)
+    }
+
+    /// Returns `true` for Hello PDUs of any kind.
+    pub fn is_hello(&self) -> bool {
+        matches!(
+            self,
+            PduType::L1LanHello | PduType::L2LanHello | PduType::P2pHello
+        )
+    }
+}
+
+/// Classify a raw IS-IS PDU by its PDU type field.
+///
+/// The common header starts with the protocol discriminator, length
+/// indicator, version/protocol ID extension and ID length; the fifth byte
+/// holds the PDU type in its low 5 bits (the top 3 bits are reserved).
+///
+/// Returns `None` if the PDU is shorter than 5 bytes or the type is unknown.
+pub fn pdu_type(pdu: &[u8]) -> Option<PduType> {
+    let pdu_type = match pdu.get(4)? & 0x1F {
+        15 => PduType::L1LanHello,
+        16 => PduType::L2LanHello,
+        17 => PduType::P2pHello,
+        18 => PduType::L1Lsp,
+        20 => PduType::L2Lsp,
+        24 => PduType::L1Csnp,
+        25 => PduType::L2Csnp,
+        26 => PduType::L1Psnp,
+        27 => PduType::L2Psnp,
+        _ => return None,
+    };
+    Some(pdu_type)
+}
+
+/// Parse an IS-IS record, returning the PDU type alongside the raw PDU bytes.
+///
+/// Like [`parse`], but also classifies the PDU with [`pdu_type`].
+pub fn parse_with_type(
+    header: &Header,
+    stream: &mut impl Read,
+) -> std::io::Result<(Option<PduType>, Vec<u8>)> {
+    let data = parse(header, stream)?;
+    Ok((pdu_type(&data), data))
+}
+
+/// IS-IS TLV type codes.
+pub mod tlv_types {
+    /// Extended IS Reachability (RFC 5305)
+    pub const EXTENDED_IS_REACHABILITY: u8 = 22;
+    /// IP Internal Reachability Information (RFC 1195)
+    pub const IP_INTERNAL_REACHABILITY: u8 = 128;
+    /// IP Interface Address (RFC 1195)
+    pub const IP_INTERFACE_ADDRESS: u8 = 132;
+    /// Extended IP Reachability (RFC 5305)
+    pub const EXTENDED_IP_REACHABILITY: u8 = 135;
+    /// Dynamic Hostname (RFC 5301)
+    pub const DYNAMIC_HOSTNAME: u8 = 137;
+}
+
+/// Length of the fixed LSP header, as carried in its length indicator.
+const LSP_HEADER_LEN: usize = 27;
+
+/// One TLV from the variable-length part of an IS-IS PDU.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IsisTlv {
+    /// TLV type code (see [`tlv_types`])
+    pub type_code: u8,
+    /// TLV value, without the type and length bytes
+    pub value: Vec<u8>,
+}
+
+impl IsisTlv {
+    /// Decode a Dynamic Hostname TLV (137).
+    ///
+    /// Returns `Ok(None)` for other TLV types and an `InvalidData` error if
+    /// the name is not valid UTF-8.
+    pub fn hostname(&self) -> std::io::Result<Option<&str>> {
+        if self.type_code != tlv_types::DYNAMIC_HOSTNAME {
+            return Ok(None);
+        }
+        std::str::from_utf8(&self.value)
+            .map(Some)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "IS-IS hostname is not valid UTF-8"))
+    }
+
+    /// Decode an IP Interface Address TLV (132).
+    ///
+    /// Format:
+    /// - 4 bytes per IPv4 address, repeated
+    ///
+    /// Returns `Ok(None)` for other TLV types and an `InvalidData` error if
+    /// the value is not a whole number of addresses.
+    pub fn interface_addresses(&self) -> std::io::Result<Option<Vec<Ipv4Addr>>> {
+        if self.type_code != tlv_types::IP_INTERFACE_ADDRESS {
+            return Ok(None);
+        }
+        if !self.value.len().is_multiple_of(4) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("IS-IS interface address TLV has length {}", self.value.len()),
+            ));
+        }
+        let addresses = self
+            .value
+            .chunks_exact(4)
+            .map(|octets| Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+            .collect();
+        Ok(Some(addresses))
+    }
+}
+
+/// Split the TLVs out of a raw IS-IS LSP.
+///
+/// The fixed LSP header is skipped using its length indicator, and the TLV
+/// block runs to the PDU length field, so padding after the PDU is ignored.
+///
+/// Format:
+/// - 8 bytes: common header (length indicator in byte 1, PDU type in byte 4)
+/// - 2 bytes: PDU length
+/// - 2 bytes: remaining lifetime
+/// - ID length + 2 bytes: LSP ID
+/// - 4 bytes: sequence number
+/// - 2 bytes: checksum
+/// - 1 byte: P/ATT/overload/IS type flags
+/// - variable: TLVs, each 1 byte type, 1 byte length, value
+///
+/// Fails with `InvalidData` if the PDU is not an LSP, if the length fields
+/// do not fit the PDU, or if a TLV runs past the end of the PDU.
+pub fn parse_lsp_tlvs(pdu: &[u8]) -> std::io::Result<Vec<IsisTlv>> {
+    if !pdu_type(pdu).is_some_and(|t| t.is_lsp()) {
+        return Err(Error::new(ErrorKind::InvalidData, "IS-IS PDU is not an LSP"));
+    }
+    let header_len = pdu[1] as usize;
+    if header_len < LSP_HEADER_LEN || pdu.len() < header_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("IS-IS LSP header length {} is invalid", header_len),
+        ));
+    }
+    let pdu_len = u16::from_be_bytes([pdu[8], pdu[9]]) as usize;
+    if pdu_len < header_len || pdu_len > pdu.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("IS-IS LSP length {} does not fit {} bytes", pdu_len, pdu.len()),
+        ));
+    }
+
+    let mut tlvs = Vec::new();
+    let mut rest = &pdu[header_len..pdu_len];
+    while !rest.is_empty() {
+        if rest.len() < 2 || rest.len() < 2 + rest[1] as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "IS-IS TLV runs past the end of the LSP",
+            ));
+        }
+        let len = rest[1] as usize;
+        tlvs.push(IsisTlv {
+            type_code: rest[0],
+            value: rest[2..2 + len].to_vec(),
+        });
+        rest = &rest[2 + len..];
+    }
+    Ok(tlvs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,8 +238,101 @@ mod tests {
             length: 10,
         };
         let data: &[u8] = &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A];
-        let result = parse(&header, &mut data.as_ref()).unwrap();
+        let result = parse(&header, &mut &data[..]).unwrap();
         assert_eq!(result.len(), 10);
         assert_eq!(result, data);
     }
+
+    #[test]
+    fn test_pdu_type() {
+        // Common header of an L2 LSP (discriminator 0x83, length 27, type 20)
+        let lsp: &[u8] = &[0x83, 0x1B, 0x01, 0x00, 0x14, 0x01, 0x00, 0x00];
+        assert_eq!(pdu_type(lsp), Some(PduType::L2Lsp));
+        assert!(pdu_type(lsp).unwrap().is_lsp());
+
+        // Reserved bits set on a P2P Hello are ignored
+        let hello: &[u8] = &[0x83, 0x14, 0x01, 0x00, 0xE0 | 0x11];
+        assert_eq!(pdu_type(hello), Some(PduType::P2pHello));
+        assert!(pdu_type(hello).unwrap().is_hello());
+
+        assert_eq!(pdu_type(&[0x83, 0x1B, 0x01, 0x00]), None);
+        assert_eq!(pdu_type(&[0x83, 0x1B, 0x01, 0x00, 0x13]), None);
+    }
+
+    /// An L2 LSP carrying a hostname and an interface address.
+    const LSP: &[u8] = &[
+        0x83, // protocol discriminator
+        0x1B, // length indicator = 27
+        0x01, // version/protocol ID extension
+        0x00, // ID length = 6
+        0x14, // PDU type = L2 LSP
+        0x01, // version
+        0x00, // reserved
+        0x00, // max area addresses
+        0x00, 0x2B, // PDU length = 43
+        0x04, 0xB0, // remaining lifetime = 1200
+        0x19, 0x21, 0x68, 0x00, 0x10, 0x01, 0x00, 0x00, // LSP ID
+        0x00, 0x00, 0x00, 0x05, // sequence number
+        0xAB, 0xCD, // checksum
+        0x03, // flags: IS type L1/L2
+        0x89, 0x04, b'r', b't', b'r', b'1', // hostname = "rtr1"
+        0x84, 0x08, 192, 0, 2, 1, 192, 0, 2, 9, // interface addresses
+        0x00, 0x00, // padding after the PDU
+    ];
+
+    #[test]
+    fn test_parse_lsp_tlvs() {
+        let tlvs = parse_lsp_tlvs(LSP).unwrap();
+        assert_eq!(tlvs.len(), 2);
+        assert_eq!(tlvs[0].type_code, tlv_types::DYNAMIC_HOSTNAME);
+        assert_eq!(tlvs[0].hostname().unwrap(), Some("rtr1"));
+        assert_eq!(tlvs[0].interface_addresses().unwrap(), None);
+        assert_eq!(
+            tlvs[1].interface_addresses().unwrap().unwrap(),
+            [Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 9)]
+        );
+        assert_eq!(tlvs[1].hostname().unwrap(), None);
+
+        // A TLV without its value
+        let mut truncated = LSP[..37].to_vec();
+        truncated[9] = 37;
+        assert!(parse_lsp_tlvs(&truncated).is_err());
+
+        // PDU length beyond the data
+        assert!(parse_lsp_tlvs(&LSP[..40]).is_err());
+
+        // Not an LSP
+        let mut csnp = LSP.to_vec();
+        csnp[4] = 0x19;
+        assert!(parse_lsp_tlvs(&csnp).is_err());
+    }
+
+    #[test]
+    fn test_isis_tlv_errors() {
+        let tlv = IsisTlv {
+            type_code: tlv_types::IP_INTERFACE_ADDRESS,
+            value: vec![192, 0, 2],
+        };
+        assert!(tlv.interface_addresses().is_err());
+        let tlv = IsisTlv {
+            type_code: tlv_types::DYNAMIC_HOSTNAME,
+            value: vec![0xFF, 0xFE],
+        };
+        assert!(tlv.hostname().is_err());
+    }
+
+    #[test]
+    fn test_parse_with_type() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 32,
+            sub_type: 0,
+            length: 5,
+        };
+        let data: &[u8] = &[0x83, 0x21, 0x01, 0x00, 0x18];
+        let (pdu_type, pdu) = parse_with_type(&header, &mut &data[..]).unwrap();
+        assert_eq!(pdu_type, Some(PduType::L1Csnp));
+        assert_eq!(pdu, data);
+    }
 }
@@ -11,3 +11,28 @@ pub mod isis;
 pub mod ospf;
 pub mod rip;
 pub mod tabledump;
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+/// The BGP peer of a message, state change or entry record.
+///
+/// The record formats differ in AS number width and address family; this
+/// gives generic code one view of the peer. 16-bit AS numbers are widened
+/// to `u32`.
+pub trait HasPeer {
+    /// The peer AS number.
+    fn peer_as_u32(&self) -> u32;
+
+    /// The peer IP address.
+    fn peer_addr(&self) -> IpAddr;
+}
+
+/// Decode a NUL-terminated, possibly NUL-padded, string field.
+///
+/// Stops at the first NUL (or the end of `bytes`) and replaces invalid
+/// UTF-8 with U+FFFD.
+pub(crate) fn nul_terminated_str(bytes: &[u8]) -> Cow<'_, str> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end])
+}
@@ -4,15 +4,92 @@
 //!
 //! This module handles OSPFv2 (IPv4) and OSPFv3 (IPv4/IPv6) routing protocol records.
 
-use crate::address::{read_afi, read_ip_by_afi, read_ipv4};
+use crate::address::{common_afi, read_afi, read_ip_by_afi, read_ipv4, write_afi, write_ip};
 use crate::Header;
-use std::io::Read;
+use std::io::{Error, ErrorKind, Read, Write};
 use std::net::{IpAddr, Ipv4Addr};
 
+/// OSPF packet type (RFC 2328 section A.3.1, RFC 5340 section A.3.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OspfPacketType {
+    /// Hello (type 1)
+    Hello,
+    /// Database Description (type 2)
+    DatabaseDescription,
+    /// Link State Request (type 3)
+    LinkStateRequest,
+    /// Link State Update (type 4)
+    LinkStateUpdate,
+    /// Link State Acknowledgment (type 5)
+    LinkStateAck,
+}
+
+/// Common OSPF packet header fields shared by OSPFv2 and OSPFv3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OspfHeader {
+    /// OSPF version (2 or 3)
+    pub version: u8,
+    /// Packet type
+    pub packet_type: OspfPacketType,
+    /// Packet length in bytes, including the OSPF header
+    pub packet_length: u16,
+    /// Router ID of the packet's source
+    pub router_id: Ipv4Addr,
+    /// Area the packet belongs to
+    pub area_id: Ipv4Addr,
+}
+
+impl OspfHeader {
+    /// Parse the common OSPF header from the start of an OSPF packet.
+    ///
+    /// Format:
+    /// - 1 byte: version
+    /// - 1 byte: packet type
+    /// - 2 bytes: packet length
+    /// - 4 bytes: router ID
+    /// - 4 bytes: area ID
+    ///
+    /// Returns an error if the buffer is shorter than the header, the packet
+    /// type is unknown, or the declared packet length is outside the buffer.
+    pub fn parse(bytes: &[u8]) -> std::io::Result<Self> {
+        if bytes.len() < 12 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated OSPF header"));
+        }
+
+        let version = bytes[0];
+        let packet_type = match bytes[1] {
+            1 => OspfPacketType::Hello,
+            2 => OspfPacketType::DatabaseDescription,
+            3 => OspfPacketType::LinkStateRequest,
+            4 => OspfPacketType::LinkStateUpdate,
+            5 => OspfPacketType::LinkStateAck,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid OSPF packet type")),
+        };
+        let packet_length = u16::from_be_bytes([bytes[2], bytes[3]]);
+        if (packet_length as usize) < 12 || packet_length as usize > bytes.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "OSPF packet length does not match message length",
+            ));
+        }
+        let router_id = Ipv4Addr::new(bytes[4], bytes[5], bytes[6], bytes[7]);
+        let area_id = Ipv4Addr::new(bytes[8], bytes[9], bytes[10], bytes[11]);
+
+        Ok(OspfHeader {
+            version,
+            packet_type,
+            packet_length,
+            router_id,
+            area_id,
+        })
+    }
+}
+
 /// OSPFv2 protocol record.
 ///
 /// Contains IPv4 addresses for source and destination along with the OSPF message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OSPFv2 {
     /// Remote peer IPv4 address
     pub remote: Ipv4Addr,
@@ -44,12 +121,25 @@ impl OSPFv2 {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_all(&self.remote.octets())?;
+        stream.write_all(&self.local.octets())?;
+        stream.write_all(&self.message)
+    }
+
+    /// Parse the OSPF header at the start of `message`.
+    pub fn header(&self) -> std::io::Result<OspfHeader> {
+        OspfHeader::parse(&self.message)
+    }
 }
 
 /// OSPFv3 protocol record.
 ///
 /// OSPFv3 can use either IPv4 or IPv6 addresses, determined by the AFI field.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OSPFv3 {
     /// Remote peer IP address (IPv4 or IPv6)
     pub remote: IpAddr,
@@ -74,14 +164,8 @@ impl OSPFv3 {
         let remote = read_ip_by_afi(stream, &afi)?;
         let local = read_ip_by_afi(stream, &afi)?;
 
-        // Calculate message length: total minus AFI (2) and addresses
-        // For extended types, length already accounts for microseconds being subtracted
-        let body_length = if header.record_type == 49 {
-            // OSPFv3_ET
-            header.length.saturating_sub(4)
-        } else {
-            header.length
-        };
+        // Calculate message length: body minus AFI (2) and addresses
+        let body_length = crate::body_length(header);
 
         let addresses_size = afi.size() * 2 + 2; // Two addresses plus AFI field
         let message_len = body_length.saturating_sub(addresses_size) as usize;
@@ -94,6 +178,22 @@ impl OSPFv3 {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    ///
+    /// Fails if the remote and local addresses are of different families.
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let afi = common_afi(&self.remote, &self.local)?;
+        write_afi(stream, &afi)?;
+        write_ip(stream, &self.remote)?;
+        write_ip(stream, &self.local)?;
+        stream.write_all(&self.message)
+    }
+
+    /// Parse the OSPF header at the start of `message`.
+    pub fn header(&self) -> std::io::Result<OspfHeader> {
+        OspfHeader::parse(&self.message)
+    }
 }
 
 #[cfg(test)]
@@ -115,12 +215,38 @@ mod tests {
             10, 0, 0, 2, // local
             0x01, 0x02, 0x03, 0x04, // message
         ];
-        let result = OSPFv2::parse(&header, &mut data.as_ref()).unwrap();
+        let result = OSPFv2::parse(&header, &mut &data[..]).unwrap();
         assert_eq!(result.remote, Ipv4Addr::new(10, 0, 0, 1));
         assert_eq!(result.local, Ipv4Addr::new(10, 0, 0, 2));
         assert_eq!(result.message, vec![0x01, 0x02, 0x03, 0x04]);
     }
 
+    #[test]
+    fn test_ospf_header() {
+        let ospf = OSPFv2 {
+            remote: Ipv4Addr::new(10, 0, 0, 1),
+            local: Ipv4Addr::new(10, 0, 0, 2),
+            message: vec![
+                0x02, 0x01, 0x00, 0x0C, // version 2, Hello, length 12
+                10, 0, 0, 1, // router_id
+                0, 0, 0, 0, // area_id
+            ],
+        };
+        let header = ospf.header().unwrap();
+        assert_eq!(header.version, 2);
+        assert_eq!(header.packet_type, OspfPacketType::Hello);
+        assert_eq!(header.packet_length, 12);
+        assert_eq!(header.router_id, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(header.area_id, Ipv4Addr::UNSPECIFIED);
+    }
+
+    #[test]
+    fn test_ospf_header_length_mismatch() {
+        let message: &[u8] = &[0x03, 0x04, 0x00, 0x40, 10, 0, 0, 1, 0, 0, 0, 0];
+        assert!(OspfHeader::parse(message).is_err());
+        assert!(OspfHeader::parse(&message[..8]).is_err());
+    }
+
     #[test]
     fn test_parse_ospfv3_ipv4() {
         let header = Header {
@@ -136,7 +262,7 @@ mod tests {
             10, 0, 0, 2, // local
             0x01, 0x02, 0x03, 0x04, // message
         ];
-        let result = OSPFv3::parse(&header, &mut data.as_ref()).unwrap();
+        let result = OSPFv3::parse(&header, &mut &data[..]).unwrap();
         assert_eq!(result.remote, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
         assert_eq!(result.local, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
         assert_eq!(result.message, vec![0x01, 0x02, 0x03, 0x04]);
@@ -6,13 +6,87 @@
 
 use crate::address::{read_ipv4, read_ipv6};
 use crate::Header;
-use std::io::Read;
+use std::io::{Error, ErrorKind, Read, Write};
 use std::net::{Ipv4Addr, Ipv6Addr};
 
+/// A single RIP route entry (RFC 2453 section 4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RipEntry {
+    /// Address family identifier (2 for IP, 0xFFFF for authentication)
+    pub afi: u16,
+    /// Route tag (zero in RIPv1)
+    pub route_tag: u16,
+    /// Destination address
+    pub ip: Ipv4Addr,
+    /// Subnet mask (zero in RIPv1)
+    pub subnet_mask: Ipv4Addr,
+    /// Next hop (zero in RIPv1)
+    pub next_hop: Ipv4Addr,
+    /// Metric (1-16, where 16 is unreachable)
+    pub metric: u32,
+}
+
+/// A decoded RIPv1/RIPv2 packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RipPacket {
+    /// Command (1 = request, 2 = response)
+    pub command: u8,
+    /// RIP version
+    pub version: u8,
+    /// Route entries in packet order
+    pub entries: Vec<RipEntry>,
+}
+
+impl RipPacket {
+    /// Parse a RIP packet.
+    ///
+    /// Format:
+    /// - 1 byte: command
+    /// - 1 byte: version
+    /// - 2 bytes: unused
+    /// - 20 bytes per route entry: AFI, route tag, IP address, subnet mask,
+    ///   next hop, metric
+    ///
+    /// Returns an error if the packet is shorter than its header or the
+    /// entries are not a whole number of 20-byte blocks. Authentication
+    /// entries are returned like route entries, with `afi` set to 0xFFFF.
+    pub fn parse(bytes: &[u8]) -> std::io::Result<Self> {
+        if bytes.len() < 4 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated RIP header"));
+        }
+        let entry_bytes = &bytes[4..];
+        if !entry_bytes.len().is_multiple_of(20) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "RIP entries are not a multiple of 20 bytes",
+            ));
+        }
+
+        let entries = entry_bytes
+            .chunks_exact(20)
+            .map(|e| RipEntry {
+                afi: u16::from_be_bytes([e[0], e[1]]),
+                route_tag: u16::from_be_bytes([e[2], e[3]]),
+                ip: Ipv4Addr::new(e[4], e[5], e[6], e[7]),
+                subnet_mask: Ipv4Addr::new(e[8], e[9], e[10], e[11]),
+                next_hop: Ipv4Addr::new(e[12], e[13], e[14], e[15]),
+                metric: u32::from_be_bytes([e[16], e[17], e[18], e[19]]),
+            })
+            .collect();
+
+        Ok(RipPacket {
+            command: bytes[0],
+            version: bytes[1],
+            entries,
+        })
+    }
+}
+
 /// RIP (Routing Information Protocol) record for IPv4.
 ///
 /// Contains the source and destination addresses along with the RIP message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIP {
     /// Remote peer IPv4 address
     pub remote: Ipv4Addr,
@@ -44,12 +118,25 @@ impl RIP {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_all(&self.remote.octets())?;
+        stream.write_all(&self.local.octets())?;
+        stream.write_all(&self.message)
+    }
+
+    /// Decode `message` as a RIP packet.
+    pub fn packet(&self) -> std::io::Result<RipPacket> {
+        RipPacket::parse(&self.message)
+    }
 }
 
 /// RIPng (RIP next generation) record for IPv6.
 ///
 /// Contains the source and destination addresses along with the RIPng message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIPNG {
     /// Remote peer IPv6 address
     pub remote: Ipv6Addr,
@@ -81,6 +168,13 @@ impl RIPNG {
             message,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_all(&self.remote.octets())?;
+        stream.write_all(&self.local.octets())?;
+        stream.write_all(&self.message)
+    }
 }
 
 #[cfg(test)]
@@ -101,7 +195,7 @@ mod tests {
             192, 168, 1, 2, // local
             0x01, 0x02, 0x03, 0x04, // message
         ];
-        let result = RIP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = RIP::parse(&header, &mut &data[..]).unwrap();
         assert_eq!(result.remote, Ipv4Addr::new(192, 168, 1, 1));
         assert_eq!(result.local, Ipv4Addr::new(192, 168, 1, 2));
         assert_eq!(result.message, vec![0x01, 0x02, 0x03, 0x04]);
@@ -132,4 +226,35 @@ mod tests {
         assert_eq!(result.local, "2001:db8::2".parse::<Ipv6Addr>().unwrap());
         assert_eq!(result.message, vec![0x01, 0x02, 0x03, 0x04]);
     }
+
+    #[test]
+    fn test_rip_packet() {
+        let message: &[u8] = &[
+            0x02, 0x02, 0x00, 0x00, // command = response, version = 2
+            0x00, 0x02, 0x00, 0x07, // AFI = IP, route tag = 7
+            10, 1, 0, 0, // IP
+            255, 255, 0, 0, // subnet mask
+            192, 168, 1, 254, // next hop
+            0x00, 0x00, 0x00, 0x03, // metric
+        ];
+        let rip = RIP {
+            remote: Ipv4Addr::new(192, 168, 1, 1),
+            local: Ipv4Addr::new(192, 168, 1, 2),
+            message: message.to_vec(),
+        };
+        let packet = rip.packet().unwrap();
+        assert_eq!(packet.command, 2);
+        assert_eq!(packet.version, 2);
+        assert_eq!(packet.entries.len(), 1);
+        let entry = packet.entries[0];
+        assert_eq!(entry.route_tag, 7);
+        assert_eq!(entry.ip, Ipv4Addr::new(10, 1, 0, 0));
+        assert_eq!(entry.subnet_mask, Ipv4Addr::new(255, 255, 0, 0));
+        assert_eq!(entry.next_hop, Ipv4Addr::new(192, 168, 1, 254));
+        assert_eq!(entry.metric, 3);
+
+        assert!(RipPacket::parse(&message[..23]).is_err());
+        assert!(RipPacket::parse(&message[..3]).is_err());
+        assert!(RipPacket::parse(&message[..4]).unwrap().entries.is_empty());
+    }
 }
@@ -4,24 +4,41 @@
 //!
 //! These record types contain RIB (Routing Information Base) dumps,
 //! used by route collectors to capture BGP routing table snapshots.
+//!
+//! The `attributes_parsed` methods take the width of AS numbers in
+//! AS_PATH and AGGREGATOR as `as4`. TABLE_DUMP records use 2 bytes, so pass
+//! `false` there. TABLE_DUMP_V2 RIB entries always use 4 bytes (RFC 6396
+//! section 4.3.4), whatever the peer's session negotiated, so pass `true`.
 
 #![allow(non_camel_case_types)]
 
-use crate::address::{prefix_bytes_needed, read_afi, read_ip_by_afi, read_ipv4, read_ipv6};
+use crate::address::{
+    afi_of, check_prefix, common_afi, prefix_bytes_needed, read_ip_by_afi, read_ipv4,
+    read_ipv6, u16_len, write_ip, write_u16_prefixed,
+};
+use crate::records::bgp::attributes::safis;
+use crate::records::bgp::linkstate::{LinkStateNlri, BGP_LS_AFI};
+use crate::records::bgp::PathAttributes;
 use crate::Header;
+use crate::Prefix;
+use crate::ReadOptions;
+use crate::Record;
 use crate::AFI;
-use byteorder::{BigEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind, Read};
-use std::net::IpAddr;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr};
 
 /// TABLE_DUMP_V2 subtype constants
-mod subtypes {
+pub(crate) mod subtypes {
     pub const PEER_INDEX_TABLE: u16 = 1;
     pub const RIB_IPV4_UNICAST: u16 = 2;
     pub const RIB_IPV4_MULTICAST: u16 = 3;
     pub const RIB_IPV6_UNICAST: u16 = 4;
     pub const RIB_IPV6_MULTICAST: u16 = 5;
     pub const RIB_GENERIC: u16 = 6;
+    // RFC 6397
+    pub const GEO_PEER_TABLE: u16 = 7;
     // RFC 8050 Add-Path extensions
     pub const RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
     pub const RIB_IPV4_MULTICAST_ADDPATH: u16 = 9;
@@ -30,10 +47,67 @@ mod subtypes {
     pub const RIB_GENERIC_ADDPATH: u16 = 12;
 }
 
+/// Name of a subtype, or `None` if it is not known.
+pub(crate) fn subtype_name(sub_type: u16) -> Option<&'static str> {
+    Some(match sub_type {
+        subtypes::PEER_INDEX_TABLE => "PEER_INDEX_TABLE",
+        subtypes::RIB_IPV4_UNICAST => "RIB_IPV4_UNICAST",
+        subtypes::RIB_IPV4_MULTICAST => "RIB_IPV4_MULTICAST",
+        subtypes::RIB_IPV6_UNICAST => "RIB_IPV6_UNICAST",
+        subtypes::RIB_IPV6_MULTICAST => "RIB_IPV6_MULTICAST",
+        subtypes::RIB_GENERIC => "RIB_GENERIC",
+        subtypes::GEO_PEER_TABLE => "GEO_PEER_TABLE",
+        subtypes::RIB_IPV4_UNICAST_ADDPATH => "RIB_IPV4_UNICAST_ADDPATH",
+        subtypes::RIB_IPV4_MULTICAST_ADDPATH => "RIB_IPV4_MULTICAST_ADDPATH",
+        subtypes::RIB_IPV6_UNICAST_ADDPATH => "RIB_IPV6_UNICAST_ADDPATH",
+        subtypes::RIB_IPV6_MULTICAST_ADDPATH => "RIB_IPV6_MULTICAST_ADDPATH",
+        subtypes::RIB_GENERIC_ADDPATH => "RIB_GENERIC_ADDPATH",
+        _ => return None,
+    })
+}
+
+/// Smallest encodings of a peer entry (IPv4, 2-byte AS) and of RIB entries
+/// with empty attributes, used to bound declared counts.
+const MIN_PEER_ENTRY_LEN: usize = 11;
+const MIN_GEO_PEER_ENTRY_LEN: usize = MIN_PEER_ENTRY_LEN + 8;
+const MIN_RIB_ENTRY_LEN: usize = 8;
+const MIN_RIB_ENTRY_ADDPATH_LEN: usize = 12;
+
+/// Reject a declared entry count that exceeds `options.max_entries` or
+/// cannot fit in `remaining` body bytes.
+fn check_entry_count(
+    count: usize,
+    min_entry_len: usize,
+    remaining: usize,
+    options: &ReadOptions,
+) -> std::io::Result<()> {
+    if count > options.max_entries {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("entry count {} exceeds limit of {}", count, options.max_entries),
+        ));
+    }
+    if count.saturating_mul(min_entry_len) > remaining {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("entry count {} does not fit in {} remaining bytes", count, remaining),
+        ));
+    }
+    Ok(())
+}
+
 /// TABLE_DUMP record (type 12).
 ///
 /// The original RIB dump format, one entry per record.
-#[derive(Debug, Clone)]
+///
+/// TABLE_DUMP predates 4-byte AS numbers: `peer_as` is 16 bits and the
+/// AS_PATH and AGGREGATOR attributes in `attributes` use 2-byte AS numbers,
+/// unlike TABLE_DUMP_V2 where they are always 4 bytes. Decode the attributes
+/// with `as4 = false`, e.g. through
+/// [`attributes_parsed`](Self::attributes_parsed); with `as4 = true` the
+/// AS_PATH comes out garbled or fails to parse.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TABLE_DUMP {
     /// View number for multi-view recordings
     pub view_number: u16,
@@ -45,26 +119,70 @@ pub struct TABLE_DUMP {
     pub prefix_length: u8,
     /// Entry status
     pub status: u8,
+    /// Full 16-bit status when parsed with [`TableDumpLayout::WideStatus`]
+    /// (`status` then holds its low byte); `None` for the standard layout.
+    pub raw_status: Option<u16>,
     /// Time this route was originated
     pub originated_time: u32,
     /// Peer IP address
     pub peer_address: IpAddr,
     /// Peer AS number (16-bit)
     pub peer_as: u16,
-    /// BGP path attributes
+    /// BGP path attributes, with 2-byte AS numbers
     pub attributes: Vec<u8>,
 }
 
+/// Field layout of a TABLE_DUMP record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableDumpLayout {
+    /// RFC 6396 layout with a 1-byte status field
+    #[default]
+    Standard,
+    /// Vendor variant that encodes the status as a 2-byte field
+    WideStatus,
+}
+
 impl TABLE_DUMP {
-    /// Parse a TABLE_DUMP record.
+    /// Parse a TABLE_DUMP record using the standard RFC 6396 layout.
     ///
     /// The AFI is determined by the header subtype:
     /// - subtype 1 = AFI_IPv4
     /// - subtype 2 = AFI_IPv6
+    ///
+    /// Subtype 0 is also read as AFI_IPv4. RFC 6396 does not define it, but
+    /// some pre-2000 RouteViews archives written by MRTd use it for IPv4
+    /// dumps, as do other tools of that era. Such records are written back
+    /// with subtype 1.
+    ///
+    /// Fails with `InvalidData` if the fields do not add up to the header
+    /// length, which is the symptom of a record in a vendor layout; see
+    /// [`parse_with_layout`](Self::parse_with_layout).
     #[inline]
     pub fn parse(header: &Header, stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_layout(header, stream, TableDumpLayout::Standard)
+    }
+
+    /// Parse a TABLE_DUMP record with an explicit field layout.
+    ///
+    /// Format:
+    /// - 2 bytes: view_number
+    /// - 2 bytes: sequence_number
+    /// - 4 or 16 bytes: prefix
+    /// - 1 byte: prefix_length
+    /// - 1 byte (2 bytes with `WideStatus`): status
+    /// - 4 bytes: originated_time
+    /// - 4 or 16 bytes: peer_address
+    /// - 2 bytes: peer_as
+    /// - 2 bytes: attribute length
+    /// - variable: attributes
+    pub fn parse_with_layout(
+        header: &Header,
+        stream: &mut impl Read,
+        layout: TableDumpLayout,
+    ) -> std::io::Result<Self> {
         let afi = match header.sub_type {
-            1 => AFI::IPV4,
+            // Subtype 0 appears in legacy IPv4 dumps
+            0 | 1 => AFI::IPV4,
             2 => AFI::IPV6,
             _ => return Err(Error::new(ErrorKind::InvalidData, "invalid TABLE_DUMP subtype")),
         };
@@ -74,12 +192,36 @@ impl TABLE_DUMP {
 
         let prefix = read_ip_by_afi(stream, &afi)?;
         let prefix_length = stream.read_u8()?;
-        let status = stream.read_u8()?;
+        let (status, raw_status, status_size) = match layout {
+            TableDumpLayout::Standard => (stream.read_u8()?, None, 1),
+            TableDumpLayout::WideStatus => {
+                let raw = stream.read_u16::<BigEndian>()?;
+                (raw as u8, Some(raw), 2)
+            }
+        };
         let originated_time = stream.read_u32::<BigEndian>()?;
         let peer_address = read_ip_by_afi(stream, &afi)?;
         let peer_as = stream.read_u16::<BigEndian>()?;
 
         let attr_len = stream.read_u16::<BigEndian>()? as usize;
+
+        // Fixed fields plus attributes must account for the whole body;
+        // otherwise the fields above are misaligned
+        let fixed_size = 13 + status_size + afi.size() as usize * 2;
+        let body_length = crate::body_length(header) as usize;
+        if fixed_size + attr_len != body_length {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "TABLE_DUMP length mismatch: record is {} bytes but fields need {} \
+                     ({:?} layout)",
+                    body_length,
+                    fixed_size + attr_len,
+                    layout
+                ),
+            ));
+        }
+
         let mut attributes = vec![0u8; attr_len];
         stream.read_exact(&mut attributes)?;
 
@@ -89,20 +231,75 @@ impl TABLE_DUMP {
             prefix,
             prefix_length,
             status,
+            raw_status,
             originated_time,
             peer_address,
             peer_as,
             attributes,
         })
     }
+
+    /// Returns the prefix and prefix length as a [`Prefix`].
+    pub fn prefix_addr(&self) -> std::io::Result<Prefix> {
+        Prefix::new(self.prefix, self.prefix_length).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "prefix length exceeds address size")
+        })
+    }
+
+    /// Decode `attributes`, with `as4 = false` for this record type.
+    ///
+    /// Any 4-byte AS numbers are carried in AS4_PATH, which
+    /// [`PathAttributes::effective_as_path`] merges back in.
+    pub fn attributes_parsed(&self, as4: bool) -> std::io::Result<PathAttributes> {
+        PathAttributes::parse(&self.attributes, as4)
+    }
+
+    /// Replace `attributes` with `attrs` encoded with 2-byte AS numbers.
+    ///
+    /// The inverse of [`attributes_parsed`](Self::attributes_parsed); see
+    /// [`PathAttributes::encode`] for how the attributes are normalized.
+    pub fn set_attributes(&mut self, attrs: &PathAttributes) -> std::io::Result<()> {
+        self.attributes = attrs.encode(false)?;
+        Ok(())
+    }
+
+    /// The MRT subtype of this record (1 for IPv4, 2 for IPv6 prefixes).
+    pub fn sub_type(&self) -> u16 {
+        match self.prefix {
+            IpAddr::V4(_) => 1,
+            IpAddr::V6(_) => 2,
+        }
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    ///
+    /// The 2-byte status layout is used when [`raw_status`](Self::raw_status) is set.
+    /// Fails if the prefix and peer addresses are of different families.
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        common_afi(&self.prefix, &self.peer_address)?;
+        stream.write_u16::<BigEndian>(self.view_number)?;
+        stream.write_u16::<BigEndian>(self.sequence_number)?;
+        write_ip(stream, &self.prefix)?;
+        stream.write_u8(self.prefix_length)?;
+        match self.raw_status {
+            Some(raw) => stream.write_u16::<BigEndian>(raw)?,
+            None => stream.write_u8(self.status)?,
+        }
+        stream.write_u32::<BigEndian>(self.originated_time)?;
+        write_ip(stream, &self.peer_address)?;
+        stream.write_u16::<BigEndian>(self.peer_as)?;
+        write_u16_prefixed(stream, &self.attributes)
+    }
 }
 
 /// TABLE_DUMP_V2 record (type 13).
 ///
 /// The modern RIB dump format with improved efficiency and support for
 /// multiple RIB entries per record.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(non_camel_case_types)]
+#[non_exhaustive]
 pub enum TABLE_DUMP_V2 {
     /// Peer index table (must appear first in dump)
     PEER_INDEX_TABLE(PEER_INDEX_TABLE),
@@ -116,6 +313,8 @@ pub enum TABLE_DUMP_V2 {
     RIB_IPV6_MULTICAST(RIB_AFI),
     /// Generic RIB entries (includes AFI/SAFI)
     RIB_GENERIC(RIB_GENERIC),
+    /// Collector and peer locations (RFC 6397)
+    GEO_PEER_TABLE(GeoPeerTable),
     /// IPv4 unicast RIB entries with Add-Path
     RIB_IPV4_UNICAST_ADDPATH(RIB_AFI_ADDPATH),
     /// IPv4 multicast RIB entries with Add-Path
@@ -126,51 +325,199 @@ pub enum TABLE_DUMP_V2 {
     RIB_IPV6_MULTICAST_ADDPATH(RIB_AFI_ADDPATH),
     /// Generic RIB entries with Add-Path
     RIB_GENERIC_ADDPATH(RIB_GENERIC_ADDPATH),
+    /// Unrecognized subtype, kept as the undecoded body
+    Unknown { sub_type: u16, body: Vec<u8> },
 }
 
 impl TABLE_DUMP_V2 {
     /// Parse a TABLE_DUMP_V2 record.
     #[inline]
     pub fn parse(header: &Header, stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(header, stream, &ReadOptions::default())
+    }
+
+    /// Parse a TABLE_DUMP_V2 record, enforcing the entry limits in `options`.
+    ///
+    /// Entry and peer counts are also checked against the body length from
+    /// `header`, so a corrupt count fails instead of driving allocation.
+    pub fn parse_with_options(
+        header: &Header,
+        stream: &mut impl Read,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let len = crate::body_length(header) as usize;
         match header.sub_type {
             subtypes::PEER_INDEX_TABLE => Ok(TABLE_DUMP_V2::PEER_INDEX_TABLE(
-                PEER_INDEX_TABLE::parse(stream)?,
+                PEER_INDEX_TABLE::parse_with_options(stream, len, options)?,
+            )),
+            subtypes::RIB_IPV4_UNICAST => Ok(TABLE_DUMP_V2::RIB_IPV4_UNICAST(
+                RIB_AFI::parse_with_options(&AFI::IPV4, stream, len, options)?,
+            )),
+            subtypes::RIB_IPV4_MULTICAST => Ok(TABLE_DUMP_V2::RIB_IPV4_MULTICAST(
+                RIB_AFI::parse_with_options(&AFI::IPV4, stream, len, options)?,
+            )),
+            subtypes::RIB_IPV6_UNICAST => Ok(TABLE_DUMP_V2::RIB_IPV6_UNICAST(
+                RIB_AFI::parse_with_options(&AFI::IPV6, stream, len, options)?,
+            )),
+            subtypes::RIB_IPV6_MULTICAST => Ok(TABLE_DUMP_V2::RIB_IPV6_MULTICAST(
+                RIB_AFI::parse_with_options(&AFI::IPV6, stream, len, options)?,
+            )),
+            subtypes::RIB_GENERIC => Ok(TABLE_DUMP_V2::RIB_GENERIC(
+                RIB_GENERIC::parse_with_options(stream, len, options)?,
+            )),
+            subtypes::GEO_PEER_TABLE => Ok(TABLE_DUMP_V2::GEO_PEER_TABLE(
+                GeoPeerTable::parse_with_options(stream, len, options)?,
             )),
-            subtypes::RIB_IPV4_UNICAST => Ok(TABLE_DUMP_V2::RIB_IPV4_UNICAST(RIB_AFI::parse(
-                &AFI::IPV4,
-                stream,
-            )?)),
-            subtypes::RIB_IPV4_MULTICAST => Ok(TABLE_DUMP_V2::RIB_IPV4_MULTICAST(RIB_AFI::parse(
-                &AFI::IPV4,
-                stream,
-            )?)),
-            subtypes::RIB_IPV6_UNICAST => Ok(TABLE_DUMP_V2::RIB_IPV6_UNICAST(RIB_AFI::parse(
-                &AFI::IPV6,
-                stream,
-            )?)),
-            subtypes::RIB_IPV6_MULTICAST => Ok(TABLE_DUMP_V2::RIB_IPV6_MULTICAST(RIB_AFI::parse(
-                &AFI::IPV6,
-                stream,
-            )?)),
-            subtypes::RIB_GENERIC => {
-                Ok(TABLE_DUMP_V2::RIB_GENERIC(RIB_GENERIC::parse(stream)?))
-            }
             subtypes::RIB_IPV4_UNICAST_ADDPATH => Ok(TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(
-                RIB_AFI_ADDPATH::parse(&AFI::IPV4, stream)?,
+                RIB_AFI_ADDPATH::parse_with_options(&AFI::IPV4, stream, len, options)?,
             )),
             subtypes::RIB_IPV4_MULTICAST_ADDPATH => Ok(TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(
-                RIB_AFI_ADDPATH::parse(&AFI::IPV4, stream)?,
+                RIB_AFI_ADDPATH::parse_with_options(&AFI::IPV4, stream, len, options)?,
             )),
             subtypes::RIB_IPV6_UNICAST_ADDPATH => Ok(TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(
-                RIB_AFI_ADDPATH::parse(&AFI::IPV6, stream)?,
+                RIB_AFI_ADDPATH::parse_with_options(&AFI::IPV6, stream, len, options)?,
             )),
             subtypes::RIB_IPV6_MULTICAST_ADDPATH => Ok(TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(
-                RIB_AFI_ADDPATH::parse(&AFI::IPV6, stream)?,
+                RIB_AFI_ADDPATH::parse_with_options(&AFI::IPV6, stream, len, options)?,
             )),
             subtypes::RIB_GENERIC_ADDPATH => Ok(TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(
-                RIB_GENERIC_ADDPATH::parse(stream)?,
+                RIB_GENERIC_ADDPATH::parse_with_options(stream, len, options)?,
             )),
-            _ => Err(Error::new(ErrorKind::InvalidData, "invalid TABLE_DUMP_V2 subtype")),
+            sub_type => Ok(TABLE_DUMP_V2::Unknown {
+                sub_type,
+                body: crate::read_bounded(stream, len)?,
+            }),
+        }
+    }
+
+    /// The MRT subtype of this record.
+    pub fn sub_type(&self) -> u16 {
+        match self {
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(_) => subtypes::PEER_INDEX_TABLE,
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(_) => subtypes::RIB_IPV4_UNICAST,
+            TABLE_DUMP_V2::RIB_IPV4_MULTICAST(_) => subtypes::RIB_IPV4_MULTICAST,
+            TABLE_DUMP_V2::RIB_IPV6_UNICAST(_) => subtypes::RIB_IPV6_UNICAST,
+            TABLE_DUMP_V2::RIB_IPV6_MULTICAST(_) => subtypes::RIB_IPV6_MULTICAST,
+            TABLE_DUMP_V2::RIB_GENERIC(_) => subtypes::RIB_GENERIC,
+            TABLE_DUMP_V2::GEO_PEER_TABLE(_) => subtypes::GEO_PEER_TABLE,
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(_) => subtypes::RIB_IPV4_UNICAST_ADDPATH,
+            TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(_) => subtypes::RIB_IPV4_MULTICAST_ADDPATH,
+            TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(_) => subtypes::RIB_IPV6_UNICAST_ADDPATH,
+            TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(_) => subtypes::RIB_IPV6_MULTICAST_ADDPATH,
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(_) => subtypes::RIB_GENERIC_ADDPATH,
+            TABLE_DUMP_V2::Unknown { sub_type, .. } => *sub_type,
+        }
+    }
+
+    /// The sequence number of a RIB record, with or without Add-Path.
+    ///
+    /// Returns `None` for the subtypes that are not RIB records.
+    pub fn sequence_number(&self) -> Option<u32> {
+        match self {
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => Some(rib.sequence_number),
+            TABLE_DUMP_V2::RIB_GENERIC(rib) => Some(rib.sequence_number),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => Some(rib.sequence_number),
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(rib) => Some(rib.sequence_number),
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(_)
+            | TABLE_DUMP_V2::GEO_PEER_TABLE(_)
+            | TABLE_DUMP_V2::Unknown { .. } => None,
+        }
+    }
+
+    /// The prefix of an AFI-specific RIB record, with or without Add-Path.
+    ///
+    /// Returns `None` for RIB_GENERIC, whose NLRI need not be an IP prefix,
+    /// and for the subtypes that carry no prefix.
+    pub fn prefix_addr(&self) -> std::io::Result<Option<Prefix>> {
+        match self {
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => rib.prefix_addr().map(Some),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => rib.prefix_addr().map(Some),
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(_)
+            | TABLE_DUMP_V2::RIB_GENERIC(_)
+            | TABLE_DUMP_V2::GEO_PEER_TABLE(_)
+            | TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(_)
+            | TABLE_DUMP_V2::Unknown { .. } => Ok(None),
+        }
+    }
+
+    /// The number of RIB entries, 0 for subtypes that are not RIB records.
+    pub fn entry_count(&self) -> usize {
+        match self {
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => rib.entries.len(),
+            TABLE_DUMP_V2::RIB_GENERIC(rib) => rib.entries.len(),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => rib.entries.len(),
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(rib) => rib.entries.len(),
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(_)
+            | TABLE_DUMP_V2::GEO_PEER_TABLE(_)
+            | TABLE_DUMP_V2::Unknown { .. } => 0,
+        }
+    }
+
+    /// The path attributes of every RIB entry, in record order.
+    ///
+    /// Covers all RIB subtypes, with and without Add-Path; other subtypes
+    /// have no entries and return an empty list. The attributes use 4-byte
+    /// AS numbers.
+    pub fn entry_attributes(&self) -> Vec<&[u8]> {
+        match self {
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => {
+                rib.entries.iter().map(|e| e.attributes.as_slice()).collect()
+            }
+            TABLE_DUMP_V2::RIB_GENERIC(rib) => {
+                rib.entries.iter().map(|e| e.attributes.as_slice()).collect()
+            }
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => {
+                rib.entries.iter().map(|e| e.attributes.as_slice()).collect()
+            }
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(rib) => {
+                rib.entries.iter().map(|e| e.attributes.as_slice()).collect()
+            }
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(_)
+            | TABLE_DUMP_V2::GEO_PEER_TABLE(_)
+            | TABLE_DUMP_V2::Unknown { .. } => Vec::new(),
+        }
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(x) => x.write(stream),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(x)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(x)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(x)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(x) => x.write(stream),
+            TABLE_DUMP_V2::RIB_GENERIC(x) => x.write(stream),
+            TABLE_DUMP_V2::GEO_PEER_TABLE(x) => x.write(stream),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(x)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(x)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(x)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(x) => x.write(stream),
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(x) => x.write(stream),
+            TABLE_DUMP_V2::Unknown { body, .. } => stream.write_all(body),
         }
     }
 }
@@ -179,7 +526,8 @@ impl TABLE_DUMP_V2 {
 ///
 /// This record must appear at the start of a TABLE_DUMP_V2 file and
 /// defines the peer index mappings used in subsequent RIB entries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PEER_INDEX_TABLE {
     /// BGP identifier of the collector
     pub collector_id: u32,
@@ -193,6 +541,18 @@ impl PEER_INDEX_TABLE {
     /// Parse a PEER_INDEX_TABLE record.
     #[inline]
     pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(stream, usize::MAX, &ReadOptions::default())
+    }
+
+    /// Parse a PEER_INDEX_TABLE record with a body of `len` bytes.
+    ///
+    /// Fails if the peer count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
         let collector_id = stream.read_u32::<BigEndian>()?;
         let view_name_length = stream.read_u16::<BigEndian>()? as usize;
 
@@ -201,6 +561,8 @@ impl PEER_INDEX_TABLE {
         let view_name = String::from_utf8_lossy(&view_name_bytes).into_owned();
 
         let peer_count = stream.read_u16::<BigEndian>()? as usize;
+        let remaining = len.saturating_sub(8 + view_name_length);
+        check_entry_count(peer_count, MIN_PEER_ENTRY_LEN, remaining, options)?;
         let mut peer_entries = Vec::with_capacity(peer_count);
 
         for _ in 0..peer_count {
@@ -213,14 +575,77 @@ impl PEER_INDEX_TABLE {
             peer_entries,
         })
     }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u32::<BigEndian>(self.collector_id)?;
+        write_u16_prefixed(stream, self.view_name.as_bytes())?;
+        stream.write_u16::<BigEndian>(u16_len(self.peer_entries.len())?)?;
+        for peer in &self.peer_entries {
+            peer.write(stream)?;
+        }
+        Ok(())
+    }
+
+    /// The collector BGP identifier in dotted-quad form.
+    #[inline]
+    pub fn collector_id_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.collector_id)
+    }
+}
+
+/// PeerEntry peer_type bit constants
+mod peer_type_bits {
+    pub const IPV6: u8 = 0x01;
+    pub const AS4: u8 = 0x02;
+}
+
+/// Decoded `peer_type` flags of a [`PeerEntry`].
+///
+/// RFC 6396 defines only bits 0 and 1. Other bits, which some collectors
+/// set, are kept in `reserved` so they survive a round trip through `u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeerType {
+    /// Peer IP address is IPv6 (bit 0)
+    pub ipv6: bool,
+    /// Peer AS number is 32-bit (bit 1)
+    pub as4: bool,
+    /// Undefined bits 2-7, in place (bits 0 and 1 always clear)
+    pub reserved: u8,
+}
+
+impl From<u8> for PeerType {
+    fn from(peer_type: u8) -> Self {
+        PeerType {
+            ipv6: peer_type & peer_type_bits::IPV6 != 0,
+            as4: peer_type & peer_type_bits::AS4 != 0,
+            reserved: peer_type & !(peer_type_bits::IPV6 | peer_type_bits::AS4),
+        }
+    }
+}
+
+impl From<PeerType> for u8 {
+    /// Encode the flags; the defined bits of `reserved` are ignored.
+    fn from(flags: PeerType) -> Self {
+        let mut peer_type = flags.reserved & !(peer_type_bits::IPV6 | peer_type_bits::AS4);
+        if flags.ipv6 {
+            peer_type |= peer_type_bits::IPV6;
+        }
+        if flags.as4 {
+            peer_type |= peer_type_bits::AS4;
+        }
+        peer_type
+    }
 }
 
 /// Peer entry within a PEER_INDEX_TABLE.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PeerEntry {
     /// Peer type flags:
-    /// - Bit 0: AS number size (0 = 16-bit, 1 = 32-bit)
-    /// - Bit 1: IP address type (0 = IPv4, 1 = IPv6)
+    /// - Bit 0: IP address type (0 = IPv4, 1 = IPv6)
+    /// - Bit 1: AS number size (0 = 16-bit, 1 = 32-bit)
+    /// - Bits 2-7: undefined, kept as read and written back unchanged
     pub peer_type: u8,
     /// Peer BGP identifier
     pub peer_bgp_id: u32,
@@ -236,18 +661,17 @@ impl PeerEntry {
     pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
         let peer_type = stream.read_u8()?;
         let peer_bgp_id = stream.read_u32::<BigEndian>()?;
+        let flags = PeerType::from(peer_type);
 
         // RFC 6396: Bit 0 = Address Family (0 = IPv4, 1 = IPv6)
-        let is_ipv6 = (peer_type & 0x01) != 0;
-        let peer_ip_address = if is_ipv6 {
+        let peer_ip_address = if flags.ipv6 {
             IpAddr::V6(read_ipv6(stream)?)
         } else {
             IpAddr::V4(read_ipv4(stream)?)
         };
 
         // RFC 6396: Bit 1 = AS Size (0 = 16-bit, 1 = 32-bit)
-        let is_as4 = (peer_type & 0x02) != 0;
-        let peer_as = if is_as4 {
+        let peer_as = if flags.as4 {
             stream.read_u32::<BigEndian>()?
         } else {
             stream.read_u16::<BigEndian>()? as u32
@@ -260,10 +684,194 @@ impl PeerEntry {
             peer_as,
         })
     }
+
+    /// Create a peer entry, deriving `peer_type` from the address family.
+    ///
+    /// The AS number is always encoded as 32-bit.
+    pub fn new(peer_bgp_id: u32, peer_ip_address: IpAddr, peer_as: u32) -> Self {
+        let mut peer_type = peer_type_bits::AS4;
+        if peer_ip_address.is_ipv6() {
+            peer_type |= peer_type_bits::IPV6;
+        }
+        PeerEntry {
+            peer_type,
+            peer_bgp_id,
+            peer_ip_address,
+            peer_as,
+        }
+    }
+
+    /// Write the entry in wire format (the inverse of [`parse`](Self::parse)).
+    ///
+    /// Fails if `peer_type` disagrees with the address family, or if a
+    /// 16-bit AS encoding is requested for an AS number above 65535.
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let flags = self.flags();
+        if flags.ipv6 != self.peer_ip_address.is_ipv6() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "peer_type address family does not match peer address",
+            ));
+        }
+        stream.write_u8(self.peer_type)?;
+        stream.write_u32::<BigEndian>(self.peer_bgp_id)?;
+        write_ip(stream, &self.peer_ip_address)?;
+        if flags.as4 {
+            stream.write_u32::<BigEndian>(self.peer_as)
+        } else {
+            let peer_as = u16::try_from(self.peer_as).map_err(|_| {
+                Error::new(ErrorKind::InvalidInput, "peer AS does not fit in 16 bits")
+            })?;
+            stream.write_u16::<BigEndian>(peer_as)
+        }
+    }
+
+    /// Decoded `peer_type` flags.
+    #[inline]
+    pub fn flags(&self) -> PeerType {
+        PeerType::from(self.peer_type)
+    }
+
+    /// Whether the peer address is IPv6 (`peer_type` bit 0).
+    #[inline]
+    pub fn is_ipv6(&self) -> bool {
+        self.flags().ipv6
+    }
+
+    /// Whether the peer AS number is 32-bit (`peer_type` bit 1).
+    #[inline]
+    pub fn is_as4(&self) -> bool {
+        self.flags().as4
+    }
+
+    /// The peer BGP identifier in dotted-quad form.
+    #[inline]
+    pub fn peer_bgp_id_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.peer_bgp_id)
+    }
+}
+
+/// Geo-location table for TABLE_DUMP_V2 (RFC 6397).
+///
+/// Follows the PEER_INDEX_TABLE and gives the collector location plus one
+/// location per peer. Coordinates are in decimal degrees; RFC 6397 uses NaN
+/// for a location that is not known. As with `f32`, a NaN coordinate never
+/// compares equal, so neither does a table holding one.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GeoPeerTable {
+    /// BGP identifier of the collector
+    pub collector_bgp_id: u32,
+    /// View name (may be empty)
+    pub view_name: String,
+    /// Collector latitude
+    pub latitude: f32,
+    /// Collector longitude
+    pub longitude: f32,
+    /// Locations of the peers in this dump
+    pub peer_entries: Vec<GeoPeerEntry>,
+}
+
+impl GeoPeerTable {
+    /// Parse a GEO_PEER_TABLE record.
+    #[inline]
+    pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(stream, usize::MAX, &ReadOptions::default())
+    }
+
+    /// Parse a GEO_PEER_TABLE record with a body of `len` bytes.
+    ///
+    /// Fails if the peer count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let collector_bgp_id = stream.read_u32::<BigEndian>()?;
+        let view_name_length = stream.read_u16::<BigEndian>()? as usize;
+
+        let mut view_name_bytes = vec![0u8; view_name_length];
+        stream.read_exact(&mut view_name_bytes)?;
+        let view_name = String::from_utf8_lossy(&view_name_bytes).into_owned();
+
+        let latitude = stream.read_f32::<BigEndian>()?;
+        let longitude = stream.read_f32::<BigEndian>()?;
+
+        let peer_count = stream.read_u16::<BigEndian>()? as usize;
+        let remaining = len.saturating_sub(16 + view_name_length);
+        check_entry_count(peer_count, MIN_GEO_PEER_ENTRY_LEN, remaining, options)?;
+        let mut peer_entries = Vec::with_capacity(peer_count);
+
+        for _ in 0..peer_count {
+            peer_entries.push(GeoPeerEntry::parse(stream)?);
+        }
+
+        Ok(GeoPeerTable {
+            collector_bgp_id,
+            view_name,
+            latitude,
+            longitude,
+            peer_entries,
+        })
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u32::<BigEndian>(self.collector_bgp_id)?;
+        write_u16_prefixed(stream, self.view_name.as_bytes())?;
+        stream.write_f32::<BigEndian>(self.latitude)?;
+        stream.write_f32::<BigEndian>(self.longitude)?;
+        stream.write_u16::<BigEndian>(u16_len(self.peer_entries.len())?)?;
+        for peer in &self.peer_entries {
+            peer.write(stream)?;
+        }
+        Ok(())
+    }
+}
+
+/// Peer entry within a GEO_PEER_TABLE.
+///
+/// Format:
+/// - Peer fields as in a [`PeerEntry`]
+/// - Peer Latitude (4 bytes, IEEE 754 single precision)
+/// - Peer Longitude (4 bytes, IEEE 754 single precision)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GeoPeerEntry {
+    /// Peer type, BGP identifier, address and AS number
+    pub peer: PeerEntry,
+    /// Peer latitude
+    pub latitude: f32,
+    /// Peer longitude
+    pub longitude: f32,
+}
+
+impl GeoPeerEntry {
+    /// Parse a GeoPeerEntry from the stream.
+    pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
+        let peer = PeerEntry::parse(stream)?;
+        let latitude = stream.read_f32::<BigEndian>()?;
+        let longitude = stream.read_f32::<BigEndian>()?;
+
+        Ok(GeoPeerEntry {
+            peer,
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Write the entry in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        self.peer.write(stream)?;
+        stream.write_f32::<BigEndian>(self.latitude)?;
+        stream.write_f32::<BigEndian>(self.longitude)
+    }
 }
 
 /// RIB entry in TABLE_DUMP_V2.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIBEntry {
     /// Index into the peer index table
     pub peer_index: u16,
@@ -290,13 +898,37 @@ impl RIBEntry {
             attributes,
         })
     }
+
+    /// Write the entry in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_index)?;
+        stream.write_u32::<BigEndian>(self.originated_time)?;
+        write_u16_prefixed(stream, &self.attributes)
+    }
+
+    /// Decode `attributes`, with `as4 = true` for TABLE_DUMP_V2.
+    pub fn attributes_parsed(&self, as4: bool) -> std::io::Result<PathAttributes> {
+        PathAttributes::parse(&self.attributes, as4)
+    }
+
+    /// Replace `attributes` with `attrs` encoded with 4-byte AS numbers.
+    ///
+    /// The inverse of [`attributes_parsed`](Self::attributes_parsed); see
+    /// [`PathAttributes::encode`] for how the attributes are normalized.
+    pub fn set_attributes(&mut self, attrs: &PathAttributes) -> std::io::Result<()> {
+        self.attributes = attrs.encode(true)?;
+        Ok(())
+    }
 }
 
 /// AFI-specific RIB record (IPv4 or IPv6 unicast/multicast).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIB_AFI {
     /// Sequence number within the dump
     pub sequence_number: u32,
+    /// Address family of the prefix, implied by the record subtype
+    pub afi: AFI,
     /// Prefix length in bits
     pub prefix_length: u8,
     /// Prefix bytes (variable length based on prefix_length)
@@ -308,7 +940,52 @@ pub struct RIB_AFI {
 impl RIB_AFI {
     /// Parse a RIB_AFI record.
     #[inline]
-    pub fn parse(_afi: &AFI, stream: &mut impl Read) -> std::io::Result<Self> {
+    pub fn parse(afi: &AFI, stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(afi, stream, usize::MAX, &ReadOptions::default())
+    }
+
+    /// Parse a RIB_AFI record with a body of `len` bytes.
+    ///
+    /// Fails if the entry count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        afi: &AFI,
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let (mut rib, entry_count) = Self::parse_head(afi, stream)?;
+        let remaining = len.saturating_sub(7 + rib.prefix.len());
+        check_entry_count(entry_count, MIN_RIB_ENTRY_LEN, remaining, options)?;
+        rib.entries = Vec::with_capacity(entry_count);
+
+        for _ in 0..entry_count {
+            rib.entries.push(RIBEntry::parse(stream)?);
+        }
+
+        Ok(rib)
+    }
+
+    /// Parse a RIB_AFI record, passing each entry to `on_entry` instead of
+    /// collecting them.
+    ///
+    /// The returned record has no `entries`, so memory use stays flat however
+    /// many entries share the prefix.
+    pub fn parse_streaming(
+        afi: &AFI,
+        stream: &mut impl Read,
+        mut on_entry: impl FnMut(RIBEntry),
+    ) -> std::io::Result<Self> {
+        let (rib, entry_count) = Self::parse_head(afi, stream)?;
+        for _ in 0..entry_count {
+            on_entry(RIBEntry::parse(stream)?);
+        }
+        Ok(rib)
+    }
+
+    /// Read the fields before the entries, returning the record without
+    /// entries and the entry count.
+    fn parse_head(afi: &AFI, stream: &mut impl Read) -> std::io::Result<(Self, usize)> {
         let sequence_number = stream.read_u32::<BigEndian>()?;
         let prefix_length = stream.read_u8()?;
 
@@ -317,28 +994,50 @@ impl RIB_AFI {
         stream.read_exact(&mut prefix)?;
 
         let entry_count = stream.read_u16::<BigEndian>()? as usize;
-        let mut entries = Vec::with_capacity(entry_count);
-
-        for _ in 0..entry_count {
-            entries.push(RIBEntry::parse(stream)?);
-        }
-
-        Ok(RIB_AFI {
+        let rib = RIB_AFI {
             sequence_number,
+            afi: *afi,
             prefix_length,
             prefix,
-            entries,
-        })
+            entries: Vec::new(),
+        };
+        Ok((rib, entry_count))
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    ///
+    /// Fails if `prefix` does not hold exactly the bytes `prefix_length` needs.
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        check_prefix(self.prefix_length, &self.prefix)?;
+        stream.write_u32::<BigEndian>(self.sequence_number)?;
+        stream.write_u8(self.prefix_length)?;
+        stream.write_all(&self.prefix)?;
+        stream.write_u16::<BigEndian>(u16_len(self.entries.len())?)?;
+        for entry in &self.entries {
+            entry.write(stream)?;
+        }
+        Ok(())
+    }
+
+    /// Decode the prefix bytes into a [`Prefix`].
+    pub fn prefix_addr(&self) -> std::io::Result<Prefix> {
+        Prefix::from_wire(&self.afi, self.prefix_length, &self.prefix)
     }
 }
 
 /// Generic RIB record with explicit AFI/SAFI.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIB_GENERIC {
     /// Sequence number within the dump
     pub sequence_number: u32,
-    /// Address family identifier
+    /// Address family identifier; `IPV4` for families [`AFI`] cannot
+    /// represent, see `afi_raw`
     pub afi: AFI,
+    /// Address family identifier as written on the wire. Differs from `afi`
+    /// only for families other than IPv4 and IPv6, such as [`BGP_LS_AFI`],
+    /// and is then what `write` emits.
+    pub afi_raw: u16,
     /// Subsequent AFI
     pub safi: u8,
     /// NLRI (Network Layer Reachability Information)
@@ -350,8 +1049,52 @@ pub struct RIB_GENERIC {
 impl RIB_GENERIC {
     /// Parse a RIB_GENERIC record.
     pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(stream, usize::MAX, &ReadOptions::default())
+    }
+
+    /// Parse a RIB_GENERIC record with a body of `len` bytes.
+    ///
+    /// Fails if the entry count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let (mut rib, entry_count) = Self::parse_head(stream)?;
+        let remaining = len.saturating_sub(11 + rib.nlri.len());
+        check_entry_count(entry_count, MIN_RIB_ENTRY_LEN, remaining, options)?;
+        rib.entries = Vec::with_capacity(entry_count);
+
+        for _ in 0..entry_count {
+            rib.entries.push(RIBEntry::parse(stream)?);
+        }
+
+        Ok(rib)
+    }
+
+    /// Parse a RIB_GENERIC record, passing each entry to `on_entry` instead of
+    /// collecting them.
+    ///
+    /// The returned record has no `entries`, so memory use stays flat however
+    /// many entries share the NLRI.
+    pub fn parse_streaming(
+        stream: &mut impl Read,
+        mut on_entry: impl FnMut(RIBEntry),
+    ) -> std::io::Result<Self> {
+        let (rib, entry_count) = Self::parse_head(stream)?;
+        for _ in 0..entry_count {
+            on_entry(RIBEntry::parse(stream)?);
+        }
+        Ok(rib)
+    }
+
+    /// Read the fields before the entries, returning the record without
+    /// entries and the entry count.
+    fn parse_head(stream: &mut impl Read) -> std::io::Result<(Self, usize)> {
         let sequence_number = stream.read_u32::<BigEndian>()?;
-        let afi = read_afi(stream)?;
+        let afi_raw = stream.read_u16::<BigEndian>()?;
+        let afi = AFI::from_u16(afi_raw).unwrap_or(AFI::IPV4);
         let safi = stream.read_u8()?;
 
         // NLRI is preceded by length
@@ -360,24 +1103,44 @@ impl RIB_GENERIC {
         stream.read_exact(&mut nlri)?;
 
         let entry_count = stream.read_u16::<BigEndian>()? as usize;
-        let mut entries = Vec::with_capacity(entry_count);
-
-        for _ in 0..entry_count {
-            entries.push(RIBEntry::parse(stream)?);
-        }
-
-        Ok(RIB_GENERIC {
+        let rib = RIB_GENERIC {
             sequence_number,
             afi,
+            afi_raw,
             safi,
             nlri,
-            entries,
-        })
+            entries: Vec::new(),
+        };
+        Ok((rib, entry_count))
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u32::<BigEndian>(self.sequence_number)?;
+        stream.write_u16::<BigEndian>(generic_wire_afi(&self.afi, self.afi_raw))?;
+        stream.write_u8(self.safi)?;
+        write_u16_prefixed(stream, &self.nlri)?;
+        stream.write_u16::<BigEndian>(u16_len(self.entries.len())?)?;
+        for entry in &self.entries {
+            entry.write(stream)?;
+        }
+        Ok(())
+    }
+
+    /// Decode the NLRI of a BGP-LS record.
+    ///
+    /// Returns `None` if the AFI is not [`BGP_LS_AFI`].
+    pub fn link_state_nlri(&self) -> std::io::Result<Option<LinkStateNlri>> {
+        if self.afi_raw != BGP_LS_AFI {
+            return Ok(None);
+        }
+        LinkStateNlri::parse(&self.nlri).map(Some)
     }
 }
 
 /// RIB entry with Add-Path extension.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIBEntryAddPath {
     /// Index into the peer index table
     pub peer_index: u16,
@@ -408,13 +1171,38 @@ impl RIBEntryAddPath {
             attributes,
         })
     }
+
+    /// Write the entry in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u16::<BigEndian>(self.peer_index)?;
+        stream.write_u32::<BigEndian>(self.originated_time)?;
+        stream.write_u32::<BigEndian>(self.path_identifier)?;
+        write_u16_prefixed(stream, &self.attributes)
+    }
+
+    /// Decode `attributes`; see [`RIBEntry::attributes_parsed`].
+    pub fn attributes_parsed(&self, as4: bool) -> std::io::Result<PathAttributes> {
+        PathAttributes::parse(&self.attributes, as4)
+    }
+
+    /// Replace `attributes` with `attrs` encoded with 4-byte AS numbers.
+    ///
+    /// The inverse of [`attributes_parsed`](Self::attributes_parsed); see
+    /// [`PathAttributes::encode`] for how the attributes are normalized.
+    pub fn set_attributes(&mut self, attrs: &PathAttributes) -> std::io::Result<()> {
+        self.attributes = attrs.encode(true)?;
+        Ok(())
+    }
 }
 
 /// AFI-specific RIB record with Add-Path extension.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIB_AFI_ADDPATH {
     /// Sequence number within the dump
     pub sequence_number: u32,
+    /// Address family of the prefix, implied by the record subtype
+    pub afi: AFI,
     /// Prefix length in bits
     pub prefix_length: u8,
     /// Prefix bytes (variable length based on prefix_length)
@@ -426,37 +1214,120 @@ pub struct RIB_AFI_ADDPATH {
 impl RIB_AFI_ADDPATH {
     /// Parse a RIB_AFI_ADDPATH record.
     #[inline]
-    pub fn parse(_afi: &AFI, stream: &mut impl Read) -> std::io::Result<Self> {
-        let sequence_number = stream.read_u32::<BigEndian>()?;
-        let prefix_length = stream.read_u8()?;
-
-        let prefix_bytes = prefix_bytes_needed(prefix_length);
-        let mut prefix = vec![0u8; prefix_bytes];
-        stream.read_exact(&mut prefix)?;
+    pub fn parse(afi: &AFI, stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(afi, stream, usize::MAX, &ReadOptions::default())
+    }
 
-        let entry_count = stream.read_u16::<BigEndian>()? as usize;
-        let mut entries = Vec::with_capacity(entry_count);
+    /// Parse a RIB_AFI_ADDPATH record with a body of `len` bytes.
+    ///
+    /// Fails if the entry count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        afi: &AFI,
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let (mut rib, entry_count) = Self::parse_head(afi, stream)?;
+        let remaining = len.saturating_sub(7 + rib.prefix.len());
+        check_entry_count(entry_count, MIN_RIB_ENTRY_ADDPATH_LEN, remaining, options)?;
+        rib.entries = Vec::with_capacity(entry_count);
 
         for _ in 0..entry_count {
-            entries.push(RIBEntryAddPath::parse(stream)?);
+            rib.entries.push(RIBEntryAddPath::parse(stream)?);
         }
 
-        Ok(RIB_AFI_ADDPATH {
-            sequence_number,
-            prefix_length,
+        Ok(rib)
+    }
+
+    /// Parse a RIB_AFI_ADDPATH record, passing each entry to `on_entry` instead of
+    /// collecting them.
+    ///
+    /// The returned record has no `entries`, so memory use stays flat however
+    /// many entries share the prefix.
+    pub fn parse_streaming(
+        afi: &AFI,
+        stream: &mut impl Read,
+        mut on_entry: impl FnMut(RIBEntryAddPath),
+    ) -> std::io::Result<Self> {
+        let (rib, entry_count) = Self::parse_head(afi, stream)?;
+        for _ in 0..entry_count {
+            on_entry(RIBEntryAddPath::parse(stream)?);
+        }
+        Ok(rib)
+    }
+
+    /// Read the fields before the entries, returning the record without
+    /// entries and the entry count.
+    fn parse_head(afi: &AFI, stream: &mut impl Read) -> std::io::Result<(Self, usize)> {
+        let sequence_number = stream.read_u32::<BigEndian>()?;
+        let prefix_length = stream.read_u8()?;
+
+        let prefix_bytes = prefix_bytes_needed(prefix_length);
+        let mut prefix = vec![0u8; prefix_bytes];
+        stream.read_exact(&mut prefix)?;
+
+        let entry_count = stream.read_u16::<BigEndian>()? as usize;
+        let rib = RIB_AFI_ADDPATH {
+            sequence_number,
+            afi: *afi,
+            prefix_length,
             prefix,
-            entries,
-        })
+            entries: Vec::new(),
+        };
+        Ok((rib, entry_count))
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    ///
+    /// Fails if `prefix` does not hold exactly the bytes `prefix_length` needs.
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        check_prefix(self.prefix_length, &self.prefix)?;
+        stream.write_u32::<BigEndian>(self.sequence_number)?;
+        stream.write_u8(self.prefix_length)?;
+        stream.write_all(&self.prefix)?;
+        stream.write_u16::<BigEndian>(u16_len(self.entries.len())?)?;
+        for entry in &self.entries {
+            entry.write(stream)?;
+        }
+        Ok(())
+    }
+
+    /// Decode the prefix bytes into a [`Prefix`].
+    pub fn prefix_addr(&self) -> std::io::Result<Prefix> {
+        Prefix::from_wire(&self.afi, self.prefix_length, &self.prefix)
+    }
+
+    /// Per-path routes as `(prefix, path_id, peer_index, attributes)`.
+    ///
+    /// With Add-Path the same prefix appears once per path; each entry's
+    /// path identifier is paired with the shared, decoded prefix.
+    pub fn routes(&self) -> std::io::Result<Vec<AddPathRoute<'_, Prefix>>> {
+        let prefix = self.prefix_addr()?;
+        Ok(self
+            .entries
+            .iter()
+            .map(|e| (prefix, e.path_identifier, e.peer_index, e.attributes.as_slice()))
+            .collect())
     }
 }
 
+/// One path of an Add-Path RIB record: `(prefix or NLRI, path_id, peer_index, attributes)`.
+pub type AddPathRoute<'a, P> = (P, u32, u16, &'a [u8]);
+
 /// Generic RIB record with Add-Path extension.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RIB_GENERIC_ADDPATH {
     /// Sequence number within the dump
     pub sequence_number: u32,
-    /// Address family identifier
+    /// Address family identifier; `IPV4` for families [`AFI`] cannot
+    /// represent, see `afi_raw`
     pub afi: AFI,
+    /// Address family identifier as written on the wire. Differs from `afi`
+    /// only for families other than IPv4 and IPv6, such as [`BGP_LS_AFI`],
+    /// and is then what `write` emits.
+    pub afi_raw: u16,
     /// Subsequent AFI
     pub safi: u8,
     /// NLRI (Network Layer Reachability Information)
@@ -468,8 +1339,52 @@ pub struct RIB_GENERIC_ADDPATH {
 impl RIB_GENERIC_ADDPATH {
     /// Parse a RIB_GENERIC_ADDPATH record.
     pub fn parse(stream: &mut impl Read) -> std::io::Result<Self> {
+        Self::parse_with_options(stream, usize::MAX, &ReadOptions::default())
+    }
+
+    /// Parse a RIB_GENERIC_ADDPATH record with a body of `len` bytes.
+    ///
+    /// Fails if the entry count exceeds `options.max_entries` or cannot fit
+    /// in the rest of the body.
+    pub fn parse_with_options(
+        stream: &mut impl Read,
+        len: usize,
+        options: &ReadOptions,
+    ) -> std::io::Result<Self> {
+        let (mut rib, entry_count) = Self::parse_head(stream)?;
+        let remaining = len.saturating_sub(11 + rib.nlri.len());
+        check_entry_count(entry_count, MIN_RIB_ENTRY_ADDPATH_LEN, remaining, options)?;
+        rib.entries = Vec::with_capacity(entry_count);
+
+        for _ in 0..entry_count {
+            rib.entries.push(RIBEntryAddPath::parse(stream)?);
+        }
+
+        Ok(rib)
+    }
+
+    /// Parse a RIB_GENERIC_ADDPATH record, passing each entry to `on_entry` instead of
+    /// collecting them.
+    ///
+    /// The returned record has no `entries`, so memory use stays flat however
+    /// many entries share the NLRI.
+    pub fn parse_streaming(
+        stream: &mut impl Read,
+        mut on_entry: impl FnMut(RIBEntryAddPath),
+    ) -> std::io::Result<Self> {
+        let (rib, entry_count) = Self::parse_head(stream)?;
+        for _ in 0..entry_count {
+            on_entry(RIBEntryAddPath::parse(stream)?);
+        }
+        Ok(rib)
+    }
+
+    /// Read the fields before the entries, returning the record without
+    /// entries and the entry count.
+    fn parse_head(stream: &mut impl Read) -> std::io::Result<(Self, usize)> {
         let sequence_number = stream.read_u32::<BigEndian>()?;
-        let afi = read_afi(stream)?;
+        let afi_raw = stream.read_u16::<BigEndian>()?;
+        let afi = AFI::from_u16(afi_raw).unwrap_or(AFI::IPV4);
         let safi = stream.read_u8()?;
 
         // NLRI is preceded by length
@@ -478,22 +1393,424 @@ impl RIB_GENERIC_ADDPATH {
         stream.read_exact(&mut nlri)?;
 
         let entry_count = stream.read_u16::<BigEndian>()? as usize;
-        let mut entries = Vec::with_capacity(entry_count);
-
-        for _ in 0..entry_count {
-            entries.push(RIBEntryAddPath::parse(stream)?);
-        }
-
-        Ok(RIB_GENERIC_ADDPATH {
+        let rib = RIB_GENERIC_ADDPATH {
             sequence_number,
             afi,
+            afi_raw,
             safi,
             nlri,
-            entries,
-        })
+            entries: Vec::new(),
+        };
+        Ok((rib, entry_count))
+    }
+
+    /// Write the record body in wire format (the inverse of [`parse`](Self::parse)).
+    pub fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        stream.write_u32::<BigEndian>(self.sequence_number)?;
+        stream.write_u16::<BigEndian>(generic_wire_afi(&self.afi, self.afi_raw))?;
+        stream.write_u8(self.safi)?;
+        write_u16_prefixed(stream, &self.nlri)?;
+        stream.write_u16::<BigEndian>(u16_len(self.entries.len())?)?;
+        for entry in &self.entries {
+            entry.write(stream)?;
+        }
+        Ok(())
+    }
+
+    /// Per-path routes as `(nlri, path_id, peer_index, attributes)`.
+    ///
+    /// The NLRI is shared by all entries; this pairs it with each entry's
+    /// path identifier so per-path routes can be reconstructed.
+    pub fn routes(&self) -> Vec<AddPathRoute<'_, &[u8]>> {
+        self.entries
+            .iter()
+            .map(|e| {
+                (
+                    self.nlri.as_slice(),
+                    e.path_identifier,
+                    e.peer_index,
+                    e.attributes.as_slice(),
+                )
+            })
+            .collect()
+    }
+
+    /// Decode the NLRI of a BGP-LS record.
+    ///
+    /// Returns `None` if the AFI is not [`BGP_LS_AFI`].
+    pub fn link_state_nlri(&self) -> std::io::Result<Option<LinkStateNlri>> {
+        if self.afi_raw != BGP_LS_AFI {
+            return Ok(None);
+        }
+        LinkStateNlri::parse(&self.nlri).map(Some)
+    }
+}
+
+/// A single route from a TABLE_DUMP_V2 RIB record with its peer resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatRoute {
+    /// Route prefix
+    pub prefix: Prefix,
+    /// Index of the peer in the PEER_INDEX_TABLE
+    pub peer_index: u16,
+    /// Peer that announced the route
+    pub peer: PeerEntry,
+    /// Time this route was originated
+    pub originated_time: u32,
+    /// BGP path attributes
+    pub attributes: Vec<u8>,
+    /// Add-Path identifier (`None` for non-Add-Path subtypes)
+    pub path_id: Option<u32>,
+}
+
+/// Tracks TABLE_DUMP_V2 state across the records of a RIB dump.
+///
+/// RIB records only carry an index into the PEER_INDEX_TABLE that precedes
+/// them; feed every record through [`flatten`](Self::flatten) and the session
+/// remembers the table and resolves peers for subsequent RIB entries.
+///
+/// Concatenated dumps (e.g. several per-collector RIB files joined with
+/// `cat`) contain several PEER_INDEX_TABLE records. Each new table replaces
+/// the previous one, so `resolve` and `flatten` always use the most recent
+/// table, matching the peer namespace of the records that follow it.
+#[derive(Debug, Clone, Default)]
+pub struct TableDumpV2Session {
+    peer_table: Option<PEER_INDEX_TABLE>,
+}
+
+impl TableDumpV2Session {
+    /// Create a session with no peer index table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The peer index table currently in effect, if one has been seen.
+    pub fn peer_table(&self) -> Option<&PEER_INDEX_TABLE> {
+        self.peer_table.as_ref()
+    }
+
+    /// Look up a peer by its index in the current peer index table.
+    pub fn resolve(&self, peer_index: u16) -> Option<&PeerEntry> {
+        self.peer_table
+            .as_ref()
+            .and_then(|t| t.peer_entries.get(peer_index as usize))
+    }
+
+    /// Flatten a TABLE_DUMP_V2 record into individual routes.
+    ///
+    /// A PEER_INDEX_TABLE replaces the session's table and yields no routes. RIB
+    /// records yield one [`FlatRoute`] per entry. Entries whose peer index
+    /// cannot be resolved, RIB_GENERIC NLRI that is not a plain IPv4/IPv6
+    /// unicast or multicast prefix, GEO_PEER_TABLE records and unknown
+    /// subtypes are skipped.
+    pub fn flatten(&mut self, record: TABLE_DUMP_V2) -> std::vec::IntoIter<FlatRoute> {
+        let mut routes = Vec::new();
+
+        match record {
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(table) => self.peer_table = Some(table),
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => {
+                let prefix = rib.prefix_addr().ok();
+                self.push_entries(prefix, rib.entries, &mut routes);
+            }
+            TABLE_DUMP_V2::RIB_GENERIC(rib) => {
+                let prefix = generic_prefix(rib.afi_raw, rib.safi, &rib.nlri);
+                self.push_entries(prefix, rib.entries, &mut routes);
+            }
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => {
+                let prefix = rib.prefix_addr().ok();
+                self.push_addpath_entries(prefix, rib.entries, &mut routes);
+            }
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(rib) => {
+                let prefix = generic_prefix(rib.afi_raw, rib.safi, &rib.nlri);
+                self.push_addpath_entries(prefix, rib.entries, &mut routes);
+            }
+            TABLE_DUMP_V2::GEO_PEER_TABLE(_) | TABLE_DUMP_V2::Unknown { .. } => {}
+        }
+
+        routes.into_iter()
+    }
+
+    fn push_entries(
+        &self,
+        prefix: Option<Prefix>,
+        entries: Vec<RIBEntry>,
+        out: &mut Vec<FlatRoute>,
+    ) {
+        let Some(prefix) = prefix else { return };
+        for entry in entries {
+            if let Some(peer) = self.resolve(entry.peer_index) {
+                out.push(FlatRoute {
+                    prefix,
+                    peer_index: entry.peer_index,
+                    peer: peer.clone(),
+                    originated_time: entry.originated_time,
+                    attributes: entry.attributes,
+                    path_id: None,
+                });
+            }
+        }
+    }
+
+    fn push_addpath_entries(
+        &self,
+        prefix: Option<Prefix>,
+        entries: Vec<RIBEntryAddPath>,
+        out: &mut Vec<FlatRoute>,
+    ) {
+        let Some(prefix) = prefix else { return };
+        for entry in entries {
+            if let Some(peer) = self.resolve(entry.peer_index) {
+                out.push(FlatRoute {
+                    prefix,
+                    peer_index: entry.peer_index,
+                    peer: peer.clone(),
+                    originated_time: entry.originated_time,
+                    attributes: entry.attributes,
+                    path_id: Some(entry.path_identifier),
+                });
+            }
+        }
+    }
+}
+
+/// Builds a TABLE_DUMP_V2 RIB dump from individual routes.
+///
+/// Peers get indices in the order they are added. Routes are buffered and
+/// grouped by prefix; [`finish`](Self::finish) writes the PEER_INDEX_TABLE
+/// followed by one RIB_IPV4_UNICAST or RIB_IPV6_UNICAST record per prefix,
+/// IPv4 before IPv6 and each family in ascending prefix order.
+///
+/// # Example
+///
+/// ```
+/// use mrt_ingester::tabledump::{PeerEntry, TableDumpV2Writer};
+///
+/// let mut writer = TableDumpV2Writer::new(0x0A000001, 1_700_000_000);
+/// let peer = writer
+///     .add_peer(PeerEntry::new(0x0A000002, "192.0.2.2".parse().unwrap(), 65001))
+///     .unwrap();
+/// let origin_igp = vec![0x40, 0x01, 0x01, 0x00];
+/// writer
+///     .add_route(peer, "198.51.100.0/24".parse().unwrap(), origin_igp, 1_700_000_000)
+///     .unwrap();
+///
+/// let mut out = Vec::new();
+/// writer.finish(&mut out).unwrap();
+/// let table = mrt_ingester::read_peer_index(&mut out.as_slice()).unwrap().unwrap();
+/// assert_eq!(table.peer_entries.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TableDumpV2Writer {
+    timestamp: u32,
+    table: PEER_INDEX_TABLE,
+    routes: BTreeMap<Prefix, Vec<RIBEntry>>,
+}
+
+impl TableDumpV2Writer {
+    /// Create a writer for a dump by `collector_id`, stamping every record
+    /// with `timestamp`.
+    pub fn new(collector_id: u32, timestamp: u32) -> Self {
+        TableDumpV2Writer {
+            timestamp,
+            table: PEER_INDEX_TABLE {
+                collector_id,
+                view_name: String::new(),
+                peer_entries: Vec::new(),
+            },
+            routes: BTreeMap::new(),
+        }
+    }
+
+    /// Set the view name written in the PEER_INDEX_TABLE (empty by default).
+    pub fn set_view_name(&mut self, view_name: impl Into<String>) {
+        self.table.view_name = view_name.into();
+    }
+
+    /// Add a peer and return its index.
+    ///
+    /// Fails with `InvalidInput` once the table holds 65535 peers.
+    pub fn add_peer(&mut self, peer: PeerEntry) -> std::io::Result<u16> {
+        let index = u16::try_from(self.table.peer_entries.len())
+            .ok()
+            .filter(|&index| index < u16::MAX)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "too many peers"))?;
+        self.table.peer_entries.push(peer);
+        Ok(index)
+    }
+
+    /// Buffer a route announced by the peer at `peer_index`.
+    ///
+    /// Fails with `InvalidInput` if no peer has that index.
+    pub fn add_route(
+        &mut self,
+        peer_index: u16,
+        prefix: Prefix,
+        attributes: Vec<u8>,
+        originated_time: u32,
+    ) -> std::io::Result<()> {
+        if peer_index as usize >= self.table.peer_entries.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "route {} refers to peer index {} of {}",
+                    prefix,
+                    peer_index,
+                    self.table.peer_entries.len()
+                ),
+            ));
+        }
+        self.routes.entry(prefix).or_default().push(RIBEntry {
+            peer_index,
+            originated_time,
+            attributes,
+        });
+        Ok(())
+    }
+
+    /// Write the dump: the PEER_INDEX_TABLE, then the RIB records.
+    pub fn finish(self, w: &mut impl Write) -> std::io::Result<()> {
+        let table = TABLE_DUMP_V2::PEER_INDEX_TABLE(self.table);
+        crate::write(w, self.timestamp, 0, &Record::TABLE_DUMP_V2(table))?;
+
+        for (sequence_number, (prefix, entries)) in self.routes.into_iter().enumerate() {
+            let rib = RIB_AFI {
+                sequence_number: sequence_number as u32,
+                afi: afi_of(&prefix.addr()),
+                prefix_length: prefix.prefix_length(),
+                prefix: prefix.wire_bytes(),
+                entries,
+            };
+            let record = match prefix.addr() {
+                IpAddr::V4(_) => TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib),
+                IpAddr::V6(_) => TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib),
+            };
+            crate::write(w, self.timestamp, 0, &Record::TABLE_DUMP_V2(record))?;
+        }
+        Ok(())
     }
 }
 
+/// Reader that enforces the TABLE_DUMP_V2 peer index table invariants.
+///
+/// Wraps [`read`](crate::read) and returns an `InvalidData` error when a RIB
+/// record arrives before any PEER_INDEX_TABLE, or when a RIB entry's
+/// `peer_index` is out of range for the current table. Each PEER_INDEX_TABLE
+/// replaces the previous one. Records of other types pass through unchanged.
+pub struct ValidatingTableReader<R> {
+    inner: R,
+    body_buf: Vec<u8>,
+    peer_count: Option<usize>,
+}
+
+impl<R: Read> ValidatingTableReader<R> {
+    /// Wrap a stream positioned at the start of an MRT file.
+    pub fn new(inner: R) -> Self {
+        ValidatingTableReader {
+            inner,
+            body_buf: Vec::new(),
+            peer_count: None,
+        }
+    }
+
+    /// Read and validate the next record.
+    ///
+    /// Returns `Ok(None)` at a clean end of stream, like [`read`](crate::read).
+    pub fn read(&mut self) -> std::io::Result<Option<(Header, Record)>> {
+        let (header, record) =
+            match crate::read_with_buffer(&mut self.inner, &mut self.body_buf)? {
+                Some(r) => r,
+                None => return Ok(None),
+            };
+
+        if let Record::TABLE_DUMP_V2(ref table_dump) = record {
+            self.validate(table_dump)?;
+        }
+
+        Ok(Some((header, record)))
+    }
+
+    /// Consume the reader, returning the wrapped stream.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn validate(&mut self, record: &TABLE_DUMP_V2) -> std::io::Result<()> {
+        match record {
+            TABLE_DUMP_V2::PEER_INDEX_TABLE(table) => {
+                self.peer_count = Some(table.peer_entries.len());
+                Ok(())
+            }
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST(rib) => {
+                self.check_indices(rib.entries.iter().map(|e| e.peer_index))
+            }
+            TABLE_DUMP_V2::RIB_GENERIC(rib) => {
+                self.check_indices(rib.entries.iter().map(|e| e.peer_index))
+            }
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV4_MULTICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_UNICAST_ADDPATH(rib)
+            | TABLE_DUMP_V2::RIB_IPV6_MULTICAST_ADDPATH(rib) => {
+                self.check_indices(rib.entries.iter().map(|e| e.peer_index))
+            }
+            TABLE_DUMP_V2::RIB_GENERIC_ADDPATH(rib) => {
+                self.check_indices(rib.entries.iter().map(|e| e.peer_index))
+            }
+            TABLE_DUMP_V2::GEO_PEER_TABLE(_) | TABLE_DUMP_V2::Unknown { .. } => Ok(()),
+        }
+    }
+
+    fn check_indices(&self, mut indices: impl Iterator<Item = u16>) -> std::io::Result<()> {
+        let peer_count = self.peer_count.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "TABLE_DUMP_V2 RIB record before any PEER_INDEX_TABLE",
+            )
+        })?;
+
+        match indices.find(|&index| index as usize >= peer_count) {
+            Some(index) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "RIB entry peer_index {} out of range for PEER_INDEX_TABLE with {} peers",
+                    index, peer_count
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The AFI written for a RIB_GENERIC record: `afi` unless `afi_raw` holds a
+/// family it cannot represent.
+fn generic_wire_afi(afi: &AFI, afi_raw: u16) -> u16 {
+    match AFI::from_u16(afi_raw) {
+        Ok(_) => afi.to_u16(),
+        Err(_) => afi_raw,
+    }
+}
+
+/// Decode RIB_GENERIC NLRI as a single length-prefixed IP prefix.
+///
+/// Only unicast and multicast NLRI is a plain prefix; other SAFIs prepend
+/// labels or route distinguishers and yield `None`.
+fn generic_prefix(afi: u16, safi: u8, nlri: &[u8]) -> Option<Prefix> {
+    if !matches!(safi, safis::UNICAST | safis::MULTICAST) {
+        return None;
+    }
+    let afi = AFI::from_u16(afi).ok()?;
+    let (&prefix_length, bytes) = nlri.split_first()?;
+    Prefix::from_wire(&afi, prefix_length, bytes).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -519,13 +1836,121 @@ mod tests {
             0x00, 0x64, // peer_as = 100
             0x00, 0x00, // attr_len = 0
         ];
-        let result = TABLE_DUMP::parse(&header, &mut data.as_ref()).unwrap();
+        let result = TABLE_DUMP::parse(&header, &mut &data[..]).unwrap();
         assert_eq!(result.view_number, 0);
         assert_eq!(result.sequence_number, 1);
         assert_eq!(result.prefix, IpAddr::V4(Ipv4Addr::new(192, 168, 0, 0)));
         assert_eq!(result.prefix_length, 24);
         assert_eq!(result.peer_address, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
         assert_eq!(result.peer_as, 100);
+        assert_eq!(result.raw_status, None);
+    }
+
+    #[test]
+    fn test_table_dump_legacy_as_path() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 12,
+            sub_type: 1, // AFI_IPv4
+            length: 44,
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, // view_number = 0
+            0x00, 0x01, // sequence_number = 1
+            192, 0, 2, 0, // prefix
+            0x18, // prefix_length = 24
+            0x01, // status
+            0x3A, 0x9B, 0x8C, 0x00, // originated_time
+            10, 0, 0, 1, // peer_address
+            0x0D, 0x1C, // peer_as = 3356
+            0x00, 0x16, // attr_len = 22
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+            0x40, 0x02, 0x08, // AS_PATH, length 8
+            0x02, 0x03, // AS_SEQUENCE of 3
+            0x0D, 0x1C, 0x00, 0xAE, 0xFD, 0xE9, // 3356 174 65001 (2-byte ASNs)
+            0x40, 0x03, 0x04, 10, 0, 0, 1, // NEXT_HOP: 10.0.0.1
+        ];
+        let result = TABLE_DUMP::parse(&header, &mut &data[..]).unwrap();
+
+        let attrs = result.attributes_parsed(false).unwrap();
+        let as_path = attrs.as_path().unwrap().unwrap();
+        assert_eq!(as_path.segments.len(), 1);
+        assert_eq!(as_path.segments[0].asns, vec![3356, 174, 65001]);
+        assert_eq!(as_path.hop_count(), 3);
+
+        // Decoding as 4-byte ASNs reads past the segment
+        let wide = PathAttributes::parse(&result.attributes, true).unwrap();
+        assert!(wide.as_path().is_err());
+    }
+
+    #[test]
+    fn test_parse_table_dump_subtype_zero() {
+        let data: &[u8] = &[
+            0x24, 0x6E, 0x7A, 0x00, // timestamp
+            0x00, 0x0C, // type = TABLE_DUMP
+            0x00, 0x00, // subtype = 0 (legacy AFI_IPv4)
+            0x00, 0x00, 0x00, 0x1A, // length = 26
+            0x00, 0x00, // view_number = 0
+            0x00, 0x07, // sequence_number = 7
+            198, 51, 100, 0, // prefix
+            0x18, // prefix_length = 24
+            0x01, // status
+            0x24, 0x6E, 0x70, 0x00, // originated_time
+            10, 0, 0, 1, // peer_address
+            0x1A, 0x0A, // peer_as = 6666
+            0x00, 0x04, // attr_len = 4
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+        ];
+        let (header, record) = crate::read(&mut &data[..]).unwrap().unwrap();
+        assert_eq!(header.sub_type, 0);
+        let table_dump = match record {
+            Record::TABLE_DUMP(x) => x,
+            other => panic!("unexpected record {:?}", other),
+        };
+        assert_eq!(table_dump.prefix_addr().unwrap().to_string(), "198.51.100.0/24");
+        assert_eq!(table_dump.peer_as, 6666);
+        assert_eq!(table_dump.sub_type(), 1);
+    }
+
+    #[test]
+    fn test_parse_table_dump_wide_status() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 12,
+            sub_type: 1, // AFI_IPv4
+            length: 23,  // one byte longer than the standard layout
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, // view_number = 0
+            0x00, 0x01, // sequence_number = 1
+            192, 168, 0, 0, // prefix
+            0x18, // prefix_length = 24
+            0x00, 0x01, // status (16-bit)
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            10, 0, 0, 1, // peer_address
+            0x00, 0x64, // peer_as = 100
+            0x00, 0x00, // attr_len = 0
+        ];
+
+        // The standard layout reads attr_len from the wrong offset
+        let err = TABLE_DUMP::parse(&header, &mut &data[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("length mismatch"));
+
+        let result =
+            TABLE_DUMP::parse_with_layout(&header, &mut &data[..], TableDumpLayout::WideStatus)
+                .unwrap();
+        assert_eq!(result.status, 1);
+        assert_eq!(result.raw_status, Some(1));
+        assert_eq!(result.originated_time, 0x5F5E1000);
+        assert_eq!(result.peer_address, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(result.peer_as, 100);
+
+        let mut out = Vec::new();
+        result.write(&mut out).unwrap();
+        assert_eq!(out, data);
     }
 
     #[test]
@@ -548,10 +1973,12 @@ mod tests {
             192, 168, 1, 1, // peer_ip_address (IPv4)
             0x00, 0x64, // peer_as = 100 (16-bit)
         ];
-        let result = TABLE_DUMP_V2::parse(&header, &mut data.as_ref()).unwrap();
+        let result = TABLE_DUMP_V2::parse(&header, &mut &data[..]).unwrap();
         match result {
             TABLE_DUMP_V2::PEER_INDEX_TABLE(pit) => {
                 assert_eq!(pit.collector_id, 0x0A000001);
+                assert_eq!(pit.collector_id_addr(), Ipv4Addr::new(10, 0, 0, 1));
+                assert_eq!(pit.peer_entries[0].peer_bgp_id_addr(), Ipv4Addr::new(10, 0, 0, 1));
                 assert_eq!(pit.view_name, "test");
                 assert_eq!(pit.peer_entries.len(), 1);
                 assert_eq!(pit.peer_entries[0].peer_as, 100);
@@ -583,7 +2010,8 @@ mod tests {
             0x5F, 0x5E, 0x10, 0x00, // originated_time
             0x00, 0x00, // attr_len = 0
         ];
-        let result = TABLE_DUMP_V2::parse(&header, &mut data.as_ref()).unwrap();
+        let result = TABLE_DUMP_V2::parse(&header, &mut &data[..]).unwrap();
+        assert_eq!(result.entry_count(), 1);
         match result {
             TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib) => {
                 assert_eq!(rib.sequence_number, 1);
@@ -591,11 +2019,366 @@ mod tests {
                 assert_eq!(rib.prefix, vec![192, 168, 1]);
                 assert_eq!(rib.entries.len(), 1);
                 assert_eq!(rib.entries[0].peer_index, 0);
+                assert_eq!(rib.afi, AFI::IPV4);
+                assert_eq!(rib.prefix_addr().unwrap().to_string(), "192.168.1.0/24");
             }
             _ => panic!("Expected RIB_IPV4_UNICAST"),
         }
     }
 
+    #[test]
+    fn test_parse_rib_generic_bgp_ls() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x07, // sequence_number = 7
+            0x40, 0x04, // AFI = 16388 (BGP-LS)
+            0x47, // SAFI = 71 (BGP-LS)
+            0x00, 0x0D, // nlri_len = 13
+            0x00, 0x01, // NLRI type: Node
+            0x00, 0x09, // NLRI length
+            0x03, // protocol_id: OSPFv2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // identifier
+            0x00, 0x00, // entry_count = 0
+        ];
+        let header = Header {
+            timestamp: 0,
+            extended: 0,
+            record_type: 13,
+            sub_type: 6, // RIB_GENERIC
+            length: data.len() as u32,
+        };
+        let TABLE_DUMP_V2::RIB_GENERIC(rib) = TABLE_DUMP_V2::parse(&header, &mut &data[..]).unwrap()
+        else {
+            panic!("Expected RIB_GENERIC");
+        };
+        assert_eq!((rib.afi_raw, rib.safi), (BGP_LS_AFI, crate::bgp::attributes::safis::BGP_LS));
+        assert_eq!(rib.afi, AFI::IPV4);
+        let Some(LinkStateNlri::Node(node)) = rib.link_state_nlri().unwrap() else {
+            panic!("Expected a BGP-LS Node NLRI");
+        };
+        assert_eq!((node.protocol_id, node.identifier), (3, 1));
+
+        let mut out = Vec::new();
+        rib.write(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_default_route_prefixes() {
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 12,
+            sub_type: 1, // AFI_IPv4
+            length: 22,
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, // view_number = 0
+            0x00, 0x01, // sequence_number = 1
+            0, 0, 0, 0, // prefix
+            0x00, // prefix_length = 0
+            0x01, // status
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            10, 0, 0, 1, // peer_address
+            0x00, 0x64, // peer_as = 100
+            0x00, 0x00, // attr_len = 0
+        ];
+        let result = TABLE_DUMP::parse(&header, &mut &data[..]).unwrap();
+        assert_eq!(result.prefix_addr().unwrap().to_string(), "0.0.0.0/0");
+
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 13,
+            sub_type: 4, // RIB_IPV6_UNICAST
+            length: 15,
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+            0x00, // prefix_length = 0, no prefix bytes
+            0x00, 0x01, // entry_count = 1
+            // RIB entry:
+            0x00, 0x00, // peer_index = 0
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, // attr_len = 0
+        ];
+        match TABLE_DUMP_V2::parse(&header, &mut &data[..]).unwrap() {
+            TABLE_DUMP_V2::RIB_IPV6_UNICAST(rib) => {
+                assert!(rib.prefix.is_empty());
+                assert_eq!(rib.entries.len(), 1);
+                assert_eq!(rib.prefix_addr().unwrap().to_string(), "::/0");
+            }
+            other => panic!("Expected RIB_IPV6_UNICAST, got {:?}", other),
+        }
+
+        let header = Header {
+            sub_type: 8, // RIB_IPV4_UNICAST_ADDPATH
+            length: 7,
+            ..header
+        };
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+            0x00, // prefix_length = 0, no prefix bytes
+            0x00, 0x00, // entry_count = 0
+        ];
+        match TABLE_DUMP_V2::parse(&header, &mut &data[..]).unwrap() {
+            TABLE_DUMP_V2::RIB_IPV4_UNICAST_ADDPATH(rib) => {
+                assert_eq!(rib.prefix_addr().unwrap().to_string(), "0.0.0.0/0");
+            }
+            other => panic!("Expected RIB_IPV4_UNICAST_ADDPATH, got {:?}", other),
+        }
+
+        // RIB_GENERIC NLRI holding just the zero length byte
+        let prefix = generic_prefix(2, safis::UNICAST, &[0x00]).unwrap();
+        assert_eq!(prefix.to_string(), "::/0");
+        assert!(generic_prefix(1, safis::UNICAST, &[]).is_none());
+    }
+
+    #[test]
+    fn test_session_flatten() {
+        let pit_header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 13,
+            sub_type: 1, // PEER_INDEX_TABLE
+            length: 23,
+        };
+        let pit: &[u8] = &[
+            0x0A, 0x00, 0x00, 0x01, // collector_id
+            0x00, 0x00, // view_name_length = 0
+            0x00, 0x01, // peer_count = 1
+            0x02, // peer_type = AS4, IPv4
+            0x0A, 0x00, 0x00, 0x02, // peer_bgp_id
+            192, 168, 1, 1, // peer_ip_address
+            0x00, 0x00, 0xFD, 0xE8, // peer_as = 65000
+        ];
+        let rib_header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 13,
+            sub_type: 8, // RIB_IPV4_UNICAST_ADDPATH
+            length: 33,
+        };
+        let rib: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+            0x10, // prefix_length = 16
+            10, 1, // prefix
+            0x00, 0x02, // entry_count = 2
+            0x00, 0x00, // peer_index = 0
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, 0x00, 0x07, // path_identifier = 7
+            0x00, 0x00, // attr_len = 0
+            0x00, 0x05, // peer_index = 5 (unknown, skipped)
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, 0x00, 0x08, // path_identifier = 8
+            0x00, 0x00, // attr_len = 0
+        ];
+
+        let mut session = TableDumpV2Session::new();
+        let pit = TABLE_DUMP_V2::parse(&pit_header, &mut &pit[..]).unwrap();
+        assert_eq!(session.flatten(pit).count(), 0);
+        assert!(session.peer_table().is_some());
+
+        let rib = TABLE_DUMP_V2::parse(&rib_header, &mut &rib[..]).unwrap();
+        let routes: Vec<FlatRoute> = session.flatten(rib).collect();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].prefix.to_string(), "10.1.0.0/16");
+        assert_eq!(routes[0].peer.peer_as, 65000);
+        assert_eq!(routes[0].path_id, Some(7));
+    }
+
+    #[test]
+    fn test_session_flatten_generic_safi() {
+        let pit_header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 13,
+            sub_type: 1, // PEER_INDEX_TABLE
+            length: ONE_PEER_TABLE.len() as u32,
+        };
+        let generic = |safi: u8, nlri: &[u8]| {
+            let mut body = vec![
+                0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+                0x00, 0x01, // AFI = IPv4
+                safi, // SAFI
+            ];
+            body.extend_from_slice(&(nlri.len() as u16).to_be_bytes());
+            body.extend_from_slice(nlri);
+            body.extend_from_slice(&[
+                0x00, 0x01, // entry_count = 1
+                0x00, 0x00, // peer_index = 0
+                0x5F, 0x5E, 0x10, 0x00, // originated_time
+                0x00, 0x00, // attr_len = 0
+            ]);
+            let header = Header {
+                sub_type: 6, // RIB_GENERIC
+                length: body.len() as u32,
+                ..pit_header
+            };
+            TABLE_DUMP_V2::parse(&header, &mut &body[..]).unwrap()
+        };
+
+        let mut session = TableDumpV2Session::new();
+        let pit = TABLE_DUMP_V2::parse(&pit_header, &mut &ONE_PEER_TABLE[..]).unwrap();
+        assert_eq!(session.flatten(pit).count(), 0);
+
+        let unicast = generic(safis::UNICAST, &[0x08, 10]);
+        let routes: Vec<FlatRoute> = session.flatten(unicast).collect();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].prefix.to_string(), "10.0.0.0/8");
+
+        // Labeled unicast: 32 bits of label and prefix would pass as an IPv4 /32
+        let labeled = generic(safis::MPLS_LABEL, &[0x20, 0x00, 0x06, 0x41, 10]);
+        assert_eq!(session.flatten(labeled).count(), 0);
+    }
+
+    /// Frame a record body with an MRT common header.
+    fn mrt_record(record_type: u16, sub_type: u16, body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&1000u32.to_be_bytes());
+        data.extend_from_slice(&record_type.to_be_bytes());
+        data.extend_from_slice(&sub_type.to_be_bytes());
+        data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    const ONE_PEER_TABLE: &[u8] = &[
+        0x0A, 0x00, 0x00, 0x01, // collector_id
+        0x00, 0x00, // view_name_length = 0
+        0x00, 0x01, // peer_count = 1
+        0x00, // peer_type = IPv4, 16-bit AS
+        0x0A, 0x00, 0x00, 0x02, // peer_bgp_id
+        192, 168, 1, 1, // peer_ip_address
+        0x00, 0x64, // peer_as = 100
+    ];
+
+    fn rib_ipv4_entry(peer_index: u16) -> Vec<u8> {
+        let mut body = vec![
+            0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+            0x08, 10, // 10.0.0.0/8
+            0x00, 0x01, // entry_count = 1
+        ];
+        body.extend_from_slice(&peer_index.to_be_bytes());
+        body.extend_from_slice(&[0x5F, 0x5E, 0x10, 0x00, 0x00, 0x00]); // time, attr_len = 0
+        body
+    }
+
+    #[test]
+    fn test_parse_streaming() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x07, // sequence_number = 7
+            0x10, 192, 168, // 192.168.0.0/16
+            0x00, 0x02, // entry_count = 2
+            0x00, 0x03, // peer_index = 3
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x04, // attr_len = 4
+            0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+            0x00, 0x04, // peer_index = 4
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, // attr_len = 0
+        ];
+        let mut peers = Vec::new();
+        let rib = RIB_AFI::parse_streaming(&AFI::IPV4, &mut &data[..], |entry| {
+            peers.push(entry.peer_index)
+        })
+        .unwrap();
+        assert_eq!(peers, [3, 4]);
+        assert_eq!(rib.sequence_number, 7);
+        assert_eq!(rib.prefix_addr().unwrap().to_string(), "192.168.0.0/16");
+        assert!(rib.entries.is_empty());
+
+        // The same entries as the eager parser
+        let eager = RIB_AFI::parse(&AFI::IPV4, &mut &data[..]).unwrap();
+        let mut entries = Vec::new();
+        RIB_AFI::parse_streaming(&AFI::IPV4, &mut &data[..], |entry| entries.push(entry)).unwrap();
+        assert_eq!(entries, eager.entries);
+
+        // A truncated entry fails after the complete ones were passed on
+        let mut count = 0;
+        let err = RIB_AFI::parse_streaming(&AFI::IPV4, &mut &data[..data.len() - 1], |_| count += 1)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(count, 1);
+
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // sequence_number
+            0x00, 0x02, // AFI = IPv6
+            0x01, // SAFI = unicast
+            0x00, 0x03, // nlri_len = 3
+            0x10, 0x20, 0x01, // 2001::/16
+            0x00, 0x01, // entry_count = 1
+            0x00, 0x00, // peer_index
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, 0x00, 0x09, // path_identifier = 9
+            0x00, 0x00, // attr_len = 0
+        ];
+        let mut path_ids = Vec::new();
+        let rib = RIB_GENERIC_ADDPATH::parse_streaming(&mut &data[..], |entry| {
+            path_ids.push(entry.path_identifier)
+        })
+        .unwrap();
+        assert_eq!(path_ids, [9]);
+        assert_eq!(rib.nlri, &[0x10, 0x20, 0x01]);
+    }
+
+    #[test]
+    fn test_validating_reader() {
+        let mut data = mrt_record(13, 1, ONE_PEER_TABLE);
+        data.extend(mrt_record(13, 2, &rib_ipv4_entry(0)));
+        let mut reader = ValidatingTableReader::new(&data[..]);
+        assert!(reader.read().unwrap().is_some());
+        assert!(reader.read().unwrap().is_some());
+        assert!(reader.read().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_validating_reader_rib_before_table() {
+        let data = mrt_record(13, 2, &rib_ipv4_entry(0));
+        let mut reader = ValidatingTableReader::new(&data[..]);
+        let err = reader.read().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("before any PEER_INDEX_TABLE"));
+    }
+
+    #[test]
+    fn test_validating_reader_index_out_of_range() {
+        let mut data = mrt_record(13, 1, ONE_PEER_TABLE);
+        data.extend(mrt_record(13, 2, &rib_ipv4_entry(1)));
+        let mut reader = ValidatingTableReader::new(&data[..]);
+        assert!(reader.read().unwrap().is_some());
+        let err = reader.read().unwrap_err();
+        assert!(err.to_string().contains("peer_index 1 out of range"));
+    }
+
+    #[test]
+    fn test_session_concatenated_peer_tables() {
+        let second_table: &[u8] = &[
+            0x0A, 0x00, 0x00, 0x09, // collector_id
+            0x00, 0x00, // view_name_length = 0
+            0x00, 0x01, // peer_count = 1
+            0x00, // peer_type = IPv4, 16-bit AS
+            0x0A, 0x00, 0x00, 0x03, // peer_bgp_id
+            192, 168, 2, 2, // peer_ip_address
+            0x00, 0xC8, // peer_as = 200
+        ];
+        let mut data = mrt_record(13, 1, ONE_PEER_TABLE);
+        data.extend(mrt_record(13, 2, &rib_ipv4_entry(0)));
+        data.extend(mrt_record(13, 1, second_table));
+        data.extend(mrt_record(13, 2, &rib_ipv4_entry(0)));
+
+        let mut session = TableDumpV2Session::new();
+        let mut peer_as = Vec::new();
+        let mut cursor = &data[..];
+        while let Some((_, record)) = crate::read(&mut cursor).unwrap() {
+            if let Record::TABLE_DUMP_V2(table_dump) = record {
+                peer_as.extend(session.flatten(table_dump).map(|route| route.peer.peer_as));
+            }
+        }
+
+        assert_eq!(peer_as, vec![100, 200]);
+        assert_eq!(session.peer_table().unwrap().collector_id, 0x0A000009);
+    }
+
     #[test]
     fn test_peer_type_flags() {
         // Test IPv6 + 32-bit AS
@@ -607,8 +2390,286 @@ mod tests {
             0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
             0x00, 0x01, 0x00, 0x00, // peer_as = 65536 (32-bit)
         ];
-        let result = PeerEntry::parse(&mut data.as_ref()).unwrap();
+        let result = PeerEntry::parse(&mut &data[..]).unwrap();
         assert!(result.peer_ip_address.is_ipv6());
         assert_eq!(result.peer_as, 65536);
+        assert!(result.is_ipv6());
+        assert!(result.is_as4());
+        assert_eq!(
+            result.flags(),
+            PeerType {
+                ipv6: true,
+                as4: true,
+                reserved: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_peer_type_reserved_bits() {
+        let data: &[u8] = &[
+            0x07, // peer_type = IPv6, AS4 and undefined bit 2
+            0x0A, 0x00, 0x00, 0x01, // peer_bgp_id
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 2001:db8::1
+            0x00, 0x01, 0x00, 0x00, // peer_as = 65536
+        ];
+        let peer = PeerEntry::parse(&mut &data[..]).unwrap();
+        let flags = peer.flags();
+        assert!(flags.ipv6 && flags.as4);
+        assert_eq!(flags.reserved, 0x04);
+        assert_eq!(u8::from(flags), 0x07);
+
+        let mut out = Vec::new();
+        peer.write(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let table = PEER_INDEX_TABLE::parse(&mut &ONE_PEER_TABLE[..]).unwrap();
+        let mut out = Vec::new();
+        table.write(&mut out).unwrap();
+        assert_eq!(out, ONE_PEER_TABLE);
+
+        let body = rib_ipv4_entry(0);
+        let rib = RIB_AFI::parse(&AFI::IPV4, &mut &body[..]).unwrap();
+        let record = TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib);
+        assert_eq!(record.sub_type(), 2);
+        let mut out = Vec::new();
+        record.write(&mut out).unwrap();
+        assert_eq!(out, body);
+    }
+
+    #[test]
+    fn test_peer_entry_new_write() {
+        let peer = PeerEntry::new(0x0A000001, "2001:db8::1".parse().unwrap(), 65536);
+        assert_eq!(u8::from(peer.flags()), 0x03);
+        let mut out = Vec::new();
+        peer.write(&mut out).unwrap();
+        let parsed = PeerEntry::parse(&mut &out[..]).unwrap();
+        assert_eq!(parsed.peer_ip_address, peer.peer_ip_address);
+        assert_eq!(parsed.peer_as, 65536);
+
+        // 16-bit AS encoding cannot carry a 32-bit AS number
+        let mut peer = PeerEntry::new(1, "192.0.2.1".parse().unwrap(), 65536);
+        peer.peer_type = 0;
+        assert_eq!(peer.write(&mut Vec::new()).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_addpath_routes() {
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // sequence_number = 1
+            0x10, 172, 16, // 172.16.0.0/16
+            0x00, 0x02, // entry_count = 2
+            0x00, 0x00, // peer_index = 0
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, 0x00, 0x01, // path_identifier = 1
+            0x00, 0x01, 0xAA, // attributes
+            0x00, 0x03, // peer_index = 3
+            0x5F, 0x5E, 0x10, 0x00, // originated_time
+            0x00, 0x00, 0x00, 0x02, // path_identifier = 2
+            0x00, 0x00, // attr_len = 0
+        ];
+        let rib = RIB_AFI_ADDPATH::parse(&AFI::IPV4, &mut &data[..]).unwrap();
+        let routes = rib.routes().unwrap();
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].0.to_string(), "172.16.0.0/16");
+        assert_eq!((routes[0].1, routes[0].2, routes[0].3), (1, 0, &[0xAA][..]));
+        assert_eq!((routes[1].1, routes[1].2), (2, 3));
+
+        let generic = RIB_GENERIC_ADDPATH {
+            sequence_number: 1,
+            afi: AFI::IPV4,
+            afi_raw: 1,
+            safi: 1,
+            nlri: vec![0x10, 172, 16],
+            entries: rib.entries,
+        };
+        let routes = generic.routes();
+        assert_eq!(routes.len(), 2);
+        assert!(routes.iter().all(|r| r.0 == [0x10, 172, 16]));
+        assert_eq!(routes[1].1, 2);
+    }
+
+    #[test]
+    fn test_entry_count_bounds() {
+        // entry_count claims 65535 entries in a 16-byte body
+        let mut body = rib_ipv4_entry(0);
+        body[6..8].copy_from_slice(&[0xFF, 0xFF]);
+        let data = mrt_record(13, 2, &body);
+        let err = crate::read(&mut &data[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("does not fit"));
+
+        let mut table = ONE_PEER_TABLE.to_vec();
+        table[6..8].copy_from_slice(&[0x00, 0x02]); // peer_count = 2
+        let data = mrt_record(13, 1, &table);
+        assert!(crate::read(&mut &data[..]).is_err());
+
+        // A well-formed record over the configured limit
+        let mut body = rib_ipv4_entry(0);
+        body[7] = 2; // entry_count = 2
+        body.extend_from_slice(&[0x00, 0x00, 0x5F, 0x5E, 0x10, 0x00, 0x00, 0x00]);
+        let data = mrt_record(13, 2, &body);
+        assert!(crate::read(&mut &data[..]).is_ok());
+        let options = ReadOptions {
+            max_entries: 1,
+            ..Default::default()
+        };
+        let err = crate::read_with_options(&mut &data[..], &mut Vec::new(), &options).unwrap_err();
+        assert!(err.to_string().contains("exceeds limit"));
+    }
+
+    #[test]
+    fn test_unknown_subtype() {
+        // A body with a subtype from a future revision of the registry
+        let data = mrt_record(13, 99, &[0xDE, 0xAD, 0xBE, 0xEF]);
+        let (_, record) = crate::read(&mut &data[..]).unwrap().unwrap();
+        let Record::TABLE_DUMP_V2(table_dump) = record else {
+            panic!("Expected TABLE_DUMP_V2");
+        };
+        assert!(matches!(
+            &table_dump,
+            TABLE_DUMP_V2::Unknown { sub_type: 99, body } if body == &[0xDE, 0xAD, 0xBE, 0xEF]
+        ));
+
+        let mut session = TableDumpV2Session::new();
+        assert_eq!(session.flatten(table_dump.clone()).count(), 0);
+
+        let mut out = Vec::new();
+        crate::write(&mut out, 1000, 0, &Record::TABLE_DUMP_V2(table_dump)).unwrap();
+        assert_eq!(out, data);
+
+        // A huge length on a short stream fails without allocating it
+        let header = Header {
+            timestamp: 1000,
+            extended: 0,
+            record_type: 13,
+            sub_type: 99,
+            length: u32::MAX,
+        };
+        let err = TABLE_DUMP_V2::parse(&header, &mut &[0xDE, 0xAD][..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_geo_peer_table() {
+        let body: &[u8] = &[
+            0x0A, 0x00, 0x00, 0x01, // collector_bgp_id
+            0x00, 0x00, // view_name_length = 0
+            0x42, 0x4E, 0x00, 0x00, // latitude = 51.5
+            0xBE, 0x80, 0x00, 0x00, // longitude = -0.25
+            0x00, 0x01, // peer_count = 1
+            0x02, // peer_type = AS4, IPv4
+            0xC0, 0x00, 0x02, 0x01, // peer_bgp_id
+            0xC0, 0x00, 0x02, 0x01, // peer_ip = 192.0.2.1
+            0x00, 0x00, 0xFD, 0xE9, // peer_as = 65001
+            0x42, 0x20, 0x00, 0x00, // latitude = 40.0
+            0xC2, 0x94, 0x00, 0x00, // longitude = -74.0
+        ];
+        let data = mrt_record(13, 7, body);
+        let (header, record) = crate::read(&mut &data[..]).unwrap().unwrap();
+        assert_eq!(crate::subtype_name(header.record_type, header.sub_type), "GEO_PEER_TABLE");
+        let Record::TABLE_DUMP_V2(TABLE_DUMP_V2::GEO_PEER_TABLE(ref table)) = record else {
+            panic!("Expected GEO_PEER_TABLE");
+        };
+        assert_eq!(table.collector_bgp_id, 0x0A000001);
+        assert_eq!(table.latitude, 51.5);
+        assert_eq!(table.longitude, -0.25);
+        assert_eq!(table.peer_entries.len(), 1);
+        let entry = &table.peer_entries[0];
+        assert_eq!(entry.peer.peer_as, 65001);
+        assert_eq!(entry.latitude, 40.0);
+        assert_eq!(entry.longitude, -74.0);
+
+        let mut out = Vec::new();
+        crate::write(&mut out, 1000, 0, &record).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_table_dump_v2_writer() {
+        let mut writer = TableDumpV2Writer::new(0x0A000001, 1000);
+        writer.set_view_name("main");
+        let a = writer.add_peer(PeerEntry::new(1, "192.0.2.1".parse().unwrap(), 65001)).unwrap();
+        let b = writer.add_peer(PeerEntry::new(2, "2001:db8::2".parse().unwrap(), 65002)).unwrap();
+        assert_eq!((a, b), (0, 1));
+
+        let routes = [
+            ("2001:db8::/32", b),
+            ("10.0.0.0/8", a),
+            ("9.0.0.0/8", b),
+            ("10.0.0.0/8", b),
+        ];
+        for (prefix, peer) in routes {
+            writer.add_route(peer, prefix.parse().unwrap(), vec![], 500).unwrap();
+        }
+        let err = writer.add_route(2, "10.0.0.0/8".parse().unwrap(), vec![], 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let mut out = Vec::new();
+        writer.finish(&mut out).unwrap();
+
+        let mut reader = ValidatingTableReader::new(out.as_slice());
+        let mut session = TableDumpV2Session::new();
+        let mut flat = Vec::new();
+        while let Some((_, Record::TABLE_DUMP_V2(table_dump))) = reader.read().unwrap() {
+            flat.extend(session.flatten(table_dump));
+        }
+        assert_eq!(session.peer_table().unwrap().view_name, "main");
+
+        let routes: Vec<(String, u32)> =
+            flat.iter().map(|r| (r.prefix.to_string(), r.peer.peer_as)).collect();
+        assert_eq!(
+            routes,
+            [
+                ("9.0.0.0/8".to_string(), 65002),
+                ("10.0.0.0/8".to_string(), 65001),
+                ("10.0.0.0/8".to_string(), 65002),
+                ("2001:db8::/32".to_string(), 65002),
+            ]
+        );
+        assert!(flat.iter().all(|r| r.originated_time == 500));
+    }
+
+    #[test]
+    fn test_rib_entry_attributes_parsed_as4() {
+        let entry = RIBEntry {
+            peer_index: 0,
+            originated_time: 0,
+            attributes: vec![
+                0x40, 0x02, 0x0A, // AS_PATH, length 10
+                0x02, 0x02, 0x00, 0x00, 0xFD, 0xE8, 0x00, 0x03, 0x0D, 0x40, // SEQ 65000 200000
+            ],
+        };
+        let path = entry.attributes_parsed(true).unwrap().as_path().unwrap().unwrap();
+        assert_eq!(path.segments[0].asns, vec![65000, 200000]);
+    }
+
+    #[test]
+    fn test_rib_entry_set_attributes() {
+        let mut entry = RIBEntry {
+            peer_index: 0,
+            originated_time: 0,
+            attributes: vec![
+                0x40, 0x01, 0x01, 0x00, // ORIGIN: IGP
+                0xC0, 0x08, 0x08, 0xFD, 0xE8, 0x00, 0x01, 0xFD, 0xE8, 0x00, 0x02, // COMMUNITIES
+            ],
+        };
+
+        // Strip one community and write the entry back out
+        let mut attrs = entry.attributes_parsed(true).unwrap();
+        attrs.attributes[1].value.truncate(4);
+        entry.set_attributes(&attrs).unwrap();
+        assert_eq!(
+            entry.attributes,
+            &[0x40, 0x01, 0x01, 0x00, 0xC0, 0x08, 0x04, 0xFD, 0xE8, 0x00, 0x01]
+        );
+
+        let mut out = Vec::new();
+        entry.write(&mut out).unwrap();
+        let parsed = RIBEntry::parse(&mut out.as_slice()).unwrap();
+        assert_eq!(parsed.attributes, entry.attributes);
     }
 }
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Sequence number checks for TABLE_DUMP_V2 RIB dumps.
+
+use std::fmt;
+
+use crate::Record;
+
+/// A RIB record whose sequence number does not follow the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceError {
+    /// The sequence number that should have come next
+    pub expected: u32,
+    /// The sequence number found in the record
+    pub found: u32,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RIB sequence number {} out of order, expected {}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// Checks that TABLE_DUMP_V2 RIB sequence numbers increase by one per record.
+///
+/// A gap or reset in the sequence usually means records were lost, or that
+/// several dumps were concatenated into one file. Concatenation is legitimate
+/// in some workflows, so this check is opt-in: feed records to
+/// [`check`](SequenceChecker::check) and decide what to do with the errors.
+///
+/// After a mismatch the checker continues from the sequence number found, so
+/// each break is reported once. The first break is kept in
+/// [`first_error`](SequenceChecker::first_error).
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::SequenceChecker;
+///
+/// let mut reader = std::io::BufReader::new(std::fs::File::open("rib.mrt").unwrap());
+/// let mut checker = SequenceChecker::new();
+/// while let Some((_header, record)) = mrt_ingester::read(&mut reader).unwrap() {
+///     checker.check(&record).ok();
+/// }
+/// if let Some(err) = checker.first_error() {
+///     eprintln!("warning: {}", err);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SequenceChecker {
+    next: Option<u32>,
+    first_error: Option<SequenceError>,
+}
+
+impl SequenceChecker {
+    /// Create a checker that accepts any sequence number for the first record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the sequence number of a TABLE_DUMP_V2 RIB record.
+    ///
+    /// Other records, including PEER_INDEX_TABLE, are ignored.
+    pub fn check(&mut self, record: &Record) -> Result<(), SequenceError> {
+        match record {
+            Record::TABLE_DUMP_V2(record) => match record.sequence_number() {
+                Some(sequence_number) => self.check_sequence_number(sequence_number),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the next sequence number directly.
+    pub fn check_sequence_number(&mut self, sequence_number: u32) -> Result<(), SequenceError> {
+        let expected = self.next;
+        self.next = Some(sequence_number.wrapping_add(1));
+        match expected {
+            Some(expected) if expected != sequence_number => {
+                let err = SequenceError {
+                    expected,
+                    found: sequence_number,
+                };
+                self.first_error.get_or_insert(err);
+                Err(err)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The first break in the sequence, if any.
+    pub fn first_error(&self) -> Option<SequenceError> {
+        self.first_error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tabledump::{RIB_AFI, RIB_GENERIC, TABLE_DUMP_V2};
+    use crate::AFI;
+
+    fn rib(sequence_number: u32) -> Record {
+        Record::TABLE_DUMP_V2(TABLE_DUMP_V2::RIB_IPV4_UNICAST(RIB_AFI {
+            sequence_number,
+            afi: AFI::IPV4,
+            prefix_length: 8,
+            prefix: vec![10],
+            entries: Vec::new(),
+        }))
+    }
+
+    #[test]
+    fn test_sequence_checker() {
+        let mut checker = SequenceChecker::new();
+        checker.check(&Record::NULL).unwrap();
+        checker.check(&rib(0)).unwrap();
+        checker.check(&rib(1)).unwrap();
+        let generic = Record::TABLE_DUMP_V2(TABLE_DUMP_V2::RIB_GENERIC(RIB_GENERIC {
+            sequence_number: 2,
+            afi: AFI::IPV6,
+            afi_raw: 2,
+            safi: 1,
+            nlri: Vec::new(),
+            entries: Vec::new(),
+        }));
+        checker.check(&generic).unwrap();
+        assert_eq!(checker.first_error(), None);
+
+        // A second dump appended to the first restarts at zero
+        let err = checker.check(&rib(0)).unwrap_err();
+        assert_eq!(err, SequenceError { expected: 3, found: 0 });
+        assert_eq!(err.to_string(), "RIB sequence number 0 out of order, expected 3");
+        checker.check(&rib(1)).unwrap();
+        let err = checker.check(&rib(5)).unwrap_err();
+        assert_eq!(err, SequenceError { expected: 2, found: 5 });
+        assert_eq!(checker.first_error(), Some(SequenceError { expected: 3, found: 0 }));
+    }
+}
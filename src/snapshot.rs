@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! In-memory RIB snapshots and the differences between them.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::records::tabledump::{FlatRoute, PeerEntry, TableDumpV2Session};
+use crate::{Prefix, Record, RecordReader};
+
+/// Identifies a route within a RIB snapshot.
+///
+/// Peers are identified by PEER_INDEX_TABLE index, so peers sharing an
+/// address stay distinct. Resolve the index with [`RibSnapshot::peer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RouteKey {
+    /// Index of the peer that announced the route
+    pub peer_index: u16,
+    /// Route prefix
+    pub prefix: Prefix,
+    /// Add-Path identifier (`None` for non-Add-Path subtypes)
+    pub path_id: Option<u32>,
+}
+
+/// The routes of a TABLE_DUMP_V2 RIB dump, keyed by peer and prefix.
+///
+/// Each route maps to its raw path attribute blob.
+///
+/// # Example
+///
+/// ```no_run
+/// use mrt_ingester::RibSnapshot;
+///
+/// let open = |path| std::io::BufReader::new(std::fs::File::open(path).unwrap());
+/// let old = RibSnapshot::from_reader(open("rib.0000.mrt")).unwrap();
+/// let new = RibSnapshot::from_reader(open("rib.0200.mrt")).unwrap();
+///
+/// let diff = old.diff(&new);
+/// println!("{} added, {} removed", diff.added.len(), diff.removed.len());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RibSnapshot {
+    routes: HashMap<RouteKey, Vec<u8>>,
+    peers: HashMap<u16, PeerEntry>,
+}
+
+/// Routes that differ between two [`RibSnapshot`]s, each list sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RibDiff {
+    /// Routes present only in the newer snapshot
+    pub added: Vec<RouteKey>,
+    /// Routes present only in the older snapshot
+    pub removed: Vec<RouteKey>,
+    /// Routes present in both with different attribute bytes
+    pub changed: Vec<RouteKey>,
+}
+
+impl RibSnapshot {
+    /// Create an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a whole RIB dump, flattening its TABLE_DUMP_V2 records.
+    ///
+    /// Records of other types are ignored. Routes whose peer index cannot be
+    /// resolved are skipped, as in [`TableDumpV2Session::flatten`].
+    pub fn from_reader(reader: impl Read) -> std::io::Result<Self> {
+        let mut snapshot = RibSnapshot::new();
+        let mut session = TableDumpV2Session::new();
+        for result in RecordReader::new(reader) {
+            if let (_, Record::TABLE_DUMP_V2(table_dump)) = result? {
+                session
+                    .flatten(table_dump)
+                    .for_each(|route| snapshot.insert(route));
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Add a route, replacing any earlier route with the same key.
+    pub fn insert(&mut self, route: FlatRoute) {
+        let key = RouteKey {
+            peer_index: route.peer_index,
+            prefix: route.prefix,
+            path_id: route.path_id,
+        };
+        self.routes.insert(key, route.attributes);
+        self.peers.insert(route.peer_index, route.peer);
+    }
+
+    /// The peer with `peer_index`, as last seen in an inserted route.
+    pub fn peer(&self, peer_index: u16) -> Option<&PeerEntry> {
+        self.peers.get(&peer_index)
+    }
+
+    /// Attribute blob of the route with `key`, if present.
+    pub fn get(&self, key: &RouteKey) -> Option<&[u8]> {
+        self.routes.get(key).map(Vec::as_slice)
+    }
+
+    /// Number of routes.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Whether the snapshot holds no routes.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Compare with a newer snapshot.
+    ///
+    /// Attributes are compared byte for byte, so a route whose attributes
+    /// were re-encoded differently counts as changed. Routes are matched by
+    /// peer index, so the routes of a peer renumbered between the dumps are
+    /// reported as removed and added.
+    pub fn diff(&self, newer: &RibSnapshot) -> RibDiff {
+        let mut diff = RibDiff::default();
+        for (key, attributes) in &self.routes {
+            match newer.routes.get(key) {
+                None => diff.removed.push(*key),
+                Some(other) if other != attributes => diff.changed.push(*key),
+                Some(_) => {}
+            }
+        }
+        diff.added = newer
+            .routes
+            .keys()
+            .filter(|key| !self.routes.contains_key(key))
+            .copied()
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::tabledump::{PEER_INDEX_TABLE, RIB_AFI, RIBEntry, TABLE_DUMP_V2};
+
+    /// A RIB dump with one peer whose index depends on `padding` extra peers.
+    fn dump(padding: usize, routes: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut peer_entries =
+            vec![PeerEntry::new(9, "198.51.100.9".parse().unwrap(), 64999); padding];
+        peer_entries.push(PeerEntry::new(1, "192.0.2.1".parse().unwrap(), 65001));
+        let table = PEER_INDEX_TABLE {
+            collector_id: 0,
+            view_name: String::new(),
+            peer_entries,
+        };
+
+        let mut out = Vec::new();
+        let record = Record::TABLE_DUMP_V2(TABLE_DUMP_V2::PEER_INDEX_TABLE(table));
+        crate::write(&mut out, 0, 0, &record).unwrap();
+        for (sequence_number, (prefix, attributes)) in routes.iter().enumerate() {
+            let prefix: Prefix = prefix.parse().unwrap();
+            let rib = RIB_AFI {
+                sequence_number: sequence_number as u32,
+                afi: crate::AFI::IPV4,
+                prefix_length: prefix.prefix_length(),
+                prefix: prefix.wire_bytes(),
+                entries: vec![RIBEntry {
+                    peer_index: padding as u16,
+                    originated_time: 0,
+                    attributes: attributes.to_vec(),
+                }],
+            };
+            let record = Record::TABLE_DUMP_V2(TABLE_DUMP_V2::RIB_IPV4_UNICAST(rib));
+            crate::write(&mut out, 0, 0, &record).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_diff() {
+        let origin_igp: &[u8] = &[0x40, 0x01, 0x01, 0x00];
+        let origin_egp: &[u8] = &[0x40, 0x01, 0x01, 0x01];
+        let old = dump(
+            0,
+            &[("10.0.0.0/8", origin_igp), ("10.1.0.0/16", origin_igp)],
+        );
+        let new = dump(
+            0,
+            &[("10.1.0.0/16", origin_egp), ("192.0.2.0/24", origin_igp)],
+        );
+
+        let old = RibSnapshot::from_reader(old.as_slice()).unwrap();
+        let new = RibSnapshot::from_reader(new.as_slice()).unwrap();
+        assert_eq!(old.len(), 2);
+
+        let key = |peer_index: u16, prefix: &str| RouteKey {
+            peer_index,
+            prefix: prefix.parse().unwrap(),
+            path_id: None,
+        };
+        assert_eq!(new.get(&key(0, "10.1.0.0/16")), Some(origin_egp));
+        assert_eq!(new.peer(0).unwrap().peer_as, 65001);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, [key(0, "192.0.2.0/24")]);
+        assert_eq!(diff.removed, [key(0, "10.0.0.0/8")]);
+        assert_eq!(diff.changed, [key(0, "10.1.0.0/16")]);
+
+        assert_eq!(new.diff(&new), RibDiff::default());
+
+        // Same peer at a different index
+        let renumbered = dump(2, &[("10.1.0.0/16", origin_egp)]);
+        let renumbered = RibSnapshot::from_reader(renumbered.as_slice()).unwrap();
+        let diff = new.diff(&renumbered);
+        assert_eq!(diff.added, [key(2, "10.1.0.0/16")]);
+        assert_eq!(
+            diff.removed,
+            [key(0, "10.1.0.0/16"), key(0, "192.0.2.0/24")]
+        );
+    }
+
+    #[test]
+    fn test_peers_sharing_an_address() {
+        let route = |peer_index, peer_bgp_id| FlatRoute {
+            prefix: "10.0.0.0/8".parse().unwrap(),
+            peer_index,
+            peer: PeerEntry::new(peer_bgp_id, "192.0.2.1".parse().unwrap(), 65001),
+            originated_time: 0,
+            attributes: Vec::new(),
+            path_id: None,
+        };
+        let mut snapshot = RibSnapshot::new();
+        snapshot.insert(route(0, 1));
+        snapshot.insert(route(1, 2));
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.peer(1).unwrap().peer_bgp_id, 2);
+        assert!(snapshot.peer(2).is_none());
+    }
+}
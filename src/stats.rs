@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-record-type counters for profiling and summaries.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{subtype_name, type_name, Header};
+
+/// Collects record counts and payload sizes per `(record_type, sub_type)`.
+///
+/// Only headers are inspected, so it can be fed from [`read`](crate::read),
+/// [`read_header_only`](crate::read_header_only) or any other reader.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use mrt_ingester::Stats;
+///
+/// let mut reader = BufReader::new(File::open("updates.mrt").unwrap());
+/// let mut stats = Stats::new();
+/// while let Ok(Some((header, _record))) = mrt_ingester::read(&mut reader) {
+///     stats.record(&header);
+/// }
+/// for line in stats.report() {
+///     println!("{}", line);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    counters: HashMap<(u16, u16), (u64, u64)>,
+}
+
+impl Stats {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    /// Counts one record and its payload length.
+    pub fn record(&mut self, header: &Header) {
+        let entry = self
+            .counters
+            .entry((header.record_type, header.sub_type))
+            .or_default();
+        entry.0 += 1;
+        entry.1 += header.length as u64;
+    }
+
+    /// Total number of records counted.
+    pub fn total_records(&self) -> u64 {
+        self.counters.values().map(|&(count, _)| count).sum()
+    }
+
+    /// Total payload bytes counted, excluding the MRT headers.
+    pub fn total_bytes(&self) -> u64 {
+        self.counters.values().map(|&(_, bytes)| bytes).sum()
+    }
+
+    /// One line per `(record_type, sub_type)`, most frequent first.
+    ///
+    /// Ties are ordered by record type, then subtype.
+    pub fn report(&self) -> Vec<StatLine> {
+        let mut lines: Vec<StatLine> = self
+            .counters
+            .iter()
+            .map(|(&(record_type, sub_type), &(count, bytes))| StatLine {
+                record_type,
+                sub_type,
+                count,
+                bytes,
+            })
+            .collect();
+        lines.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then((a.record_type, a.sub_type).cmp(&(b.record_type, b.sub_type)))
+        });
+        lines
+    }
+}
+
+/// Counters for one `(record_type, sub_type)` pair, from [`Stats::report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StatLine {
+    /// MRT record type
+    pub record_type: u16,
+    /// MRT record subtype
+    pub sub_type: u16,
+    /// Number of records
+    pub count: u64,
+    /// Sum of the payload lengths (`Header::length`)
+    pub bytes: u64,
+}
+
+impl fmt::Display for StatLine {
+    /// Formats as `TYPE/SUBTYPE (type/sub_type): count records, bytes bytes`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} ({}/{}): {} records, {} bytes",
+            type_name(self.record_type),
+            subtype_name(self.record_type, self.sub_type),
+            self.record_type,
+            self.sub_type,
+            self.count,
+            self.bytes
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(record_type: u16, sub_type: u16, length: u32) -> Header {
+        Header {
+            timestamp: 0,
+            extended: 0,
+            record_type,
+            sub_type,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_stats_report() {
+        let mut stats = Stats::new();
+        stats.record(&header(13, 2, 100));
+        stats.record(&header(16, 4, 50));
+        stats.record(&header(13, 2, 120));
+        stats.record(&header(13, 1, 40));
+
+        assert_eq!(stats.total_records(), 4);
+        assert_eq!(stats.total_bytes(), 310);
+
+        let report = stats.report();
+        assert_eq!(report.len(), 3);
+        assert_eq!((report[0].record_type, report[0].sub_type), (13, 2));
+        assert_eq!((report[0].count, report[0].bytes), (2, 220));
+        // Equal counts fall back to type/subtype order
+        assert_eq!((report[1].record_type, report[1].sub_type), (13, 1));
+        assert_eq!((report[2].record_type, report[2].sub_type), (16, 4));
+
+        assert_eq!(
+            report[0].to_string(),
+            "TABLE_DUMP_V2/RIB_IPV4_UNICAST (13/2): 2 records, 220 bytes"
+        );
+    }
+}